@@ -0,0 +1,38 @@
+/* C interface of the redbin crate, built by the redbin-capi crate as
+ * libredbin_capi. */
+
+#ifndef REDBIN_H
+#define REDBIN_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Bytes allocated by the library, released with redbin_buffer_free. */
+typedef struct RedbinBuffer {
+    uint8_t *data;
+    size_t len;
+} RedbinBuffer;
+
+/* Encodes `len` bytes of UTF-8 Red source text into a Redbin document.
+ * Returns 0 on success, -1 on failure. */
+int redbin_encode(const char *text, size_t len, RedbinBuffer *out);
+
+/* Decodes a Redbin document into UTF-8 Red source text, not NUL-terminated.
+ * Returns 0 on success, -1 on failure. */
+int redbin_decode(const uint8_t *data, size_t len, RedbinBuffer *out);
+
+/* Releases a buffer filled by redbin_encode or redbin_decode. */
+void redbin_buffer_free(RedbinBuffer *buffer);
+
+/* Message of the last failure on the calling thread, or NULL. */
+const char *redbin_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* REDBIN_H */
@@ -0,0 +1,132 @@
+//! C functions for applications embedding Red, declared in `include/redbin.h`.
+//! Built as a C library of its own, so the `redbin` crate stays an `rlib`.
+//!
+//! Red source text is encoded into Redbin and Redbin decoded into Red source
+//! text, so C code doesn't need its own model of Red values. Functions return
+//! 0 on success and -1 on failure, with the reason in `redbin_last_error`.
+
+use redbin::{from_bytes, parse_red, to_bytes, Result, Value};
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, UnwindSafe};
+use std::ptr;
+
+/// Bytes allocated by this library, released with `redbin_buffer_free`.
+#[repr(C)]
+pub struct RedbinBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(message: String) -> c_int {
+    let message = CString::new(message).unwrap_or_else(|e| {
+        let end = e.nul_position();
+        CString::new(&e.into_vec()[..end]).expect("cut at the first NUL")
+    });
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+    -1
+}
+
+/// Runs `f`, handing the bytes it makes over to the caller through `out`.
+/// Panics mustn't unwind into C, so they're reported as failures too.
+unsafe fn finish(f: impl FnOnce() -> Result<Vec<u8>> + UnwindSafe, out: *mut RedbinBuffer) -> c_int {
+    match panic::catch_unwind(f) {
+        Err(_) => set_error("internal error".to_string()),
+        Ok(Err(e)) => set_error(e.to_string()),
+        Ok(Ok(bytes)) => {
+            let len = bytes.len();
+            *out = RedbinBuffer { data: Box::into_raw(bytes.into_boxed_slice()) as *mut u8, len };
+            0
+        }
+    }
+}
+
+/// Encodes `len` bytes of UTF-8 Red source text at `text` into a Redbin
+/// document, loaded as Red's `load` would.
+///
+/// # Safety
+///
+/// `text` must point to `len` readable bytes and `out` to a writable buffer.
+#[no_mangle]
+pub unsafe extern "C" fn redbin_encode(text: *const c_char, len: usize, out: *mut RedbinBuffer) -> c_int {
+    if text.is_null() || out.is_null() {
+        return set_error("null pointer passed to redbin_encode".to_string());
+    }
+    let text = match std::str::from_utf8(std::slice::from_raw_parts(text as *const u8, len)) {
+        Ok(text) => text,
+        Err(e) => return set_error(format!("invalid UTF-8 in Red source: {}", e)),
+    };
+    finish(|| parse_red(text).and_then(|value| Ok(to_bytes(&value)?)), out)
+}
+
+/// Decodes the Redbin document of `len` bytes at `data` into UTF-8 Red
+/// source text, without a terminating NUL.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` to a writable buffer.
+#[no_mangle]
+pub unsafe extern "C" fn redbin_decode(data: *const u8, len: usize, out: *mut RedbinBuffer) -> c_int {
+    if data.is_null() || out.is_null() {
+        return set_error("null pointer passed to redbin_decode".to_string());
+    }
+    let data = std::slice::from_raw_parts(data, len);
+    finish(|| Ok(from_bytes::<Value>(data)?.to_string().into_bytes()), out)
+}
+
+/// Releases a buffer filled by `redbin_encode` or `redbin_decode`, leaving it
+/// empty. Empty buffers are left as they are.
+///
+/// # Safety
+///
+/// `buffer` must be null or point to a buffer filled by this library.
+#[no_mangle]
+pub unsafe extern "C" fn redbin_buffer_free(buffer: *mut RedbinBuffer) {
+    if buffer.is_null() || (*buffer).data.is_null() {
+        return;
+    }
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut((*buffer).data, (*buffer).len)));
+    *buffer = RedbinBuffer { data: ptr::null_mut(), len: 0 };
+}
+
+/// Message of the last failure on this thread, or null. It stays valid until
+/// the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn redbin_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{redbin_buffer_free, redbin_decode, redbin_encode, redbin_last_error, RedbinBuffer};
+    use std::ffi::CStr;
+    use std::ptr;
+
+    #[test]
+    fn test_capi() {
+        let text = "[a: 1 \"x\" #{CAFE}]";
+        let mut encoded = RedbinBuffer { data: ptr::null_mut(), len: 0 };
+        let mut decoded = RedbinBuffer { data: ptr::null_mut(), len: 0 };
+        unsafe {
+            assert_eq!(0, redbin_encode(text.as_ptr().cast(), text.len(), &mut encoded));
+            assert_eq!(b"REDBIN", std::slice::from_raw_parts(encoded.data, 6));
+            assert_eq!(0, redbin_decode(encoded.data, encoded.len, &mut decoded));
+            assert_eq!(text.as_bytes(), std::slice::from_raw_parts(decoded.data, decoded.len));
+            redbin_buffer_free(&mut encoded);
+            redbin_buffer_free(&mut decoded);
+            assert!(encoded.data.is_null() && decoded.data.is_null());
+            redbin_buffer_free(&mut decoded);
+
+            assert_eq!(-1, redbin_decode([b'X'; 16].as_ptr(), 16, &mut decoded));
+            assert!(!redbin_last_error().is_null());
+            assert_eq!(-1, redbin_encode("[1".as_ptr().cast(), 2, &mut encoded));
+            assert!(CStr::from_ptr(redbin_last_error()).to_str().unwrap().starts_with("1:"));
+            assert!(encoded.data.is_null());
+        }
+    }
+}
@@ -0,0 +1,979 @@
+//! Field adapters for Red-specific record types.
+//!
+//! Plain serde data model has no notion of `issue!`, `file!`, `vector!` or
+//! `set-word!`, so these types let individual fields opt into them:
+//!
+//! ```
+//! # use serde_derive::{Deserialize, Serialize};
+//! # use std::collections::BTreeMap;
+//! # use std::net::Ipv4Addr;
+//! #[derive(Serialize, Deserialize)]
+//! struct Ticket {
+//!     #[serde(with = "redbin::adapters::Issue")]
+//!     id: String,
+//!     #[serde(with = "redbin::adapters::File")]
+//!     attachment: String,
+//!     #[serde(with = "redbin::adapters::Vector")]
+//!     samples: Vec<f64>,
+//!     #[serde(with = "redbin::adapters::SetWordKeys")]
+//!     meta: BTreeMap<String, i32>,
+//!     #[serde(with = "redbin::adapters::Ip")]
+//!     host: Ipv4Addr,
+//! }
+//! ```
+//!
+//! Each adapter wraps the value in a newtype struct with a reserved name that
+//! the Redbin `Serializer` and `Deserializer` recognize. Other formats see the
+//! plain inner value, except for `Vector`, `Ip`, `Socket`, `Time`, `Date`,
+//! `RawRecord` and `ArrowArray`, whose payloads are pre-encoded.
+
+use serde::de::value::{
+    BorrowedStrDeserializer, MapAccessDeserializer, SeqAccessDeserializer, StrDeserializer,
+    StringDeserializer,
+};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeTuple, Serializer};
+use serde_bytes::Bytes;
+use crate::error::SerError;
+use crate::types;
+use std::cmp::Ordering;
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+mod date;
+pub use date::{Date, DateValue};
+mod raw;
+pub use raw::RawRecord;
+mod os;
+pub use os::{CText, OsLossy, OsText};
+mod series;
+pub use series::{RedSeries, RedString};
+mod rc;
+pub use rc::{Shared, SharedSeries};
+#[cfg(feature = "de")]
+pub(crate) use rc::{with_key as with_shared_key, with_series as with_shared_series};
+#[cfg(feature = "num-bigint")]
+mod bignum;
+#[cfg(feature = "num-bigint")]
+pub use bignum::{Bignum, BignumValue};
+#[cfg(feature = "bytes")]
+mod shared;
+#[cfg(feature = "bytes")]
+pub use shared::SharedBytes;
+#[cfg(all(feature = "bytes", feature = "de"))]
+pub(crate) use shared::with_source as with_shared_source;
+#[cfg(feature = "arrow")]
+mod arrow;
+#[cfg(feature = "arrow")]
+pub use arrow::{from_arrow, to_arrow, ArrowArray};
+#[cfg(any(feature = "glam", feature = "nalgebra"))]
+mod geometry;
+#[cfg(any(feature = "glam", feature = "nalgebra"))]
+pub use geometry::{Geometry, GeometryValue};
+
+pub(crate) const ISSUE: &str = "$redbin::private::Issue";
+pub(crate) const FILE: &str = "$redbin::private::File";
+pub(crate) const VECTOR: &str = "$redbin::private::Vector";
+pub(crate) const SET_WORD_KEYS: &str = "$redbin::private::SetWordKeys";
+pub(crate) const IP: &str = "$redbin::private::Ip";
+#[cfg_attr(not(any(feature = "ser", feature = "de")), allow(dead_code))]
+pub(crate) const GEOMETRY: &str = "$redbin::private::Geometry";
+pub(crate) const TIME: &str = "$redbin::private::Time";
+pub(crate) const DATE: &str = "$redbin::private::Date";
+pub(crate) const WORD: &str = "$redbin::private::Word";
+pub(crate) const POSITIONAL: &str = "$redbin::private::Positional";
+pub(crate) const RAW_CHUNKS: &str = "$redbin::private::RawChunks";
+pub(crate) const VALUE: &str = "$redbin::private::Value";
+pub(crate) const SERIES: &str = "$redbin::private::Series";
+pub(crate) const RED_STRING: &str = "$redbin::private::RedString";
+pub(crate) const UNIT_1: &str = "$redbin::private::Unit1";
+pub(crate) const UNIT_2: &str = "$redbin::private::Unit2";
+pub(crate) const UNIT_4: &str = "$redbin::private::Unit4";
+pub(crate) const UTF16: &str = "$redbin::private::Utf16";
+pub(crate) const OR_DEFAULT: &str = "$redbin::private::OrDefault";
+pub(crate) const SHARED: &str = "$redbin::private::Shared";
+pub(crate) const RAW_RECORD: &str = "$redbin::private::RawRecord";
+
+/// A `word!`, e.g. `foo`, usable wherever a plain string would be, for
+/// example as a map key: `HashMap<Word, i32>` maps `[foo 1 bar 2]`, and
+/// reads `map!`s like `#(foo: 1 bar: 2)` as well.
+///
+/// Any word-like record (`set-word!`, `lit-word!`, `issue!`, ...) reads as a
+/// `Word`, so `SetWordKeys` maps can have `Word` keys too; these are written
+/// back as `set-word!`s.
+///
+/// Words compare and hash case-insensitively, like in Red, but keep the
+/// spelling they were read with: `Foo` and `foo` are equal map keys, and a
+/// `Word` is written back as it was spelled.
+#[derive(Clone, Debug, Default)]
+pub struct Word(pub String);
+
+impl PartialEq for Word {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Word {}
+
+impl Ord for Word {
+    fn cmp(&self, other: &Self) -> Ordering {
+        crate::value::cmp_words(&self.0, &other.0)
+    }
+}
+
+impl PartialOrd for Word {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for Word {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        crate::value::hash_word(&self.0, state);
+    }
+}
+
+impl Serialize for Word {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(WORD, &self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Word {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(WORD, Passthrough(PhantomData)).map(Word)
+    }
+}
+
+/// (De)serializes a string as an `issue!` record, e.g. `#abc-123`.
+pub struct Issue;
+
+impl Issue {
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(ISSUE, value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(ISSUE, Passthrough(PhantomData))
+    }
+}
+
+/// (De)serializes a string as a `file!` record, e.g. `%docs/readme.txt`.
+pub struct File;
+
+impl File {
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(FILE, value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(FILE, Passthrough(PhantomData))
+    }
+}
+
+/// (De)serializes a slice of numbers as a packed `vector!` record instead of
+/// a block of separate `integer!`/`float!` records.
+pub struct Vector;
+
+impl Vector {
+    pub fn serialize<T, S>(value: &[T], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: VectorElement,
+        S: Serializer,
+    {
+        let record = vector_record(value).map_err(ser::Error::custom)?;
+        serializer.serialize_newtype_struct(VECTOR, Bytes::new(&record))
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(VECTOR, Passthrough(PhantomData))
+    }
+}
+
+pub(crate) fn vector_record<T>(values: &[T]) -> Result<Vec<u8>, SerError>
+where
+    T: VectorElement,
+{
+    let length = i32::try_from(values.len()).map_err(|_| SerError::TooLarge)?;
+    let mut record = Vec::from(types::VECTOR.to_le_bytes());
+    record[1] = T::UNIT as u8;
+    record.extend_from_slice(&[0x00; 4]); // head position
+    record.extend_from_slice(&length.to_le_bytes());
+    record.extend_from_slice(&T::TYPE_ID.to_le_bytes());
+    for v in values {
+        v.write_le(&mut record);
+    }
+    record.resize(record.len().next_multiple_of(4), 0x00);
+    Ok(record)
+}
+
+/// Numbers that can be stored in a `vector!` record.
+pub trait VectorElement: Copy {
+    /// Datatype of the elements, `integer!` or `float!`.
+    const TYPE_ID: i32;
+    /// Size of one element in bytes.
+    const UNIT: usize;
+
+    fn write_le(self, output: &mut Vec<u8>);
+}
+
+macro_rules! vector_element {
+    ($t:ty, $type_id:expr) => {
+        impl VectorElement for $t {
+            const TYPE_ID: i32 = $type_id;
+            const UNIT: usize = std::mem::size_of::<$t>();
+
+            fn write_le(self, output: &mut Vec<u8>) {
+                output.extend_from_slice(&self.to_le_bytes());
+            }
+        }
+    };
+}
+
+vector_element!(u8, types::INTEGER);
+vector_element!(i16, types::INTEGER);
+vector_element!(i32, types::INTEGER);
+vector_element!(f32, types::FLOAT);
+vector_element!(f64, types::FLOAT);
+
+/// (De)serializes a map as a block of `set-word!`/value pairs, e.g.
+/// `[a: 1 b: "x"]`, like a struct, instead of a `map!` of string keys.
+pub struct SetWordKeys;
+
+impl SetWordKeys {
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(SET_WORD_KEYS, value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(SET_WORD_KEYS, Passthrough(PhantomData))
+    }
+}
+
+/// (De)serializes a struct as a block of bare values in field order, e.g.
+/// `[12.5 "sdf"]`, instead of key/value pairs. Applies to the struct held
+/// directly by the field, not to structs nested in it.
+///
+/// A shorter block is fine when the fields it leaves out have
+/// `#[serde(default)]`.
+pub struct Positional;
+
+impl Positional {
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(POSITIONAL, value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(POSITIONAL, Passthrough(PhantomData))
+    }
+}
+
+/// (De)serializes an IP address the way Red networking code expects it:
+/// IPv4 as a `tuple!` (`192.168.0.1`), IPv6 as a 16-byte `binary!`.
+pub struct Ip;
+
+impl Ip {
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: IpAddress,
+        S: Serializer,
+    {
+        let record = match value.to_ip() {
+            IpAddr::V4(ip) => {
+                let mut record = Vec::from(types::TUPLE.to_le_bytes());
+                record[1] = 4; // tuple size
+                record.extend_from_slice(&ip.octets());
+                record.resize(16, 0x00);
+                record
+            }
+            IpAddr::V6(ip) => {
+                let mut record = Vec::from(types::BYTES.to_le_bytes());
+                record[1] = 0x01; // unit
+                record.extend_from_slice(&[0x00; 4]); // head position
+                record.extend_from_slice(&16_i32.to_le_bytes());
+                record.extend_from_slice(&ip.octets());
+                record
+            }
+        };
+        serializer.serialize_newtype_struct(IP, Bytes::new(&record))
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: IpAddress,
+        D: Deserializer<'de>,
+    {
+        let ip = deserializer.deserialize_newtype_struct(IP, IpVisitor)?;
+        T::from_ip(ip).ok_or_else(|| de::Error::custom(format!("unexpected address {}", ip)))
+    }
+}
+
+/// (De)serializes a socket address as a `[tuple! integer!]` block, e.g.
+/// `[127.0.0.1 8080]`.
+pub struct Socket;
+
+impl Socket {
+    pub fn serialize<S>(value: &std::net::SocketAddr, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut block = serializer.serialize_tuple(2)?;
+        block.serialize_element(&IpRecord(value.ip()))?;
+        block.serialize_element(&value.port())?;
+        block.end()
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<std::net::SocketAddr, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (IpRecord(ip), port) = Deserialize::deserialize(deserializer)?;
+        Ok(std::net::SocketAddr::new(ip, port))
+    }
+}
+
+/// Addresses accepted by the `Ip` adapter.
+pub trait IpAddress: Sized {
+    fn to_ip(&self) -> IpAddr;
+    fn from_ip(ip: IpAddr) -> Option<Self>;
+}
+
+impl IpAddress for IpAddr {
+    fn to_ip(&self) -> IpAddr {
+        *self
+    }
+
+    fn from_ip(ip: IpAddr) -> Option<Self> {
+        Some(ip)
+    }
+}
+
+impl IpAddress for Ipv4Addr {
+    fn to_ip(&self) -> IpAddr {
+        IpAddr::V4(*self)
+    }
+
+    fn from_ip(ip: IpAddr) -> Option<Self> {
+        match ip {
+            IpAddr::V4(ip) => Some(ip),
+            IpAddr::V6(ip) => ip.to_ipv4_mapped(),
+        }
+    }
+}
+
+impl IpAddress for Ipv6Addr {
+    fn to_ip(&self) -> IpAddr {
+        IpAddr::V6(*self)
+    }
+
+    fn from_ip(ip: IpAddr) -> Option<Self> {
+        match ip {
+            IpAddr::V4(ip) => Some(ip.to_ipv6_mapped()),
+            IpAddr::V6(ip) => Some(ip),
+        }
+    }
+}
+
+struct IpRecord(IpAddr);
+
+impl Serialize for IpRecord {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Ip::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for IpRecord {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ip::deserialize(deserializer).map(IpRecord)
+    }
+}
+
+struct IpVisitor;
+
+impl<'de> Visitor<'de> for IpVisitor {
+    type Value = IpAddr;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a 4-element tuple! or a 16-byte binary!")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<IpAddr, E>
+    where
+        E: de::Error,
+    {
+        match v.len() {
+            4 => Ok(IpAddr::from(TryInto::<[u8; 4]>::try_into(v).unwrap())),
+            16 => Ok(IpAddr::from(TryInto::<[u8; 16]>::try_into(v).unwrap())),
+            n => Err(de::Error::invalid_length(n, &self)),
+        }
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<IpAddr, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        IpAddr::deserialize(deserializer)
+    }
+}
+
+/// (De)serializes a `Duration` as a `time!` record, e.g. `1:02:03.25`.
+///
+/// `time!` holds seconds in a 64-bit float, so nanoseconds survive for
+/// durations up to about 100 days and microseconds for a few centuries.
+pub struct Time;
+
+impl Time {
+    pub fn serialize<S>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let record = crate::types::float_record(types::TIME, value.as_secs_f64());
+        serializer.serialize_newtype_struct(TIME, Bytes::new(&record))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(TIME, TimeVisitor)
+    }
+}
+
+struct TimeVisitor;
+
+impl<'de> Visitor<'de> for TimeVisitor {
+    type Value = Duration;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a non-negative time!")
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Duration, E>
+    where
+        E: de::Error,
+    {
+        Duration::try_from_secs_f64(v).map_err(|_| de::Error::invalid_value(de::Unexpected::Float(v), &self))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Duration::deserialize(deserializer)
+    }
+}
+
+/// (De)serializes a sequence of chars as a single `string!` record, e.g.
+/// `"abc"`, instead of a block of `char!` records. Other formats see a string
+/// too.
+pub struct Chars;
+
+impl Chars {
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + AsRef<[char]>,
+        S: Serializer,
+    {
+        serializer.serialize_str(&value.as_ref().iter().collect::<String>())
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: std::iter::FromIterator<char>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_string(CharsVisitor(PhantomData))
+    }
+}
+
+struct CharsVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for CharsVisitor<T>
+where
+    T: std::iter::FromIterator<char>,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string! record")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        Ok(v.chars().collect())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<T, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        std::iter::from_fn(|| seq.next_element().transpose()).collect::<Result<Vec<char>, _>>()
+            .map(|chars| chars.into_iter().collect())
+    }
+}
+
+/// (De)serializes UTF-16 code units, e.g. for Windows APIs, as a `string!`
+/// record. UCS-2 strings are read straight from the record, without going
+/// through UTF-8. Other formats see a string.
+pub struct Utf16;
+
+impl Utf16 {
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + AsRef<[u16]>,
+        S: Serializer,
+    {
+        let text = String::from_utf16(value.as_ref()).map_err(ser::Error::custom)?;
+        serializer.serialize_str(&text)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u16>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(UTF16, Utf16Visitor)
+    }
+}
+
+struct Utf16Visitor;
+
+impl<'de> Visitor<'de> for Utf16Visitor {
+    type Value = Vec<u16>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string! record")
+    }
+
+    // little endian code units, from Redbin
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Vec<u16>, E>
+    where
+        E: de::Error,
+    {
+        Ok(v.chunks_exact(2).map(|unit| u16::from_le_bytes([unit[0], unit[1]])).collect())
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Vec<u16>, E>
+    where
+        E: de::Error,
+    {
+        Ok(v.encode_utf16().collect())
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Vec<u16>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_string(self)
+    }
+}
+
+/// Reads a field leniently: a record that doesn't decode as `T` gives
+/// `T::default()` instead of failing the whole document, and is reported in
+/// the deserializer's warnings, see `from_bytes_with_warnings`. Writes the
+/// field as it is. Other formats read the field as usual.
+pub struct OrDefault;
+
+impl OrDefault {
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        value.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de> + Default,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(OR_DEFAULT, OrDefaultVisitor(PhantomData))
+    }
+}
+
+struct OrDefaultVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for OrDefaultVisitor<T>
+where
+    T: Deserialize<'de> + Default,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any record")
+    }
+
+    // the record, or nothing if it failed to decode, from Redbin
+    fn visit_seq<A>(self, mut seq: A) -> Result<T, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        Ok(seq.next_element()?.unwrap_or_default())
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+// Hands whatever the deserializer produced for an adapter's newtype over to
+// the field's own `Deserialize` impl.
+struct Passthrough<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for Passthrough<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a Red-specific record")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        T::deserialize(StrDeserializer::new(v))
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        T::deserialize(BorrowedStrDeserializer::new(v))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        T::deserialize(StringDeserializer::new(v))
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<T, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        T::deserialize(SeqAccessDeserializer::new(seq))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<T, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        T::deserialize(MapAccessDeserializer::new(map))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use super::{Positional, SetWordKeys, Word};
+    use crate::{from_bytes, from_bytes_with, parse_red, to_bytes, to_bytes_with, DeError, DeserializerOptions, SerializerOptions, Value};
+    use serde_derive::{Deserialize, Serialize};
+    use std::collections::{BTreeMap, HashMap};
+
+    #[test]
+    fn test_adapters() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Ticket {
+            #[serde(with = "super::Issue")]
+            id: String,
+            #[serde(with = "super::File")]
+            attachment: String,
+            #[serde(with = "super::Vector")]
+            samples: Vec<i16>,
+            #[serde(with = "super::SetWordKeys")]
+            meta: BTreeMap<String, i32>,
+        }
+        let mut meta = BTreeMap::new();
+        meta.insert(String::from("a"), 1);
+        let ticket = Ticket {
+            id: String::from("abc"),
+            attachment: String::from("x.txt"),
+            samples: vec![1, -2, 3],
+            meta,
+        };
+
+        let bytes = to_bytes(&ticket).unwrap();
+        // flags: symbol table
+        assert_eq!(bytes[7], 0x04);
+        // symbols: id abc attachment samples meta a
+        assert_eq!(&bytes[16..88],
+            &[0x06, 0x00, 0x00, 0x00, 0x28, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00,
+            0x07, 0x00, 0x00, 0x00, 0x12, 0x00, 0x00, 0x00, 0x1A, 0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00,
+            0x69, 0x64, 0x00, 0x61, 0x62, 0x63, 0x00, 0x61, 0x74, 0x74, 0x61, 0x63, 0x68, 0x6D, 0x65, 0x6E,
+            0x74, 0x00, 0x73, 0x61, 0x6D, 0x70, 0x6C, 0x65, 0x73, 0x00, 0x6D, 0x65, 0x74, 0x61, 0x00, 0x61,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(ticket, from_bytes(&bytes).unwrap());
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Samples(#[serde(with = "super::Vector")] Vec<f64>);
+        let samples = Samples(vec![1.5, -0.25]);
+        assert_eq!(samples, from_bytes(&to_bytes(&samples).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_time() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Elapsed(#[serde(with = "super::Time")] std::time::Duration);
+        let elapsed = Elapsed(std::time::Duration::new(3723, 250_000_000));
+
+        // rust-redbin-helper 1:02:03.25
+        let bytes = to_bytes(&elapsed).unwrap();
+        assert_eq!(bytes,
+            &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x0C, 0x00, 0x00, 0x00,
+            0x2B, 0x00, 0x00, 0x00, 0x80, 0x16, 0xAD, 0x40, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(elapsed, from_bytes(&bytes).unwrap());
+
+        let precise = Elapsed(std::time::Duration::new(86_399, 123_456_789));
+        assert_eq!(precise, from_bytes(&to_bytes(&precise).unwrap()).unwrap());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_chars() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Letters(#[serde(with = "super::Chars")] Vec<char>);
+        let letters = Letters(vec!['ż', 'ó', 'w']);
+
+        // rust-redbin-helper "żów"
+        let bytes = to_bytes(&letters).unwrap();
+        assert_eq!(&bytes[16..], &[0x07, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00,
+            0x7C, 0x01, 0x00, 0x00, 0xF3, 0x00, 0x00, 0x00, 0x77, 0x00, 0x00, 0x00]);
+        assert_eq!(letters, from_bytes(&bytes).unwrap());
+        assert_eq!("żów", from_bytes::<String>(&bytes).unwrap());
+
+        #[derive(Serialize)]
+        struct Borrowed<'a>(#[serde(with = "super::Chars")] &'a [char]);
+        assert_eq!(to_bytes(&"ab").unwrap(), to_bytes(&Borrowed(&['a', 'b'])).unwrap());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_utf16() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Wide(#[serde(with = "super::Utf16")] Vec<u16>);
+
+        // UCS-2 "żółw", with its code units read as they are
+        let bytes = to_bytes(&crate::adapters::RedString { unit: 2, head: 0, content: "żółw".to_string() }).unwrap();
+        assert_eq!(Wide("żółw".encode_utf16().collect()), from_bytes(&bytes).unwrap());
+
+        for text in ["abc", "a😀", ""] {
+            let wide = Wide(text.encode_utf16().collect());
+            let bytes = to_bytes(&wide).unwrap();
+            assert_eq!(text, from_bytes::<String>(&bytes).unwrap());
+            assert_eq!(wide, from_bytes(&bytes).unwrap());
+        }
+        assert!(to_bytes(&Wide(vec![0xD800])).is_err());
+    }
+
+    #[test]
+    fn test_or_default() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Loose {
+            #[serde(with = "super::OrDefault")]
+            count: u8,
+            #[serde(with = "super::OrDefault")]
+            tags: Vec<String>,
+            name: String,
+        }
+        let loose = Loose { count: 1, tags: vec!["a".to_string()], name: "x".to_string() };
+        assert_eq!(loose, from_bytes(&to_bytes(&loose).unwrap()).unwrap());
+
+        let bytes = to_bytes(&crate::parse_red(r#"["count" 300 "tags" ["a" 2] "name" "x"]"#).unwrap()).unwrap();
+        let (value, warnings) = crate::from_bytes_with_warnings::<Loose>(&bytes, DeserializerOptions::new()).unwrap();
+        assert_eq!(Loose { count: 0, tags: vec![], name: "x".to_string() }, value);
+        assert_eq!(vec![48, 72], warnings.iter().map(|w| w.offset).collect::<Vec<_>>());
+        assert_eq!("at byte 48: replaced by default: u8 limit exceeded", warnings[0].to_string());
+
+        let bytes = to_bytes(&crate::parse_red(r#"["count" 1 "tags" [] "name" 2]"#).unwrap()).unwrap();
+        assert!(from_bytes::<Loose>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_ip() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Host(#[serde(with = "super::Ip")] std::net::Ipv4Addr);
+        let host = Host(std::net::Ipv4Addr::new(192, 168, 0, 1));
+
+        // rust-redbin-helper 192.168.0.1
+        let bytes = to_bytes(&host).unwrap();
+        assert_eq!(bytes,
+            &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00,
+            0x27, 0x04, 0x00, 0x00, 0xC0, 0xA8, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(host, from_bytes(&bytes).unwrap());
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Peer {
+            #[serde(with = "super::Ip")]
+            ip: std::net::IpAddr,
+            #[serde(with = "super::Socket")]
+            socket: std::net::SocketAddr,
+        }
+        let peer = Peer {
+            ip: "::1".parse().unwrap(),
+            socket: "127.0.0.1:8080".parse().unwrap(),
+        };
+        assert_eq!(peer, from_bytes(&to_bytes(&peer).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_word() {
+        let map = BTreeMap::from([(Word(String::from("foo")), 1), (Word(String::from("bar")), 2)]);
+
+        // rust-redbin-helper #(bar 2 foo 1)
+        let expected = &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x04, 0x01, 0x00, 0x00, 0x00, 0x38, 0x00, 0x00, 0x00,
+            0x02, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
+            0x62, 0x61, 0x72, 0x00, 0x66, 0x6F, 0x6F, 0x00,
+            0x28, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
+                0x0F, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                0x0B, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+                0x0F, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                0x0B, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00];
+        assert_eq!(&to_bytes(&map).unwrap()[..], expected);
+        assert_eq!(map, from_bytes::<BTreeMap<Word, i32>>(expected).unwrap());
+
+        // `set-word!` keys read as words and are written back as such
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Meta {
+            #[serde(with = "SetWordKeys")]
+            meta: BTreeMap<Word, i32>,
+        }
+        let meta = Meta { meta: map };
+        let bytes = to_bytes(&meta).unwrap();
+        assert_eq!(meta, from_bytes::<Meta>(&bytes).unwrap());
+        assert_eq!(BTreeMap::from([(String::from("bar"), 2), (String::from("foo"), 1)]),
+            from_bytes::<BTreeMap<String, BTreeMap<String, i32>>>(&bytes).unwrap()["meta"]);
+
+        // compared regardless of case, written as spelled
+        assert_eq!(Word(String::from("Foo")), Word(String::from("fOO")));
+        let word: Word = from_bytes(&to_bytes(&Word(String::from("Foo"))).unwrap()).unwrap();
+        assert_eq!("Foo", word.0);
+    }
+
+    #[test]
+    fn test_word_keys() {
+        let expected = HashMap::from([
+            (Word(String::from("a")), Value::Integer(1)),
+            (Word(String::from("b")), Value::Block(vec![Value::Word(String::from("x"))])),
+        ]);
+        for source in ["[a 1 b [x]]", "[a: 1 b: [x]]", "#(a: 1 B: [x])"] {
+            let bytes = to_bytes(&parse_red(source).unwrap()).unwrap();
+            assert_eq!(expected, from_bytes::<HashMap<Word, Value>>(&bytes).unwrap(), "{}", source);
+        }
+
+        // keys are written as words, not strings
+        let map = HashMap::from([(Word(String::from("a")), Value::Integer(1))]);
+        let value: Value = from_bytes(&to_bytes(&map).unwrap()).unwrap();
+        assert_eq!(parse_red("#(a 1)").unwrap(), value);
+        let bytes = to_bytes(&parse_red(r#"["a" 1]"#).unwrap()).unwrap();
+        assert_eq!(Err(DeError::ExpectedWord), from_bytes::<HashMap<Word, Value>>(&bytes));
+    }
+
+    #[test]
+    fn test_positional() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Point {
+            x: f64,
+            label: String,
+        }
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Shape {
+            #[serde(with = "Positional")]
+            origin: Point,
+            #[serde(with = "Positional")]
+            anchor: Option<Point>,
+            center: Point,
+        }
+
+        // rust-redbin-helper [12.5 "sdf"]
+        let expected = &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x28, 0x00, 0x00, 0x00,
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+                0x0C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x29, 0x40, 0x00, 0x00, 0x00, 0x00,
+                0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x73, 0x64, 0x66, 0x00];
+        let point = Point { x: 12.5, label: String::from("sdf") };
+        let bytes = to_bytes_with(&point, SerializerOptions::new().positional_structs(true)).unwrap();
+        assert_eq!(&bytes[..], expected);
+        assert_eq!(point, from_bytes_with::<Point>(expected, DeserializerOptions::new().positional_structs(true)).unwrap());
+        assert!(from_bytes::<Point>(expected).is_err());
+
+        let shape = Shape {
+            origin: Point { x: 1.0, label: String::from("a") },
+            anchor: None,
+            center: Point { x: 2.0, label: String::from("b") },
+        };
+        let bytes = to_bytes(&shape).unwrap();
+        assert_eq!(shape, from_bytes::<Shape>(&bytes).unwrap());
+        #[derive(Deserialize)]
+        struct Raw {
+            origin: (f64, String),
+        }
+        assert_eq!((1.0, String::from("a")), from_bytes::<Raw>(&bytes).unwrap().origin);
+    }
+}
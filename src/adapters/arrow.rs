@@ -0,0 +1,338 @@
+use super::{vector_record, VectorElement, VECTOR};
+use crate::error::{DeError, SerError};
+use crate::value::Value;
+use arrow_array::types::{Float32Type, Float64Type, Int16Type, Int32Type, UInt8Type};
+use arrow_array::{
+    Array, ArrayRef, ArrowPrimitiveType, Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array,
+    PrimitiveArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+};
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+use serde_bytes::Bytes;
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::Arc;
+
+/// (De)serializes an Arrow array of numbers as a packed `vector!` record,
+/// or as a block of numbers and `none` if it has nulls or its element type
+/// has no `vector!` counterpart, e.g. `Int8`. Reads either back, element
+/// types of a `vector!` kept; blocks mixing integers and floats read as
+/// `Float64`.
+pub struct ArrowArray;
+
+impl ArrowArray {
+    pub fn serialize<T, S>(array: &T, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Array,
+        S: Serializer,
+    {
+        let any = array.as_any();
+        let record = match () {
+            _ if array.null_count() > 0 => None,
+            _ if any.is::<UInt8Array>() => packed::<UInt8Type>(any),
+            _ if any.is::<Int16Array>() => packed::<Int16Type>(any),
+            _ if any.is::<Int32Array>() => packed::<Int32Type>(any),
+            _ if any.is::<Float32Array>() => packed::<Float32Type>(any),
+            _ if any.is::<Float64Array>() => packed::<Float64Type>(any),
+            _ => None,
+        };
+        match record {
+            Some(record) => {
+                let record = record.map_err(ser::Error::custom)?;
+                serializer.serialize_newtype_struct(VECTOR, Bytes::new(&record))
+            }
+            None => from_arrow(array).map_err(ser::Error::custom)?.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> std::result::Result<ArrayRef, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(VECTOR, ArrowVisitor)
+    }
+}
+
+/// `vector!` record of the values of a primitive array without nulls.
+fn packed<T>(any: &dyn std::any::Any) -> Option<Result<Vec<u8>, SerError>>
+where
+    T: ArrowPrimitiveType,
+    T::Native: VectorElement,
+{
+    any.downcast_ref::<PrimitiveArray<T>>().map(|array| vector_record(array.values()))
+}
+
+/// Array of the numbers in a block, paren or path, `none` being null.
+/// Integers make an `Int32` array, and so do `none`s alone; any float
+/// makes it `Float64`.
+pub fn to_arrow(value: &Value) -> Result<ArrayRef, DeError> {
+    let values = value.as_block().ok_or(DeError::ExpectedBlock)?;
+    let numbers = values.iter().map(|value| match value {
+        Value::None => Ok(None),
+        Value::Integer(i) => Ok(Some(Number::I32(*i))),
+        Value::Float(f) => Ok(Some(Number::F64(*f))),
+        _ => Err(DeError::Message(message!("expected a block of numbers"))),
+    }).collect::<Result<Vec<_>, _>>()?;
+    Ok(array(numbers))
+}
+
+/// Block of the numbers of an Arrow array, nulls being `none`.
+pub fn from_arrow<T>(array: &T) -> Result<Value, SerError>
+where
+    T: ?Sized + Array,
+{
+    fn block<A, N, F>(array: &A, convert: F) -> Result<Value, SerError>
+    where
+        A: Array,
+        for<'a> &'a A: IntoIterator<Item = Option<N>>,
+        F: Fn(N) -> Result<Value, SerError>,
+    {
+        array.into_iter().map(|n| n.map_or(Ok(Value::None), &convert)).collect::<Result<_, _>>().map(Value::Block)
+    }
+    let integer = |n: i64| i32::try_from(n).map(Value::Integer)
+        .map_err(|_| SerError::Message(message!("{} out of integer! range", n)));
+    let any = array.as_any();
+    if let Some(array) = any.downcast_ref::<Int8Array>() {
+        block(array, |n| Ok(Value::Integer(n.into())))
+    } else if let Some(array) = any.downcast_ref::<UInt8Array>() {
+        block(array, |n| Ok(Value::Integer(n.into())))
+    } else if let Some(array) = any.downcast_ref::<Int16Array>() {
+        block(array, |n| Ok(Value::Integer(n.into())))
+    } else if let Some(array) = any.downcast_ref::<UInt16Array>() {
+        block(array, |n| Ok(Value::Integer(n.into())))
+    } else if let Some(array) = any.downcast_ref::<Int32Array>() {
+        block(array, |n| Ok(Value::Integer(n)))
+    } else if let Some(array) = any.downcast_ref::<UInt32Array>() {
+        block(array, |n| integer(n.into()))
+    } else if let Some(array) = any.downcast_ref::<Int64Array>() {
+        block(array, integer)
+    } else if let Some(array) = any.downcast_ref::<UInt64Array>() {
+        block(array, |n| integer(i64::try_from(n).unwrap_or(i64::MAX)))
+    } else if let Some(array) = any.downcast_ref::<Float32Array>() {
+        block(array, |n| Ok(Value::Float(n.into())))
+    } else if let Some(array) = any.downcast_ref::<Float64Array>() {
+        block(array, |n| Ok(Value::Float(n)))
+    } else {
+        Err(SerError::Message(message!("unsupported Arrow data type {}", array.data_type())))
+    }
+}
+
+/// An element of a `vector!` or a block.
+#[derive(Clone, Copy)]
+enum Number {
+    U8(u8),
+    I16(i16),
+    I32(i32),
+    F32(f32),
+    F64(f64),
+}
+
+impl Number {
+    fn as_i32(self) -> Option<i32> {
+        match self {
+            Number::U8(n) => Some(n.into()),
+            Number::I16(n) => Some(n.into()),
+            Number::I32(n) => Some(n),
+            Number::F32(_) | Number::F64(_) => None,
+        }
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::U8(n) => n.into(),
+            Number::I16(n) => n.into(),
+            Number::I32(n) => n.into(),
+            Number::F32(n) => n.into(),
+            Number::F64(n) => n,
+        }
+    }
+}
+
+/// Array of the narrowest type holding all of `numbers`.
+fn array(numbers: Vec<Option<Number>>) -> ArrayRef {
+    let mut kinds = numbers.iter().flatten().map(std::mem::discriminant);
+    let first = kinds.next();
+    let same = kinds.all(|kind| Some(kind) == first);
+    match numbers.iter().flatten().next() {
+        Some(Number::U8(_)) if same => typed(numbers, |n| match n {Number::U8(n) => n, _ => unreachable!()}),
+        Some(Number::I16(_)) if same => typed(numbers, |n| match n {Number::I16(n) => n, _ => unreachable!()}),
+        Some(Number::F32(_)) if same => typed(numbers, |n| match n {Number::F32(n) => n, _ => unreachable!()}),
+        _ if numbers.iter().flatten().all(|n| n.as_i32().is_some()) =>
+            typed::<i32, _>(numbers, |n| n.as_i32().expect("all are integers")),
+        _ => typed(numbers, Number::as_f64),
+    }
+}
+
+fn typed<N, F>(numbers: Vec<Option<Number>>, convert: F) -> ArrayRef
+where
+    N: ArrowNative,
+    F: Fn(Number) -> N,
+{
+    N::array(numbers.into_iter().map(|n| n.map(&convert)).collect())
+}
+
+/// Numbers with an Arrow array type.
+trait ArrowNative: Sized {
+    fn array(values: Vec<Option<Self>>) -> ArrayRef;
+}
+
+macro_rules! arrow_native {
+    ($t:ty, $array:ty) => {
+        impl ArrowNative for $t {
+            fn array(values: Vec<Option<Self>>) -> ArrayRef {
+                Arc::new(<$array>::from(values))
+            }
+        }
+    };
+}
+
+arrow_native!(u8, UInt8Array);
+arrow_native!(i16, Int16Array);
+arrow_native!(i32, Int32Array);
+arrow_native!(f32, Float32Array);
+arrow_native!(f64, Float64Array);
+
+struct ArrowVisitor;
+
+impl<'de> Visitor<'de> for ArrowVisitor {
+    type Value = ArrayRef;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a vector! or a block of numbers")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<ArrayRef, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut numbers = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(Element(number)) = seq.next_element()? {
+            numbers.push(number);
+        }
+        Ok(array(numbers))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> std::result::Result<ArrayRef, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+/// A number, or `none` as null.
+struct Element(Option<Number>);
+
+impl<'de> Deserialize<'de> for Element {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ElementVisitor)
+    }
+}
+
+struct ElementVisitor;
+
+impl<'de> Visitor<'de> for ElementVisitor {
+    type Value = Element;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a number or none")
+    }
+
+    fn visit_u8<E>(self, v: u8) -> std::result::Result<Element, E> {
+        Ok(Element(Some(Number::U8(v))))
+    }
+
+    fn visit_i16<E>(self, v: i16) -> std::result::Result<Element, E> {
+        Ok(Element(Some(Number::I16(v))))
+    }
+
+    fn visit_i32<E>(self, v: i32) -> std::result::Result<Element, E> {
+        Ok(Element(Some(Number::I32(v))))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<Element, E>
+    where
+        E: de::Error,
+    {
+        i32::try_from(v).map(|v| Element(Some(Number::I32(v))))
+            .map_err(|_| E::invalid_value(de::Unexpected::Signed(v), &self))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<Element, E>
+    where
+        E: de::Error,
+    {
+        i32::try_from(v).map(|v| Element(Some(Number::I32(v))))
+            .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(v), &self))
+    }
+
+    fn visit_f32<E>(self, v: f32) -> std::result::Result<Element, E> {
+        Ok(Element(Some(Number::F32(v))))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<Element, E> {
+        Ok(Element(Some(Number::F64(v))))
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<Element, E> {
+        Ok(Element(None))
+    }
+
+    fn visit_none<E>(self) -> std::result::Result<Element, E> {
+        Ok(Element(None))
+    }
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use super::{from_arrow, to_arrow, ArrowArray};
+    use crate::{from_bytes, parse_red, to_bytes, SerError, Value};
+    use arrow_array::{Array, ArrayRef, Float32Array, Float64Array, Int32Array, Int8Array};
+    use serde_derive::{Deserialize, Serialize};
+    use std::sync::Arc;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Dataset {
+        #[serde(with = "ArrowArray")]
+        column: ArrayRef,
+    }
+
+    #[test]
+    fn test_arrow_array() {
+        let floats: ArrayRef = Arc::new(Float32Array::from(vec![1.5, 2.0, -3.25]));
+        let bytes = to_bytes(&Dataset { column: floats.clone() }).unwrap();
+        assert_eq!(0x23, bytes[36 + 12 + 16]); // vector! after the symbol table and `column:`
+        assert_eq!(&floats, &from_bytes::<Dataset>(&bytes).unwrap().column);
+        #[derive(Deserialize)]
+        struct Plain {
+            #[serde(with = "crate::adapters::Vector")]
+            column: Vec<f32>,
+        }
+        assert_eq!(vec![1.5, 2.0, -3.25], from_bytes::<Plain>(&bytes).unwrap().column);
+
+        // nulls make a block
+        let nulls: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), None, Some(3)]));
+        let bytes = to_bytes(&Dataset { column: nulls.clone() }).unwrap();
+        assert_eq!(parse_red("[column: [1 #[none] 3]]").unwrap(), from_bytes::<Value>(&bytes).unwrap());
+        assert_eq!(&nulls, &from_bytes::<Dataset>(&bytes).unwrap().column);
+
+        let narrow: ArrayRef = Arc::new(Int8Array::from(vec![-1, 2]));
+        let column = from_bytes::<Dataset>(&to_bytes(&Dataset { column: narrow }).unwrap()).unwrap().column;
+        assert_eq!(&(Arc::new(Int32Array::from(vec![-1, 2])) as ArrayRef), &column);
+    }
+
+    #[test]
+    fn test_arrow_values() {
+        let block = parse_red("[1 2.5 #[none]]").unwrap();
+        let array = to_arrow(&block).unwrap();
+        assert_eq!(&Float64Array::from(vec![Some(1.0), Some(2.5), None]), array.as_any().downcast_ref::<Float64Array>().unwrap());
+        assert_eq!(block.as_block().unwrap()[1], from_arrow(array.as_ref()).unwrap().as_block().unwrap()[1]);
+        assert_eq!(Value::Block(vec![]), from_arrow(to_arrow(&parse_red("[]").unwrap()).unwrap().as_ref()).unwrap());
+        assert!(to_arrow(&parse_red("[1 \"a\"]").unwrap()).is_err());
+        assert_eq!(SerError::Message(message!("{} out of integer! range", 3_000_000_000i64)),
+            from_arrow(&arrow_array::Int64Array::from(vec![3_000_000_000])).unwrap_err());
+    }
+}
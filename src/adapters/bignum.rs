@@ -0,0 +1,126 @@
+use num_bigint::{BigInt, BigUint, Sign};
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+use std::fmt;
+use std::marker::PhantomData;
+
+const POSITIVE: u8 = 0x00;
+const NEGATIVE: u8 = 0x01;
+
+/// (De)serializes arbitrary-precision integers as a `binary!` record: one
+/// sign byte (`0` for zero or positive, `1` for negative) followed by the
+/// big-endian magnitude, e.g. `#{01FF}` for -255.
+pub struct Bignum;
+
+impl Bignum {
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: BignumValue,
+        S: Serializer,
+    {
+        let (sign, magnitude) = value.to_sign_magnitude();
+        let mut payload = vec![if sign == Sign::Minus {NEGATIVE} else {POSITIVE}];
+        payload.extend_from_slice(&magnitude);
+        serializer.serialize_bytes(&payload)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: BignumValue,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_byte_buf(BignumVisitor(PhantomData))
+    }
+}
+
+/// Integer types accepted by the `Bignum` adapter.
+pub trait BignumValue: Sized {
+    fn to_sign_magnitude(&self) -> (Sign, Vec<u8>);
+    fn from_sign_magnitude(sign: Sign, magnitude: &[u8]) -> Option<Self>;
+}
+
+impl BignumValue for BigInt {
+    fn to_sign_magnitude(&self) -> (Sign, Vec<u8>) {
+        self.to_bytes_be()
+    }
+
+    fn from_sign_magnitude(sign: Sign, magnitude: &[u8]) -> Option<Self> {
+        Some(BigInt::from_bytes_be(sign, magnitude))
+    }
+}
+
+impl BignumValue for BigUint {
+    fn to_sign_magnitude(&self) -> (Sign, Vec<u8>) {
+        (Sign::Plus, self.to_bytes_be())
+    }
+
+    fn from_sign_magnitude(sign: Sign, magnitude: &[u8]) -> Option<Self> {
+        let value = BigUint::from_bytes_be(magnitude);
+        if sign == Sign::Minus && value != BigUint::default() {
+            None
+        } else {
+            Some(value)
+        }
+    }
+}
+
+struct BignumVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for BignumVisitor<T>
+where
+    T: BignumValue,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sign byte followed by a big-endian magnitude")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        let sign = match v.first() {
+            Some(&POSITIVE) => Sign::Plus,
+            Some(&NEGATIVE) => Sign::Minus,
+            _ => return Err(de::Error::invalid_value(de::Unexpected::Bytes(v), &self)),
+        };
+        T::from_sign_magnitude(sign, &v[1..])
+            .ok_or_else(|| de::Error::invalid_value(de::Unexpected::Bytes(v), &"a non-negative number"))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        self.visit_bytes(&v)
+    }
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use crate::{from_bytes, to_bytes};
+    use num_bigint::{BigInt, BigUint};
+    use serde_derive::{Deserialize, Serialize};
+
+    #[test]
+    fn test_bignum() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Big(#[serde(with = "super::Bignum")] BigInt);
+        let big = Big(BigInt::from(-255));
+
+        // rust-redbin-helper #{01FF}
+        let bytes = to_bytes(&big).unwrap();
+        assert_eq!(bytes,
+            &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00,
+            0x29, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0xFF, 0x00, 0x00]);
+        assert_eq!(big, from_bytes(&bytes).unwrap());
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Huge(#[serde(with = "super::Bignum")] BigUint);
+        let huge = Huge(BigUint::from(u128::MAX) * 3u8);
+        assert_eq!(huge, from_bytes(&to_bytes(&huge).unwrap()).unwrap());
+
+        assert!(from_bytes::<Huge>(&bytes).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use super::DATE;
+use crate::types;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::Serializer;
+use serde_bytes::Bytes;
+use std::convert::TryInto;
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+const TIME_FLAG: u32 = 1 << 16;
+const ZONE_SIGN: u32 = 0x40;
+
+/// (De)serializes a point in time as a `date!` record, e.g.
+/// `17-Oct-2026/10:00:00+02:00`.
+///
+/// Like Red, the record holds the UTC date and time of day plus the zone
+/// offset in 15-minute steps, so offsets that aren't a multiple of 15
+/// minutes are truncated. `SystemTime` is always written in UTC.
+pub struct Date;
+
+impl Date {
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: DateValue,
+        S: Serializer,
+    {
+        let (seconds, nanos, zone) = value.to_timestamp();
+        let days = seconds.div_euclid(SECONDS_PER_DAY);
+        let time = seconds.rem_euclid(SECONDS_PER_DAY) as f64 + nanos as f64 / 1e9;
+        let (year, month, day) = civil_from_days(days);
+
+        let quarters = zone / 15;
+        let zone_bits = if quarters < 0 {ZONE_SIGN | (-quarters) as u32} else {quarters as u32};
+        let date = (year as u32) << 17 | TIME_FLAG | month << 12 | day << 7 | (zone_bits & 0x7F);
+
+        let mut record = Vec::from(types::DATE.to_le_bytes());
+        record.extend_from_slice(&date.to_le_bytes());
+        record.extend_from_slice(&crate::types::float_record(types::DATE, time)[4..]);
+        serializer.serialize_newtype_struct(DATE, Bytes::new(&record))
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: DateValue,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(DATE, DateVisitor(PhantomData))
+    }
+}
+
+/// Points in time accepted by the `Date` adapter.
+pub trait DateValue: Sized {
+    /// Seconds and nanoseconds since the Unix epoch, plus the zone offset
+    /// in minutes east of UTC.
+    fn to_timestamp(&self) -> (i64, u32, i32);
+    fn from_timestamp(seconds: i64, nanos: u32, zone: i32) -> Option<Self>;
+}
+
+impl DateValue for SystemTime {
+    fn to_timestamp(&self) -> (i64, u32, i32) {
+        match self.duration_since(UNIX_EPOCH) {
+            Ok(d) => (d.as_secs() as i64, d.subsec_nanos(), 0),
+            Err(e) => {
+                let d = e.duration();
+                if d.subsec_nanos() == 0 {
+                    (-(d.as_secs() as i64), 0, 0)
+                } else {
+                    (-(d.as_secs() as i64) - 1, 1_000_000_000 - d.subsec_nanos(), 0)
+                }
+            }
+        }
+    }
+
+    fn from_timestamp(seconds: i64, nanos: u32, _zone: i32) -> Option<Self> {
+        if seconds >= 0 {
+            UNIX_EPOCH.checked_add(Duration::new(seconds as u64, nanos))
+        } else {
+            UNIX_EPOCH.checked_sub(Duration::from_secs(seconds.unsigned_abs()))?
+                .checked_add(Duration::from_nanos(nanos as u64))
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl DateValue for chrono::DateTime<chrono::FixedOffset> {
+    fn to_timestamp(&self) -> (i64, u32, i32) {
+        (self.timestamp(), self.timestamp_subsec_nanos(), self.offset().local_minus_utc() / 60)
+    }
+
+    fn from_timestamp(seconds: i64, nanos: u32, zone: i32) -> Option<Self> {
+        let offset = chrono::FixedOffset::east_opt(zone * 60)?;
+        chrono::DateTime::from_timestamp(seconds, nanos).map(|d| d.with_timezone(&offset))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl DateValue for chrono::DateTime<chrono::Utc> {
+    fn to_timestamp(&self) -> (i64, u32, i32) {
+        (self.timestamp(), self.timestamp_subsec_nanos(), 0)
+    }
+
+    fn from_timestamp(seconds: i64, nanos: u32, _zone: i32) -> Option<Self> {
+        chrono::DateTime::from_timestamp(seconds, nanos)
+    }
+}
+
+#[cfg(feature = "time")]
+impl DateValue for time::OffsetDateTime {
+    fn to_timestamp(&self) -> (i64, u32, i32) {
+        (self.unix_timestamp(), self.nanosecond(), self.offset().whole_minutes() as i32)
+    }
+
+    fn from_timestamp(seconds: i64, nanos: u32, zone: i32) -> Option<Self> {
+        let offset = time::UtcOffset::from_whole_seconds(zone * 60).ok()?;
+        let utc = time::OffsetDateTime::from_unix_timestamp(seconds).ok()?
+            .replace_nanosecond(nanos).ok()?;
+        Some(utc.to_offset(offset))
+    }
+}
+
+// Days since 1970-01-01 to proleptic Gregorian (year, month, day).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 {mp + 3} else {mp - 9} as u32;
+    let year = yoe + era * 400 + if month <= 2 {1} else {0};
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 {year - 1} else {year};
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+struct DateVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for DateVisitor<T>
+where
+    T: DateValue,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a date!")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        if v.len() != 12 {
+            return Err(de::Error::invalid_length(v.len(), &self));
+        }
+        let date = u32::from_le_bytes(v[0..4].try_into().unwrap());
+        let time_bytes = [&v[8..12], &v[4..8]].concat(); // swap words
+        let time = if date & TIME_FLAG != 0 {f64::from_le_bytes(time_bytes.try_into().unwrap())} else {0.0};
+
+        let zone_bits = date & 0x3F;
+        let zone = if date & ZONE_SIGN != 0 {-(zone_bits as i32)} else {zone_bits as i32} * 15;
+        let days = days_from_civil((date >> 17) as i64, (date >> 12) & 0x0F, (date >> 7) & 0x1F);
+
+        let whole = time.floor();
+        let seconds = days * SECONDS_PER_DAY + whole as i64;
+        let nanos = ((time - whole) * 1e9).round().min(999_999_999.0) as u32;
+        T::from_timestamp(seconds, nanos, zone).ok_or_else(|| de::Error::custom("date! out of range"))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (seconds, nanos, zone) = Deserialize::deserialize(deserializer)?;
+        T::from_timestamp(seconds, nanos, zone).ok_or_else(|| de::Error::custom("date out of range"))
+    }
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use crate::{from_bytes, to_bytes};
+    use serde_derive::{Deserialize, Serialize};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn test_date() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Stamp(#[serde(with = "super::Date")] SystemTime);
+        let stamp = Stamp(UNIX_EPOCH + Duration::new(1_792_231_200, 500_000_000));
+
+        // rust-redbin-helper 17-Oct-2026/10:00:00.5
+        let bytes = to_bytes(&stamp).unwrap();
+        assert_eq!(bytes,
+            &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00,
+            0x2F, 0x00, 0x00, 0x00, 0x80, 0xA8, 0xD5, 0x0F, 0x10, 0x94, 0xE1, 0x40, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(stamp, from_bytes(&bytes).unwrap());
+
+        let old = Stamp(UNIX_EPOCH - Duration::new(86_400 * 365 * 30, 250_000_000));
+        assert_eq!(old, from_bytes(&to_bytes(&old).unwrap()).unwrap());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Stamp(#[serde(with = "super::Date")] chrono::DateTime<chrono::FixedOffset>);
+        let stamp = Stamp(chrono::DateTime::parse_from_rfc3339("2026-10-17T10:00:00.125+02:00").unwrap());
+        let decoded: Stamp = from_bytes(&to_bytes(&stamp).unwrap()).unwrap();
+        assert_eq!(stamp, decoded);
+        assert_eq!(decoded.0.offset().local_minus_utc(), 7200);
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_time() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Stamp(#[serde(with = "super::Date")] time::OffsetDateTime);
+        let stamp = Stamp(time::OffsetDateTime::from_unix_timestamp(1_792_231_200).unwrap()
+            .to_offset(time::UtcOffset::from_hms(-5, -30, 0).unwrap()));
+        let decoded: Stamp = from_bytes(&to_bytes(&stamp).unwrap()).unwrap();
+        assert_eq!(stamp, decoded);
+        assert_eq!(decoded.0.offset(), stamp.0.offset());
+    }
+}
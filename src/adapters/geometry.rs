@@ -0,0 +1,275 @@
+use super::{vector_record, GEOMETRY};
+use crate::error::SerError;
+use crate::types;
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::{self, Serializer};
+use serde_bytes::Bytes;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// (De)serializes vectors and matrices as native Red geometry records:
+/// integer 2D vectors as `pair!`, float 2D/3D vectors as `point2D!` and
+/// `point3D!`, matrices as a float `vector!` in column-major order.
+pub struct Geometry;
+
+impl Geometry {
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: GeometryValue,
+        S: Serializer,
+    {
+        let record = value.to_record().map_err(ser::Error::custom)?;
+        serializer.serialize_newtype_struct(GEOMETRY, Bytes::new(&record))
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: GeometryValue,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(GEOMETRY, GeometryVisitor(PhantomData))
+    }
+}
+
+/// Types accepted by the `Geometry` adapter.
+pub trait GeometryValue: Sized {
+    /// Complete Redbin record for the value, failing if it's too large for
+    /// Redbin.
+    fn to_record(&self) -> Result<Vec<u8>, SerError>;
+    /// Builds the value from its components, `None` if there are too few
+    /// or too many of them.
+    fn from_components(components: &[f64]) -> Option<Self>;
+}
+
+fn pair_record(x: i32, y: i32) -> Vec<u8> {
+    let mut record = Vec::from(types::PAIR.to_le_bytes());
+    record.extend_from_slice(&x.to_le_bytes());
+    record.extend_from_slice(&y.to_le_bytes());
+    record
+}
+
+fn point_record(coordinates: &[f32]) -> Vec<u8> {
+    let t = if coordinates.len() == 2 {types::POINT2D} else {types::POINT3D};
+    let mut record = Vec::from(t.to_le_bytes());
+    for c in coordinates {
+        record.extend_from_slice(&c.to_le_bytes());
+    }
+    record
+}
+
+#[cfg(feature = "glam")]
+mod glam_impls {
+    use super::*;
+    use glam::{IVec2, Mat4, Vec2, Vec3};
+
+    impl GeometryValue for IVec2 {
+        fn to_record(&self) -> Result<Vec<u8>, SerError> {
+            Ok(pair_record(self.x, self.y))
+        }
+
+        fn from_components(components: &[f64]) -> Option<Self> {
+            match components {
+                &[x, y] => Some(IVec2::new(x as i32, y as i32)),
+                _ => None,
+            }
+        }
+    }
+
+    impl GeometryValue for Vec2 {
+        fn to_record(&self) -> Result<Vec<u8>, SerError> {
+            Ok(point_record(&self.to_array()))
+        }
+
+        fn from_components(components: &[f64]) -> Option<Self> {
+            match components {
+                &[x, y] => Some(Vec2::new(x as f32, y as f32)),
+                _ => None,
+            }
+        }
+    }
+
+    impl GeometryValue for Vec3 {
+        fn to_record(&self) -> Result<Vec<u8>, SerError> {
+            Ok(point_record(&self.to_array()))
+        }
+
+        fn from_components(components: &[f64]) -> Option<Self> {
+            match components {
+                &[x, y, z] => Some(Vec3::new(x as f32, y as f32, z as f32)),
+                _ => None,
+            }
+        }
+    }
+
+    impl GeometryValue for Mat4 {
+        fn to_record(&self) -> Result<Vec<u8>, SerError> {
+            vector_record(&self.to_cols_array())
+        }
+
+        fn from_components(components: &[f64]) -> Option<Self> {
+            if components.len() != 16 {
+                return None;
+            }
+            let mut cols = [0.0_f32; 16];
+            for (c, v) in cols.iter_mut().zip(components) {
+                *c = *v as f32;
+            }
+            Some(Mat4::from_cols_array(&cols))
+        }
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra_impls {
+    use super::*;
+    use nalgebra::{Matrix4, Point2, Point3, Vector2, Vector3};
+
+    impl GeometryValue for Vector2<i32> {
+        fn to_record(&self) -> Result<Vec<u8>, SerError> {
+            Ok(pair_record(self.x, self.y))
+        }
+
+        fn from_components(components: &[f64]) -> Option<Self> {
+            match components {
+                &[x, y] => Some(Vector2::new(x as i32, y as i32)),
+                _ => None,
+            }
+        }
+    }
+
+    impl GeometryValue for Vector2<f32> {
+        fn to_record(&self) -> Result<Vec<u8>, SerError> {
+            Ok(point_record(self.as_slice()))
+        }
+
+        fn from_components(components: &[f64]) -> Option<Self> {
+            match components {
+                &[x, y] => Some(Vector2::new(x as f32, y as f32)),
+                _ => None,
+            }
+        }
+    }
+
+    impl GeometryValue for Vector3<f32> {
+        fn to_record(&self) -> Result<Vec<u8>, SerError> {
+            Ok(point_record(self.as_slice()))
+        }
+
+        fn from_components(components: &[f64]) -> Option<Self> {
+            match components {
+                &[x, y, z] => Some(Vector3::new(x as f32, y as f32, z as f32)),
+                _ => None,
+            }
+        }
+    }
+
+    impl GeometryValue for Point2<f32> {
+        fn to_record(&self) -> Result<Vec<u8>, SerError> {
+            self.coords.to_record()
+        }
+
+        fn from_components(components: &[f64]) -> Option<Self> {
+            Vector2::from_components(components).map(Point2::from)
+        }
+    }
+
+    impl GeometryValue for Point3<f32> {
+        fn to_record(&self) -> Result<Vec<u8>, SerError> {
+            self.coords.to_record()
+        }
+
+        fn from_components(components: &[f64]) -> Option<Self> {
+            Vector3::from_components(components).map(Point3::from)
+        }
+    }
+
+    impl GeometryValue for Matrix4<f32> {
+        fn to_record(&self) -> Result<Vec<u8>, SerError> {
+            vector_record(self.as_slice())
+        }
+
+        fn from_components(components: &[f64]) -> Option<Self> {
+            if components.len() != 16 {
+                return None;
+            }
+            Some(Matrix4::from_iterator(components.iter().map(|v| *v as f32)))
+        }
+    }
+}
+
+struct GeometryVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for GeometryVisitor<T>
+where
+    T: GeometryValue,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a pair!, point2D!, point3D! or vector!")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<T, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut components = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(c) = seq.next_element::<f64>()? {
+            components.push(c);
+        }
+        let n = components.len();
+        T::from_components(&components).ok_or_else(|| de::Error::invalid_length(n, &self))
+    }
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use crate::{from_bytes, to_bytes};
+    use serde_derive::{Deserialize, Serialize};
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn test_glam() {
+        use glam::{IVec2, Mat4, Vec3};
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Scene {
+            #[serde(with = "super::Geometry")]
+            size: IVec2,
+            #[serde(with = "super::Geometry")]
+            eye: Vec3,
+            #[serde(with = "super::Geometry")]
+            view: Mat4,
+        }
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Eye(#[serde(with = "super::Geometry")] Vec3);
+
+        // rust-redbin-helper 1.5x2x-3
+        assert_eq!(to_bytes(&Eye(Vec3::new(1.5, 2.0, -3.0))).unwrap(),
+            &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00,
+            0x38, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x3F, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x40, 0xC0]);
+
+        let scene = Scene { size: IVec2::new(640, 480), eye: Vec3::new(0.5, 1.0, 2.0), view: Mat4::from_rotation_z(0.5) };
+        assert_eq!(scene, from_bytes(&to_bytes(&scene).unwrap()).unwrap());
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn test_nalgebra() {
+        use nalgebra::{Matrix4, Point3, Vector2};
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Scene {
+            #[serde(with = "super::Geometry")]
+            size: Vector2<i32>,
+            #[serde(with = "super::Geometry")]
+            eye: Point3<f32>,
+            #[serde(with = "super::Geometry")]
+            view: Matrix4<f32>,
+        }
+
+        let scene = Scene { size: Vector2::new(640, 480), eye: Point3::new(0.5, 1.0, 2.0), view: Matrix4::new_scaling(2.0) };
+        assert_eq!(scene, from_bytes(&to_bytes(&scene).unwrap()).unwrap());
+    }
+}
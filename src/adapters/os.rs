@@ -0,0 +1,284 @@
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::{self, Serializer};
+use serde_bytes::Bytes;
+use std::ffi::{CStr, CString, OsStr, OsString};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// (De)serializes an `OsString` or `PathBuf` as a `string!` record when it's
+/// valid Unicode, and otherwise as a `binary!` of its platform bytes: raw
+/// bytes on Unix, WTF-8 on Windows. Nothing is lost, but Red sees such names
+/// as binaries.
+pub struct OsText;
+
+impl OsText {
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + AsRef<OsStr>,
+        S: Serializer,
+    {
+        let value = value.as_ref();
+        match value.to_str() {
+            Some(text) => serializer.serialize_str(text),
+            None => serializer.serialize_bytes(&platform_bytes(value).map_err(ser::Error::custom)?),
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: From<OsString>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(OsTextVisitor(PhantomData))
+    }
+}
+
+/// (De)serializes an `OsString` or `PathBuf` as a `string!` record, replacing
+/// what isn't valid Unicode with U+FFFD.
+pub struct OsLossy;
+
+impl OsLossy {
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + AsRef<OsStr>,
+        S: Serializer,
+    {
+        serializer.serialize_str(&value.as_ref().to_string_lossy())
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: From<OsString>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_string(OsTextVisitor(PhantomData))
+    }
+}
+
+struct OsTextVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for OsTextVisitor<T>
+where
+    T: From<OsString>,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string! or binary! record")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        Ok(T::from(OsString::from(v)))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        Ok(T::from(OsString::from(v)))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        from_platform_bytes(v).map(T::from).map_err(E::custom)
+    }
+}
+
+/// (De)serializes a `CString` or `&CStr` as a `string!` record without its
+/// terminating NUL, or as a `binary!` if it isn't valid UTF-8. Either record
+/// reads back, failing clearly on a NUL inside the text.
+pub struct CText;
+
+impl CText {
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + AsRef<CStr>,
+        S: Serializer,
+    {
+        let bytes = value.as_ref().to_bytes();
+        match std::str::from_utf8(bytes) {
+            Ok(text) => serializer.serialize_str(text),
+            Err(_) => serializer.serialize_bytes(bytes),
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: From<CString>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(CTextVisitor(PhantomData))
+    }
+}
+
+struct CTextVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for CTextVisitor<T>
+where
+    T: From<CString>,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string! or binary! record without NULs")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        self.visit_bytes(v.as_bytes())
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        CString::new(v).map(T::from)
+            .map_err(|e| E::custom(format!("NUL inside a C string, at byte {}", e.nul_position())))
+    }
+}
+
+#[cfg(unix)]
+fn platform_bytes(value: &OsStr) -> Result<std::borrow::Cow<'_, Bytes>, &'static str> {
+    use std::os::unix::ffi::OsStrExt;
+    Ok(std::borrow::Cow::Borrowed(Bytes::new(value.as_bytes())))
+}
+
+#[cfg(unix)]
+fn from_platform_bytes(bytes: &[u8]) -> Result<OsString, &'static str> {
+    use std::os::unix::ffi::OsStrExt;
+    Ok(OsStr::from_bytes(bytes).to_os_string())
+}
+
+/// WTF-8: UTF-8 that also encodes unpaired surrogates, as three bytes each.
+#[cfg(windows)]
+fn platform_bytes(value: &OsStr) -> Result<std::borrow::Cow<'_, Bytes>, &'static str> {
+    use std::os::windows::ffi::OsStrExt;
+    let mut wtf8 = Vec::new();
+    for c in char::decode_utf16(value.encode_wide()) {
+        match c {
+            Ok(c) => wtf8.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            Err(e) => {
+                let unit = e.unpaired_surrogate();
+                wtf8.extend_from_slice(&[0xE0 | (unit >> 12) as u8, 0x80 | ((unit >> 6) & 0x3F) as u8,
+                    0x80 | (unit & 0x3F) as u8]);
+            }
+        }
+    }
+    Ok(std::borrow::Cow::Owned(serde_bytes::ByteBuf::from(wtf8)))
+}
+
+#[cfg(windows)]
+fn from_platform_bytes(bytes: &[u8]) -> Result<OsString, &'static str> {
+    use std::os::windows::ffi::OsStringExt;
+    let mut wide = Vec::with_capacity(bytes.len());
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let len = match rest[0] {
+            0x00..=0x7F => 1,
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => return Err("invalid WTF-8"),
+        };
+        let sequence = rest.get(..len).ok_or("invalid WTF-8")?;
+        let mut code = u32::from(sequence[0] & [0x7F, 0x1F, 0x0F, 0x07][len - 1]);
+        for &b in &sequence[1..] {
+            if b & 0xC0 != 0x80 {
+                return Err("invalid WTF-8");
+            }
+            code = (code << 6) | u32::from(b & 0x3F);
+        }
+        match code {
+            0xD800..=0xDFFF => wide.push(code as u16),
+            _ => {
+                let c = char::from_u32(code).ok_or("invalid WTF-8")?;
+                wide.extend_from_slice(c.encode_utf16(&mut [0; 2]));
+            }
+        }
+        rest = &rest[len..];
+    }
+    Ok(OsString::from_wide(&wide))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn platform_bytes(_value: &OsStr) -> Result<std::borrow::Cow<'_, Bytes>, &'static str> {
+    Err("non-Unicode OS strings aren't supported on this platform")
+}
+
+#[cfg(not(any(unix, windows)))]
+fn from_platform_bytes(_bytes: &[u8]) -> Result<OsString, &'static str> {
+    Err("non-Unicode OS strings aren't supported on this platform")
+}
+
+#[cfg(all(test, unix, feature = "ser", feature = "de"))]
+mod tests {
+    use super::{CText, OsLossy, OsText};
+    use crate::adapters::Word;
+    use crate::{from_bytes, to_bytes, DeError};
+    use serde_derive::{Deserialize, Serialize};
+    use std::ffi::{CStr, CString, OsString};
+    use std::os::unix::ffi::OsStringExt;
+    use std::path::PathBuf;
+
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_os_text() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Entry {
+            #[serde(with = "OsText")]
+            name: OsString,
+            #[serde(with = "OsText")]
+            path: PathBuf,
+        }
+        let entry = Entry { name: OsString::from("żółw.txt"), path: PathBuf::from("/tmp/a") };
+        let bytes = to_bytes(&entry).unwrap();
+        assert_eq!(entry, from_bytes(&bytes).unwrap());
+        let plain: (Word, String, Word, String) = from_bytes(&bytes).unwrap();
+        assert_eq!("żółw.txt", plain.1);
+
+        // not UTF-8, kept as a binary!
+        let entry = Entry { name: OsString::from_vec(vec![b'a', 0xFF]), path: PathBuf::new() };
+        let bytes = to_bytes(&entry).unwrap();
+        assert_eq!(entry, from_bytes(&bytes).unwrap());
+        // after the symbol table of `name path`, the block header and key
+        assert_eq!(0x29, bytes[48 + 12 + 16]);
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Lossy(#[serde(with = "OsLossy")] OsString);
+        let bytes = to_bytes(&Lossy(OsString::from_vec(vec![b'a', 0xFF]))).unwrap();
+        assert_eq!(Lossy(OsString::from("a\u{FFFD}")), from_bytes(&bytes).unwrap());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_c_text() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Name(#[serde(with = "CText")] CString);
+        let name = Name(CString::new("żółw").unwrap());
+        let bytes = to_bytes(&name).unwrap();
+        assert_eq!(0x07, bytes[16]);
+        assert_eq!(name, from_bytes(&bytes).unwrap());
+        assert_eq!("żółw", from_bytes::<String>(&bytes).unwrap());
+
+        let raw = Name(CString::new(vec![b'a', 0xFF]).unwrap());
+        let bytes = to_bytes(&raw).unwrap();
+        assert_eq!(0x29, bytes[16]);
+        assert_eq!(raw, from_bytes(&bytes).unwrap());
+
+        #[derive(Serialize)]
+        struct Borrowed<'a>(#[serde(with = "CText")] &'a CStr);
+        assert_eq!(to_bytes(&"ab").unwrap(), to_bytes(&Borrowed(CStr::from_bytes_with_nul(b"ab\0").unwrap())).unwrap());
+        let error = from_bytes::<Name>(&to_bytes(&"a\0b").unwrap()).unwrap_err();
+        // messages of `Deserialize` impls are fixed with `static-errors`
+        #[cfg(not(feature = "static-errors"))]
+        assert_eq!("NUL inside a C string, at byte 1", error.to_string());
+        assert!(matches!(error, DeError::Message(_)));
+    }
+}
@@ -0,0 +1,134 @@
+use super::RAW_RECORD;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+use serde_bytes::Bytes;
+use std::borrow::Cow;
+use std::fmt;
+
+/// A record as its Redbin bytes, written to the output as is, for record
+/// types serde can't express, e.g. `money!`, or records encoded elsewhere:
+///
+/// ```
+/// # use redbin::adapters::RawRecord;
+/// # fn main() -> redbin::Result<()> {
+/// // an `integer!` 42
+/// let record = RawRecord::from(vec![0x0B, 0, 0, 0, 42, 0, 0, 0]);
+/// let bytes = redbin::to_bytes(&(1, record))?;
+/// # assert_eq!((1, 42), redbin::from_bytes(&bytes)?);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The bytes are counted as a single value of the block they're in, but
+/// aren't checked beyond being whole 32-bit slots, so they must be a
+/// complete, valid record. Words reference the symbol table of their
+/// document, so records with words, or blocks of them, can't be copied
+/// between documents this way.
+///
+/// Read back, it holds the bytes of any one record, borrowed from the input
+/// where possible.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawRecord<'a>(pub Cow<'a, [u8]>);
+
+impl RawRecord<'_> {
+    pub fn into_owned(self) -> RawRecord<'static> {
+        RawRecord(Cow::Owned(self.0.into_owned()))
+    }
+}
+
+impl From<Vec<u8>> for RawRecord<'static> {
+    fn from(bytes: Vec<u8>) -> Self {
+        RawRecord(Cow::Owned(bytes))
+    }
+}
+
+impl<'a> From<&'a [u8]> for RawRecord<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        RawRecord(Cow::Borrowed(bytes))
+    }
+}
+
+impl Serialize for RawRecord<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if self.0.is_empty() || !self.0.len().is_multiple_of(4) {
+            return Err(ser::Error::custom(format!("raw record of {} bytes, not whole 32-bit slots", self.0.len())));
+        }
+        serializer.serialize_newtype_struct(RAW_RECORD, Bytes::new(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for RawRecord<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(RAW_RECORD, RawRecordVisitor)
+    }
+}
+
+struct RawRecordVisitor;
+
+impl<'de> Visitor<'de> for RawRecordVisitor {
+    type Value = RawRecord<'de>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("bytes of a record")
+    }
+
+    fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(RawRecord(Cow::Borrowed(v)))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(RawRecord(Cow::Owned(v.to_vec())))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(RawRecord(Cow::Owned(v)))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        serde_bytes::Deserialize::deserialize(deserializer).map(|bytes: Cow<'de, [u8]>| RawRecord(bytes))
+    }
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use super::RawRecord;
+    use crate::{from_bytes, to_bytes};
+    use serde_derive::Deserialize;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn test_raw_record() {
+        // 17-Oct-2026/10:00:00.5
+        let date = RawRecord::from(vec![0x2F, 0x00, 0x00, 0x00, 0x80, 0xA8, 0xD5, 0x0F,
+            0x10, 0x94, 0xE1, 0x40, 0x00, 0x00, 0x00, 0x00]);
+        let integer = RawRecord::from(vec![0x0B, 0x00, 0x00, 0x00, 0x2A, 0x00, 0x00, 0x00]);
+        let bytes = to_bytes(&(1, &date, [&integer])).unwrap();
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Entry(i32, #[serde(with = "crate::adapters::Date")] SystemTime, Vec<i32>);
+        let entry = Entry(1, UNIX_EPOCH + Duration::new(1_792_231_200, 500_000_000), vec![42]);
+        assert_eq!(entry, from_bytes(&bytes).unwrap());
+
+        let (_, read, [inner]): (i32, RawRecord, [RawRecord; 1]) = from_bytes(&bytes).unwrap();
+        assert_eq!((date, integer), (read, inner));
+        assert!(to_bytes(&RawRecord::from(vec![0x0B, 0x00])).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+use super::SHARED;
+use serde::de::{self, Deserialize, Deserializer, IgnoredAny, Visitor};
+use serde::ser::{Serialize, Serializer};
+use serde_bytes::ByteBuf;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Series read so far: where each is stored, its head and its value.
+type Series = Vec<(usize, usize, Box<dyn Any>)>;
+
+thread_local! {
+    // series read so far from the document being decoded, by where they're
+    // stored and their head; `None` outside of `from_bytes` and the like
+    static SERIES: RefCell<Option<Series>> = const { RefCell::new(None) };
+    // where the series the next `Shared` reads is stored
+    static NEXT: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+/// Runs `f`, the decoding of a whole document, with `Shared` values kept
+/// for later references to the same series.
+#[cfg(feature = "de")]
+pub(crate) fn with_series<T>(f: impl FnOnce() -> T) -> T {
+    let previous = SERIES.with(|s| s.replace(Some(Vec::new())));
+    let result = f();
+    SERIES.with(|s| *s.borrow_mut() = previous);
+    result
+}
+
+/// Runs `f`, reading a `Shared` value, with `key` telling where its series
+/// is stored.
+#[cfg(feature = "de")]
+pub(crate) fn with_key<T>(key: Option<(usize, usize)>, f: impl FnOnce() -> T) -> T {
+    NEXT.with(|next| next.set(key));
+    let result = f();
+    NEXT.with(|next| next.set(None));
+    result
+}
+
+fn cached<T: SharedSeries>(key: (usize, usize)) -> Option<T> {
+    SERIES.with(|s| {
+        s.borrow().as_ref()?.iter()
+            .filter(|(at, head, _)| (*at, *head) == key)
+            .find_map(|(_, _, value)| value.downcast_ref::<T>().cloned())
+    })
+}
+
+fn remember<T: SharedSeries>(key: (usize, usize), value: &T) {
+    SERIES.with(|s| {
+        if let Some(series) = s.borrow_mut().as_mut() {
+            series.push((key.0, key.1, Box::new(value.clone())));
+        }
+    });
+}
+
+/// A reference-counted string or binary, `Rc<str>`, `Arc<str>`, `Rc<[u8]>`
+/// or `Arc<[u8]>`, that `Shared` can hold.
+pub trait SharedSeries: Clone + 'static {
+    fn serialize_series<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+
+    fn deserialize_series<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+macro_rules! shared_str {
+    ($($t:ty),*) => {$(
+        impl SharedSeries for $t {
+            fn serialize_series<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self)
+            }
+
+            fn deserialize_series<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                String::deserialize(deserializer).map(<$t>::from)
+            }
+        }
+    )*};
+}
+
+macro_rules! shared_bytes {
+    ($($t:ty),*) => {$(
+        impl SharedSeries for $t {
+            fn serialize_series<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_bytes(self)
+            }
+
+            fn deserialize_series<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                ByteBuf::deserialize(deserializer).map(|bytes| <$t>::from(bytes.into_vec()))
+            }
+        }
+    )*};
+}
+
+shared_str!(Rc<str>, Arc<str>);
+shared_bytes!(Rc<[u8]>, Arc<[u8]>);
+
+/// A string or binary read once per series in a document, e.g.
+/// `Vec<Shared<Rc<str>>>`.
+///
+/// Red data often holds the same series in many places, which Redbin
+/// stores once and refers to from the other places. Read with `from_bytes`
+/// or the like, every `Shared` for the same series and head clones one
+/// `Rc` or `Arc` instead of allocating a copy. Other formats see the plain
+/// string or bytes.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Shared<T>(pub T);
+
+impl<T> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: SharedSeries> Serialize for Shared<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize_series(serializer)
+    }
+}
+
+impl<'de, T: SharedSeries> Deserialize<'de> for Shared<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(SHARED, SharedVisitor(PhantomData))
+    }
+}
+
+struct SharedVisitor<T>(PhantomData<T>);
+
+impl<'de, T: SharedSeries> Visitor<'de> for SharedVisitor<T> {
+    type Value = Shared<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string or binary")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Shared<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let key = NEXT.with(Cell::take);
+        if let Some(value) = key.and_then(cached::<T>) {
+            IgnoredAny::deserialize(deserializer)?;
+            return Ok(Shared(value));
+        }
+        let value = T::deserialize_series(deserializer)?;
+        if let Some(key) = key {
+            remember(key, &value);
+        }
+        Ok(Shared(value))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Shared<T>, E>
+    where
+        E: de::Error,
+    {
+        T::deserialize_series(de::value::StrDeserializer::new(v)).map(Shared)
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Shared<T>, E>
+    where
+        E: de::Error,
+    {
+        T::deserialize_series(de::value::BytesDeserializer::new(v)).map(Shared)
+    }
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use super::Shared;
+    use crate::{from_bytes, to_bytes, DeError, Value};
+    use serde_derive::Deserialize;
+    use std::convert::TryInto;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_shared() {
+        #[derive(Deserialize, Debug)]
+        struct Names {
+            names: Vec<Shared<Rc<str>>>,
+            data: Vec<Shared<Arc<[u8]>>>,
+        }
+
+        // [names: ["abc" <ref 0 1 0> <next ref 0 1 0>] data: [#{CAFE} <ref 0 3 0>]]
+        let mut bytes = to_bytes(&Value::Block(vec![
+            Value::SetWord("names".to_string()),
+            Value::Block(vec![Value::String("abc".to_string())]),
+            Value::SetWord("data".to_string()),
+            Value::Block(vec![Value::Binary(vec![0xCA, 0xFE])]),
+        ])).unwrap();
+        let reference = |record_type: u8, head: u8, index: u8| [record_type, 0x01, 0x08, 0x00, head, 0x00, 0x00, 0x00,
+            0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, index, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let names = bytes.windows(4).position(|w| w == [0x07, 0x01, 0x00, 0x00]).unwrap() + 16;
+        bytes.splice(names..names, [reference(0x07, 0, 1), reference(0x07, 1, 1)].concat());
+        bytes[names - 20] = 3; // length of the block
+        let data = bytes.len();
+        bytes.extend_from_slice(&reference(0x29, 0, 3));
+        bytes[data - 20] = 2;
+        let size = i32::from_le_bytes(bytes[12..16].try_into().unwrap()) + 3 * 24;
+        bytes[12..16].copy_from_slice(&size.to_le_bytes());
+
+        let decoded: Names = from_bytes(&bytes).unwrap();
+        let texts: Vec<&str> = decoded.names.iter().map(|name| &***name).collect();
+        assert_eq!(vec!["abc", "abc", "bc"], texts);
+        assert!(Rc::ptr_eq(&decoded.names[0], &decoded.names[1]));
+        assert!(!Rc::ptr_eq(&decoded.names[0], &decoded.names[2]));
+        assert!(Arc::ptr_eq(&decoded.data[0], &decoded.data[1]));
+
+        // plain fields read references as copies
+        let value: Value = from_bytes(&bytes).unwrap();
+        assert_eq!(Some(&Value::String("bc".to_string())), value.get("names").unwrap().get_index(2));
+
+        // a reference to a record of another type
+        bytes[names] = 0x29;
+        assert!(matches!(from_bytes::<Value>(&bytes), Err(DeError::Message(_))));
+    }
+}
@@ -0,0 +1,341 @@
+use super::{RED_STRING, SERIES, UNIT_1, UNIT_2, UNIT_4};
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// A series together with its head position, e.g. `next "abc"`.
+///
+/// Plain fields get the series from the head on, so `next "abc"` reads as
+/// `"bc"`. `RedSeries` keeps the whole series in `data` and the position in
+/// `head`, and writes both back. `data` must be a string, binary or block.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RedSeries<T> {
+    /// zero-based index of the first element, in characters for strings
+    pub head: usize,
+    pub data: T,
+}
+
+impl<T> RedSeries<T> {
+    pub fn new(data: T) -> Self {
+        RedSeries { head: 0, data }
+    }
+}
+
+/// Red's series operations on blocks, e.g. `skip`, `copy/part` and `find`.
+/// Positions are clamped to the series like Red clamps them, and moving
+/// the head never changes `data`, so it's written back whole with the new
+/// head.
+///
+/// ```
+/// # use redbin::adapters::RedSeries;
+/// let mut series = RedSeries::new(vec![1, 2, 3, 4]);
+/// series.skip(1);
+/// assert_eq!(series.copy_part(2), vec![2, 3]); // copy/part next s 2
+/// ```
+impl<T> RedSeries<Vec<T>> {
+    /// One-based position of the head, as `index?` gives it.
+    pub fn index(&self) -> usize {
+        self.head.min(self.data.len()) + 1
+    }
+
+    /// Values from the head on.
+    pub fn as_slice(&self) -> &[T] {
+        &self.data[self.head.min(self.data.len())..]
+    }
+
+    /// Number of values from the head on, as `length?` gives it.
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_slice().is_empty()
+    }
+
+    /// Whether the head is at the first value, as `head?` tells.
+    pub fn is_head(&self) -> bool {
+        self.head == 0
+    }
+
+    /// Whether the head is past the last value, as `tail?` tells.
+    pub fn is_tail(&self) -> bool {
+        self.head >= self.data.len()
+    }
+
+    /// Moves the head by `offset` values, back if negative, staying within
+    /// the series.
+    pub fn skip(&mut self, offset: isize) -> &mut Self {
+        let at = self.head.min(self.data.len());
+        self.head = if offset < 0 {
+            at.saturating_sub(offset.unsigned_abs())
+        } else {
+            at.saturating_add(offset as usize).min(self.data.len())
+        };
+        self
+    }
+
+    /// Moves the head to the first value.
+    pub fn to_head(&mut self) -> &mut Self {
+        self.head = 0;
+        self
+    }
+
+    /// Moves the head past the last value.
+    pub fn to_tail(&mut self) -> &mut Self {
+        self.head = self.data.len();
+        self
+    }
+
+    /// Up to `length` values from the head on, as `copy/part` copies them.
+    pub fn copy_part(&self, length: usize) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let values = self.as_slice();
+        values[..length.min(values.len())].to_vec()
+    }
+
+    /// Moves the head to the first `value` at or after it and returns the
+    /// series, or leaves it where it is and returns `None`, as `find` does.
+    pub fn find(&mut self, value: &T) -> Option<&mut Self>
+    where
+        T: PartialEq,
+    {
+        let at = self.as_slice().iter().position(|v| v == value)?;
+        self.head = self.head.min(self.data.len()) + at;
+        Some(self)
+    }
+}
+
+impl<T> Serialize for RedSeries<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let head = u32::try_from(self.head).map_err(|_| ser::Error::custom("series head exceeds 32 bits"))?;
+        serializer.serialize_newtype_struct(SERIES, &(head, &self.data))
+    }
+}
+
+impl<'de, T> Deserialize<'de> for RedSeries<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(SERIES, RedSeriesVisitor(PhantomData))
+    }
+}
+
+struct RedSeriesVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for RedSeriesVisitor<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = RedSeries<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a series with its head position")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<RedSeries<T>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let head = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let data = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Ok(RedSeries { head, data })
+    }
+
+    // other formats see a `(head, data)` pair
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<RedSeries<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple(2, self)
+    }
+}
+
+/// A `string!` as it was stored: its characters' size, head position and
+/// whole content, so it's written back byte for byte.
+///
+/// Red stores strings in 1-byte (Latin-1), 2-byte (UCS-2) or 4-byte (UCS-4)
+/// characters, whichever fits all of them; `new` picks the unit the same way,
+/// except 1-byte characters only for ASCII, which is all the serializer
+/// writes with them. Other formats see a `(head, content)` pair.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RedString {
+    /// bytes per character: 1, 2 or 4
+    pub unit: u8,
+    /// zero-based index of the first character
+    pub head: usize,
+    pub content: String,
+}
+
+impl RedString {
+    pub fn new(content: String) -> Self {
+        let max = content.chars().max().unwrap_or('\0');
+        let unit = if max <= '\u{7F}' {1} else if max <= '\u{FFFF}' {2} else {4};
+        RedString { unit, head: 0, content }
+    }
+
+    /// The string from its head on, as Red sees it.
+    pub fn as_str(&self) -> &str {
+        self.content.char_indices().nth(self.head).map_or("", |(at, _)| &self.content[at..])
+    }
+}
+
+/// Content of a `RedString`, written with its unit.
+struct UnitStr<'a>(u8, &'a str);
+
+impl Serialize for UnitStr<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let token = match self.0 {
+            1 => UNIT_1,
+            2 => UNIT_2,
+            4 => UNIT_4,
+            unit => return Err(ser::Error::custom(format!("invalid string unit {}", unit))),
+        };
+        serializer.serialize_newtype_struct(token, self.1)
+    }
+}
+
+impl Serialize for RedString {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let head = u32::try_from(self.head).map_err(|_| ser::Error::custom("series head exceeds 32 bits"))?;
+        serializer.serialize_newtype_struct(SERIES, &(head, UnitStr(self.unit, &self.content)))
+    }
+}
+
+impl<'de> Deserialize<'de> for RedString {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(RED_STRING, RedStringVisitor)
+    }
+}
+
+struct RedStringVisitor;
+
+impl<'de> Visitor<'de> for RedStringVisitor {
+    type Value = RedString;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string! record")
+    }
+
+    // read from Redbin as `unit`, `head` and `content`
+    fn visit_seq<A>(self, mut seq: A) -> Result<RedString, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let unit = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let head = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let content = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        Ok(RedString { unit, head, content })
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<RedString, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (head, content) = <(usize, String)>::deserialize(deserializer)?;
+        Ok(RedString { head, ..RedString::new(content) })
+    }
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use super::{RedSeries, RedString};
+    use crate::{from_bytes, parse_red, to_bytes, Value};
+    use serde_bytes::ByteBuf;
+
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_red_series() {
+        // next "abc"
+        let series = RedSeries { head: 1, data: "abc".to_string() };
+        let bytes = to_bytes(&series).unwrap();
+        assert_eq!(&bytes[16..], &[0x07, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00,
+            0x61, 0x62, 0x63, 0x00]);
+        assert_eq!(series, from_bytes(&bytes).unwrap());
+        assert_eq!("bc", from_bytes::<String>(&bytes).unwrap());
+
+        // skip "żółw" 2
+        let series = RedSeries { head: 2, data: "żółw".to_string() };
+        let bytes = to_bytes(&series).unwrap();
+        assert_eq!(series, from_bytes(&bytes).unwrap());
+        assert_eq!("łw", from_bytes::<String>(&bytes).unwrap());
+
+        let series = RedSeries { head: 2, data: vec![1, 2, 3] };
+        assert_eq!(series, from_bytes(&to_bytes(&series).unwrap()).unwrap());
+        let series = RedSeries { head: 1, data: ByteBuf::from(vec![0xCA, 0xFE]) };
+        assert_eq!(series, from_bytes(&to_bytes(&series).unwrap()).unwrap());
+        let nested = vec![RedSeries::new("x".to_string()), RedSeries { head: 1, data: "yz".to_string() }];
+        assert_eq!(nested, from_bytes::<Vec<RedSeries<String>>>(&to_bytes(&nested).unwrap()).unwrap());
+
+        assert!(to_bytes(&RedSeries { head: 1, data: 5 }).is_err());
+    }
+
+    #[test]
+    fn test_block_series() {
+        let mut series = RedSeries::new(vec![Value::Word("a".to_string()), Value::Integer(1), Value::Word("b".to_string()),
+            Value::Integer(2)]);
+        assert!(series.is_head());
+        assert_eq!(1, series.index());
+        series.skip(1);
+        assert_eq!(vec![Value::Integer(1), Value::Word("b".to_string())], series.copy_part(2));
+        assert_eq!(5, series.skip(-5).skip(10).index());
+        assert!(series.is_tail() && series.is_empty());
+        assert!(series.to_head().find(&Value::Word("c".to_string())).is_none());
+        assert_eq!(1, series.index());
+        let found = series.find(&Value::Word("b".to_string())).unwrap();
+        assert_eq!(3, found.index());
+        assert_eq!(vec![Value::Integer(2)], found.skip(1).copy_part(5));
+
+        // written back with its head, e.g. `skip [a 1 b 2] 3`
+        let value = parse_red("[a 1 b 2]").unwrap().into_series().unwrap();
+        assert_eq!(series.data, value.data);
+        let bytes = to_bytes(&series).unwrap();
+        assert_eq!(series, from_bytes(&bytes).unwrap());
+        assert_eq!(Value::Block(series.data.clone()), from_bytes::<Value>(&bytes).unwrap());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_red_string() {
+        // next "żółw" in UCS-2
+        let string = RedString { unit: 2, head: 1, content: "żółw".to_string() };
+        let bytes = to_bytes(&string).unwrap();
+        assert_eq!(&bytes[16..], &[0x07, 0x02, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
+            0x7C, 0x01, 0xF3, 0x00, 0x42, 0x01, 0x77, 0x00]);
+        assert_eq!(string, from_bytes(&bytes).unwrap());
+        assert_eq!("ółw", string.as_str());
+
+        for unit in [1, 2, 4] {
+            let string = RedString { unit, head: 0, content: "abc".to_string() };
+            let bytes = to_bytes(&string).unwrap();
+            assert_eq!(unit, bytes[17]);
+            assert_eq!(string, from_bytes(&bytes).unwrap());
+        }
+        assert_eq!(4, RedString::new("😀".to_string()).unit);
+        assert_eq!(2, RedString::new("café".to_string()).unit);
+        assert!(to_bytes(&RedString { unit: 1, head: 0, content: "café".to_string() }).is_err());
+        assert!(to_bytes(&RedString { unit: 2, head: 0, content: "😀".to_string() }).is_err());
+    }
+}
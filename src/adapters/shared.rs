@@ -0,0 +1,107 @@
+use bytes::Bytes;
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+use std::cell::RefCell;
+use std::fmt;
+
+thread_local! {
+    // buffer being read by `from_shared_bytes`
+    static SOURCE: RefCell<Option<Bytes>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with `source` as the buffer `SharedBytes` fields are sliced from.
+#[cfg(feature = "de")]
+pub(crate) fn with_source<T>(source: &Bytes, f: impl FnOnce() -> T) -> T {
+    let previous = SOURCE.with(|s| s.replace(Some(source.clone())));
+    let result = f();
+    SOURCE.with(|s| *s.borrow_mut() = previous);
+    result
+}
+
+/// (De)serializes a `bytes::Bytes` as a `binary!` record. When read with
+/// `from_shared_bytes`, the value shares the input buffer instead of copying
+/// out of it.
+pub struct SharedBytes;
+
+impl SharedBytes {
+    pub fn serialize<S>(value: &Bytes, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(value)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Bytes, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_bytes(SharedBytesVisitor)
+    }
+}
+
+struct SharedBytesVisitor;
+
+impl<'de> Visitor<'de> for SharedBytesVisitor {
+    type Value = Bytes;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a binary! record")
+    }
+
+    fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Bytes, E>
+    where
+        E: de::Error,
+    {
+        let shared = SOURCE.with(|s| match &*s.borrow() {
+            Some(source) if source.as_ptr_range().contains(&v.as_ptr()) => Some(source.slice_ref(v)),
+            _ => None,
+        });
+        Ok(shared.unwrap_or_else(|| Bytes::copy_from_slice(v)))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Bytes, E>
+    where
+        E: de::Error,
+    {
+        Ok(Bytes::copy_from_slice(v))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Bytes, E>
+    where
+        E: de::Error,
+    {
+        Ok(Bytes::from(v))
+    }
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use crate::{from_bytes, from_shared_bytes, to_bytes_mut, DeserializerOptions, SerializerOptions};
+    use bytes::{Bytes, BytesMut};
+    use serde_derive::{Deserialize, Serialize};
+
+    #[test]
+    fn test_shared_bytes() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Packet {
+            id: i32,
+            #[serde(with = "super::SharedBytes")]
+            payload: Bytes,
+        }
+        let packet = Packet { id: 1, payload: Bytes::from_static(&[0xCA, 0xFE]) };
+
+        let mut buffer = BytesMut::from(&b"prefix"[..]);
+        to_bytes_mut(&packet, &mut buffer, SerializerOptions::new()).unwrap();
+        let document = buffer.split_off(6).freeze();
+        assert_eq!(&buffer[..], b"prefix");
+
+        let decoded: Packet = from_shared_bytes(&document, DeserializerOptions::new()).unwrap();
+        assert_eq!(packet, decoded);
+        assert!(document.as_ptr_range().contains(&decoded.payload.as_ptr()));
+
+        // elsewhere the payload is copied
+        let decoded: Packet = from_bytes(&document).unwrap();
+        assert_eq!(packet, decoded);
+        assert!(!document.as_ptr_range().contains(&decoded.payload.as_ptr()));
+    }
+}
@@ -0,0 +1,23 @@
+//! Prints Rust types able to read a sample Redbin file, e.g.
+//! `redbin-codegen servers.redbin Servers > src/servers.rs`.
+
+use redbin::{codegen, from_file, Value};
+use std::process::exit;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let (path, root) = match (args.next(), args.next(), args.next()) {
+        (Some(path), root, None) => (path, root.unwrap_or_else(|| String::from("Root"))),
+        _ => {
+            eprintln!("usage: redbin-codegen FILE [TYPE_NAME]");
+            exit(2);
+        }
+    };
+    match from_file::<Value, _>(&path) {
+        Ok(sample) => print!("{}", codegen::rust_types(&sample, &root)),
+        Err(error) => {
+            eprintln!("redbin-codegen: {}", error);
+            exit(1);
+        }
+    }
+}
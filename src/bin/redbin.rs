@@ -0,0 +1,310 @@
+//! Tools for Redbin documents:
+//!
+//! `redbin explore FILE` browses a document in the terminal: a tree of its
+//! values, the bytes of the selected one, and search through their text.
+//!
+//! Keys: arrows or `hjkl` move, expand and collapse, `PageUp`/`PageDown`
+//! move faster, `/` searches, `n` finds the next match, `q` quits.
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use redbin::{Deserializer, Value};
+use serde::Deserialize;
+use std::process::exit;
+
+/// Longest text shown for a value.
+const LABEL_LEN: usize = 120;
+
+/// Bytes shown on each line of the hex pane.
+const ROW: usize = 16;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let path = match args.as_slice() {
+        [command, path] if command == "explore" => path,
+        _ => {
+            eprintln!("usage: redbin explore FILE");
+            exit(2);
+        }
+    };
+    let bytes = std::fs::read(path).unwrap_or_else(|e| {
+        eprintln!("redbin: {}: {}", path, e);
+        exit(1);
+    });
+    let roots = read_document(&bytes).unwrap_or_else(|e| {
+        eprintln!("redbin: {}: {}", path, e);
+        exit(1);
+    });
+    let mut explorer = Explorer::new(roots, &bytes);
+    let terminal = ratatui::init();
+    let result = explorer.run(terminal);
+    ratatui::restore();
+    if let Err(e) = result {
+        eprintln!("redbin: {}", e);
+        exit(1);
+    }
+}
+
+/// A record of the document, with where it is.
+struct Node {
+    label: String,
+    offset: usize,
+    len: usize,
+    children: Vec<Node>,
+    expanded: bool,
+}
+
+/// Root records of a document, blocks and parens with their values.
+fn read_document(bytes: &[u8]) -> redbin::Result<Vec<Node>> {
+    let mut deserializer = Deserializer::from_bytes(bytes);
+    deserializer.read_header()?;
+    let mut roots = Vec::new();
+    while deserializer.remaining().iter().any(|&b| b != 0x00) {
+        roots.push(read_node(&mut deserializer)?);
+    }
+    Ok(roots)
+}
+
+fn read_node(deserializer: &mut Deserializer) -> redbin::Result<Node> {
+    let padding = deserializer.remaining().iter().take_while(|&&b| b == 0x00).count();
+    let offset = deserializer.position() + padding;
+    let (label, children) = match deserializer.remaining().get(padding) {
+        Some(&record_type @ (0x05 | 0x06)) => {
+            let n = deserializer.enter_block()?;
+            let children = (0..n).map(|_| read_node(deserializer)).collect::<redbin::Result<Vec<_>>>()?;
+            let kind = if record_type == 0x05 { "block!" } else { "paren!" };
+            (format!("{} of {} values", kind, n), children)
+        }
+        _ => (label(&Value::deserialize(&mut *deserializer)?), Vec::new()),
+    };
+    Ok(Node { label, offset, len: deserializer.position() - offset, children, expanded: false })
+}
+
+/// Value molded as Red code, shortened to `LABEL_LEN` characters.
+fn label(value: &Value) -> String {
+    let text = value.to_string().replace('\n', "^/");
+    match text.char_indices().nth(LABEL_LEN) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    }
+}
+
+/// A node shown in the tree, by the indexes leading to it from the roots.
+type Path = Vec<usize>;
+
+struct Explorer<'a> {
+    roots: Vec<Node>,
+    bytes: &'a [u8],
+    list: ListState,
+    /// text typed after `/`, while it's being typed
+    typing: Option<String>,
+    query: String,
+    status: String,
+}
+
+impl<'a> Explorer<'a> {
+    fn new(roots: Vec<Node>, bytes: &'a [u8]) -> Self {
+        let status = format!("{} bytes, {} root values", bytes.len(), roots.len());
+        Explorer { roots, bytes, list: ListState::default().with_selected(Some(0)), typing: None,
+            query: String::new(), status }
+    }
+
+    fn run(&mut self, mut terminal: DefaultTerminal) -> std::io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let key = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+            if let Some(typed) = &mut self.typing {
+                match key.code {
+                    KeyCode::Char(c) => typed.push(c),
+                    KeyCode::Backspace => {
+                        typed.pop();
+                    }
+                    KeyCode::Enter => {
+                        self.query = self.typing.take().unwrap_or_default();
+                        self.find_next();
+                    }
+                    KeyCode::Esc => self.typing = None,
+                    _ => {}
+                }
+                continue;
+            }
+            let page = terminal.size()?.height.saturating_sub(3) as usize;
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+                KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+                KeyCode::PageDown => self.move_by(page as isize),
+                KeyCode::PageUp => self.move_by(-(page as isize)),
+                KeyCode::Right | KeyCode::Enter | KeyCode::Char('l') => self.set_expanded(true),
+                KeyCode::Left | KeyCode::Char('h') => self.collapse(),
+                KeyCode::Char('/') => self.typing = Some(String::new()),
+                KeyCode::Char('n') => self.find_next(),
+                _ => {}
+            }
+        }
+    }
+
+    /// Paths of the nodes shown, in order.
+    fn visible(&self) -> Vec<Path> {
+        self.paths(false)
+    }
+
+    /// Paths of the nodes in expanded nodes, or of all of them.
+    fn paths(&self, all: bool) -> Vec<Path> {
+        fn walk(nodes: &[Node], all: bool, path: &mut Path, out: &mut Vec<Path>) {
+            for (i, node) in nodes.iter().enumerate() {
+                path.push(i);
+                out.push(path.clone());
+                if all || node.expanded {
+                    walk(&node.children, all, path, out);
+                }
+                path.pop();
+            }
+        }
+        let mut out = Vec::new();
+        walk(&self.roots, all, &mut Vec::new(), &mut out);
+        out
+    }
+
+    fn node(&self, path: &[usize]) -> &Node {
+        let mut node = &self.roots[path[0]];
+        for &i in &path[1..] {
+            node = &node.children[i];
+        }
+        node
+    }
+
+    fn node_mut(&mut self, path: &[usize]) -> &mut Node {
+        let mut node = &mut self.roots[path[0]];
+        for &i in &path[1..] {
+            node = &mut node.children[i];
+        }
+        node
+    }
+
+    fn selected(&self) -> Option<Path> {
+        self.visible().get(self.list.selected()?).cloned()
+    }
+
+    fn select(&mut self, path: &[usize]) {
+        let index = self.visible().iter().position(|shown| shown.as_slice() == path);
+        self.list.select(index);
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let last = self.visible().len().saturating_sub(1);
+        let at = self.list.selected().unwrap_or(0) as isize + delta;
+        self.list.select(Some(at.clamp(0, last as isize) as usize));
+    }
+
+    fn set_expanded(&mut self, expanded: bool) {
+        if let Some(path) = self.selected() {
+            self.node_mut(&path).expanded = expanded;
+        }
+    }
+
+    /// Collapses the selected node, or if it isn't expanded, goes to its
+    /// parent.
+    fn collapse(&mut self) {
+        let Some(mut path) = self.selected() else { return };
+        if self.node(&path).expanded {
+            self.set_expanded(false);
+        } else if path.len() > 1 {
+            path.pop();
+            self.select(&path);
+        }
+    }
+
+    /// Selects the next node after the selected one whose label holds the
+    /// query, expanding the nodes around it.
+    fn find_next(&mut self) {
+        if self.query.is_empty() {
+            return;
+        }
+        let all = self.paths(true);
+        let start = self.selected().and_then(|path| all.iter().position(|p| *p == path)).map_or(0, |i| i + 1);
+        let query = self.query.to_lowercase();
+        let found = (0..all.len()).map(|i| &all[(start + i) % all.len()])
+            .find(|path| self.node(path).label.to_lowercase().contains(&query))
+            .cloned();
+        match found {
+            Some(path) => {
+                for depth in 1..path.len() {
+                    self.node_mut(&path[..depth]).expanded = true;
+                }
+                self.select(&path);
+                self.status = format!("found \"{}\"", self.query);
+            }
+            None => self.status = format!("\"{}\" not found", self.query),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] = Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+        let [tree, hex] = Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(main);
+
+        let items: Vec<ListItem> = self.visible().iter().map(|path| {
+            let node = self.node(path);
+            let marker = match (node.children.is_empty(), node.expanded) {
+                (true, _) => "  ",
+                (false, true) => "▾ ",
+                (false, false) => "▸ ",
+            };
+            ListItem::new(format!("{}{}{}", "  ".repeat(path.len() - 1), marker, node.label))
+        }).collect();
+        let list = List::new(items)
+            .block(Block::bordered().title("values"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, tree, &mut self.list);
+
+        let (title, lines) = match self.selected() {
+            Some(path) => {
+                let node = self.node(&path);
+                let rows = hex.height.saturating_sub(2) as usize;
+                (format!("{} bytes at {:#X}", node.len, node.offset), hex_lines(self.bytes, node.offset, node.len, rows))
+            }
+            None => (String::from("bytes"), Vec::new()),
+        };
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(title)), hex);
+
+        let status_line = match &self.typing {
+            Some(typed) => format!("/{}", typed),
+            None => self.status.clone(),
+        };
+        frame.render_widget(Paragraph::new(status_line), status);
+    }
+}
+
+/// Up to `rows` lines of hex dump of `len` bytes at `offset`.
+fn hex_lines(bytes: &[u8], offset: usize, len: usize, rows: usize) -> Vec<Line<'static>> {
+    let end = (offset + len).min(bytes.len());
+    bytes[offset..end].chunks(ROW).take(rows).enumerate().map(|(i, chunk)| {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02X}", b)).collect();
+        let text: String = chunk.iter().map(|&b| if b.is_ascii_graphic() { b as char } else { '.' }).collect();
+        Line::from(format!("{:08X}  {:<47}  {}", offset + i * ROW, hex.join(" "), text))
+    }).collect()
+}
+
+#[cfg(all(test, feature = "ser"))]
+mod tests {
+    use super::read_document;
+
+    #[test]
+    fn test_read_document() {
+        let bytes = redbin::to_bytes(&redbin::parse_red("[a: 1 b: [\"x\" (2)]]").unwrap()).unwrap();
+        let roots = read_document(&bytes).unwrap();
+        assert_eq!(1, roots.len());
+        assert_eq!("block! of 4 values", roots[0].label);
+        assert_eq!(bytes.len(), roots[0].offset + roots[0].len);
+        let labels: Vec<&str> = roots[0].children.iter().map(|node| node.label.as_str()).collect();
+        assert_eq!(vec!["a:", "1", "b:", "block! of 2 values"], labels);
+        assert_eq!("paren! of 1 values", roots[0].children[3].children[1].label);
+    }
+}
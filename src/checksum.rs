@@ -0,0 +1,92 @@
+//! CRC-32 trailers guarding documents kept on flaky media or sent over
+//! unreliable channels.
+//!
+//! This is not part of the Redbin format: the checksum follows the document
+//! as 8 more bytes, `RBCK` and the CRC-32 (IEEE) of the document, little
+//! endian. Red doesn't know about it, so strip it before giving documents to
+//! Red.
+
+use crate::error::DeError;
+#[cfg(feature = "ser")]
+use crate::error::SerError;
+
+/// Marks the trailer, telling it apart from a document without one.
+const MAGIC: &[u8; 4] = b"RBCK";
+
+const TRAILER_LEN: usize = 8;
+
+/// CRC-32 with the IEEE polynomial, as zlib and PNG compute it.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {(crc >> 1) ^ 0xEDB8_8320} else {crc >> 1};
+        }
+    }
+    !crc
+}
+
+/// Appends the checksum trailer to `document`.
+pub fn append_checksum(document: &mut Vec<u8>) {
+    let crc = crc32(document);
+    document.extend_from_slice(MAGIC);
+    document.extend_from_slice(&crc.to_le_bytes());
+}
+
+/// The document without its checksum trailer, if the checksum matches.
+pub fn strip_checksum(bytes: &[u8]) -> Result<&[u8], DeError> {
+    let split = bytes.len().checked_sub(TRAILER_LEN).ok_or(DeError::Eof)?;
+    let (document, trailer) = bytes.split_at(split);
+    if &trailer[..4] != MAGIC {
+        return Err(DeError::Message(message!("missing checksum trailer")));
+    }
+    let mut crc = [0; 4];
+    crc.copy_from_slice(&trailer[4..]);
+    if crc32(document) != u32::from_le_bytes(crc) {
+        return Err(DeError::ChecksumMismatch);
+    }
+    Ok(document)
+}
+
+/// Serializes `value` with a checksum trailer.
+#[cfg(feature = "ser")]
+pub fn to_bytes_checked<T>(value: &T) -> Result<Vec<u8>, SerError>
+where
+    T: serde::Serialize,
+{
+    let mut document = crate::to_bytes(value)?;
+    append_checksum(&mut document);
+    Ok(document)
+}
+
+/// Deserializes a document written by `to_bytes_checked`, after checking it.
+#[cfg(feature = "de")]
+pub fn from_bytes_checked<'a, T>(bytes: &'a [u8]) -> Result<T, DeError>
+where
+    T: serde::Deserialize<'a>,
+{
+    crate::from_bytes(strip_checksum(bytes)?)
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use super::{crc32, from_bytes_checked, strip_checksum, to_bytes_checked};
+    use crate::{from_bytes, DeError};
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+
+        let bytes = to_bytes_checked(&vec!["a", "b"]).unwrap();
+        assert_eq!(b"RBCK", &bytes[bytes.len() - 8..bytes.len() - 4]);
+        assert_eq!(vec!["a", "b"], from_bytes_checked::<Vec<&str>>(&bytes).unwrap());
+        assert_eq!(vec!["a", "b"], from_bytes::<Vec<&str>>(strip_checksum(&bytes).unwrap()).unwrap());
+
+        let mut corrupt = bytes.clone();
+        corrupt[20] ^= 0x01;
+        assert_eq!(Err(DeError::ChecksumMismatch), from_bytes_checked::<Vec<&str>>(&corrupt));
+        assert!(strip_checksum(&bytes[..bytes.len() - 8]).is_err());
+        assert_eq!(Err(DeError::Eof), strip_checksum(b"RBCK"));
+    }
+}
@@ -0,0 +1,313 @@
+//! Rust type definitions inferred from a sample document, as printed by
+//! the `redbin-codegen` tool.
+//!
+//! ```no_run
+//! # use redbin::{codegen::rust_types, from_file, Value};
+//! # fn main() -> redbin::Result<()> {
+//! let sample: Value = from_file("servers.redbin")?;
+//! print!("{}", rust_types(&sample, "Servers"));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Blocks of string or word keys followed by their values become structs,
+//! other blocks, and ones of strings only, become `Vec`s. Fields missing from some of the objects in
+//! a block, or holding `none`, become `Option`s. Whatever doesn't fit a
+//! single Rust type is left as a `redbin::Value`.
+
+use crate::value::Value;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// Source of `#[derive(Serialize, Deserialize)]` types able to read
+/// `sample`, the type of the whole document named `root`.
+pub fn rust_types(sample: &Value, root: &str) -> String {
+    let shape = Shape::of(sample);
+    let mut types = Types { names: BTreeSet::new(), structs: Vec::new() };
+    let mut out = String::from("use serde::{Deserialize, Serialize};\n");
+    if let Kind::Object(fields) = &shape.kind {
+        if !shape.optional {
+            types.add_struct(root, fields);
+            for source in types.structs {
+                out.push('\n');
+                out.push_str(&source);
+            }
+            return out;
+        }
+    }
+    let name = types.name(root);
+    let target = types.rust_type("item", &shape);
+    for source in types.structs {
+        out.push('\n');
+        out.push_str(&source);
+    }
+    let _ = write!(out, "\npub type {} = {};\n", name, target);
+    out
+}
+
+/// What all the samples of a value have in common.
+#[derive(Clone, Debug, PartialEq)]
+struct Shape {
+    kind: Kind,
+    /// `none` in some samples, or missing from some objects
+    optional: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Kind {
+    /// nothing but `none` seen, or no samples at all
+    Unknown,
+    Logic,
+    Integer,
+    Float,
+    Char,
+    String,
+    Binary,
+    Any,
+    Seq(Box<Shape>),
+    Object(Vec<(String, Shape)>),
+}
+
+impl Shape {
+    fn new(kind: Kind) -> Self {
+        Shape { kind, optional: false }
+    }
+
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::None => Shape { kind: Kind::Unknown, optional: true },
+            Value::Logic(_) => Shape::new(Kind::Logic),
+            Value::Integer(_) => Shape::new(Kind::Integer),
+            Value::Float(_) => Shape::new(Kind::Float),
+            Value::Char(_) => Shape::new(Kind::Char),
+            Value::String(_) => Shape::new(Kind::String),
+            Value::Binary(_) => Shape::new(Kind::Binary),
+            Value::Block(values) | Value::Paren(values) => match object_fields(values) {
+                Some(fields) => Shape::new(Kind::Object(fields)),
+                None => {
+                    let items = values.iter().map(Shape::of)
+                        .fold(Shape::new(Kind::Unknown), Shape::merge);
+                    Shape::new(Kind::Seq(Box::new(items)))
+                }
+            },
+            _ => Shape::new(Kind::Any),
+        }
+    }
+
+    /// Shape fitting both `self` and `other` samples.
+    fn merge(self, other: Shape) -> Shape {
+        let optional = self.optional || other.optional;
+        let kind = match (self.kind, other.kind) {
+            (Kind::Unknown, kind) | (kind, Kind::Unknown) => kind,
+            (Kind::Seq(a), Kind::Seq(b)) => Kind::Seq(Box::new(a.merge(*b))),
+            // an empty block, read as an object without fields
+            (Kind::Seq(items), Kind::Object(fields)) | (Kind::Object(fields), Kind::Seq(items))
+                if items.kind == Kind::Unknown && !items.optional =>
+            {
+                Kind::Object(fields.into_iter().map(|(key, shape)| (key, shape.or_missing())).collect())
+            }
+            (Kind::Object(a), Kind::Object(b)) => Kind::Object(merge_fields(a, b)),
+            (a, b) if a == b => a,
+            _ => Kind::Any,
+        };
+        Shape { kind, optional }
+    }
+
+    fn or_missing(self) -> Shape {
+        Shape { optional: true, ..self }
+    }
+}
+
+/// Keys and shapes of the values of a block of unique string or word keys,
+/// each followed by its value. A block of nothing but strings is taken for
+/// a sequence.
+fn object_fields(values: &[Value]) -> Option<Vec<(String, Shape)>> {
+    if values.is_empty() || !values.len().is_multiple_of(2) || values.iter().all(|v| matches!(v, Value::String(_))) {
+        return None;
+    }
+    let mut keys = BTreeSet::new();
+    let mut fields = Vec::new();
+    for pair in values.chunks(2) {
+        let key = match &pair[0] {
+            Value::String(key) | Value::Word(key) | Value::SetWord(key) | Value::LitWord(key) => key,
+            _ => return None,
+        };
+        if key.is_empty() || !keys.insert(key) {
+            return None;
+        }
+        fields.push((key.clone(), Shape::of(&pair[1])));
+    }
+    Some(fields)
+}
+
+/// Fields of two objects, in the order they first appear, optional unless
+/// both have them.
+fn merge_fields(a: Vec<(String, Shape)>, mut b: Vec<(String, Shape)>) -> Vec<(String, Shape)> {
+    let mut fields = Vec::new();
+    for (key, shape) in a {
+        match b.iter().position(|(other, _)| *other == key) {
+            Some(i) => {
+                let (_, other) = b.remove(i);
+                fields.push((key, shape.merge(other)));
+            }
+            None => fields.push((key, shape.or_missing())),
+        }
+    }
+    fields.extend(b.into_iter().map(|(key, shape)| (key, shape.or_missing())));
+    fields
+}
+
+/// Structs written so far, and the names taken by them.
+struct Types {
+    names: BTreeSet<String>,
+    structs: Vec<String>,
+}
+
+impl Types {
+    /// Unused type name made of `key`.
+    fn name(&mut self, key: &str) -> String {
+        let mut base = String::new();
+        for part in key.split(|c: char| !c.is_ascii_alphanumeric()).filter(|part| !part.is_empty()) {
+            let mut chars = part.chars();
+            base.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+            base.push_str(chars.as_str());
+        }
+        if !base.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            base.insert(0, 'T');
+        }
+        let mut name = base.clone();
+        let mut n = 1;
+        while self.names.contains(&name) {
+            n += 1;
+            name = format!("{}{}", base, n);
+        }
+        self.names.insert(name.clone());
+        name
+    }
+
+    /// Writes the struct for an object, and the ones for objects in it,
+    /// returning its name.
+    fn add_struct(&mut self, key: &str, fields: &[(String, Shape)]) -> String {
+        let name = self.name(key);
+        let at = self.structs.len();
+        self.structs.push(String::new());
+        let mut source = format!("#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\npub struct {} {{\n", name);
+        let mut idents = BTreeSet::new();
+        for (key, shape) in fields {
+            let mut ident = field_ident(key);
+            let base = ident.clone();
+            let mut n = 1;
+            while !idents.insert(ident.clone()) {
+                n += 1;
+                ident = format!("{}_{}", base, n);
+            }
+            let target = self.rust_type(key, shape);
+            if ident.trim_start_matches("r#") != key {
+                let _ = writeln!(source, "    #[serde(rename = {:?})]", key);
+            }
+            let _ = writeln!(source, "    pub {}: {},", ident, target);
+        }
+        source.push_str("}\n");
+        self.structs[at] = source;
+        name
+    }
+
+    /// Type for values of `shape`, found under `key`.
+    fn rust_type(&mut self, key: &str, shape: &Shape) -> String {
+        let target = match &shape.kind {
+            Kind::Unknown | Kind::Any => String::from("redbin::Value"),
+            Kind::Logic => String::from("bool"),
+            Kind::Integer => String::from("i32"),
+            Kind::Float => String::from("f64"),
+            Kind::Char => String::from("char"),
+            Kind::String => String::from("String"),
+            Kind::Binary => String::from("serde_bytes::ByteBuf"),
+            Kind::Seq(items) => format!("Vec<{}>", self.rust_type(key, items)),
+            Kind::Object(fields) => self.add_struct(key, fields),
+        };
+        option(shape, target)
+    }
+}
+
+fn option(shape: &Shape, target: String) -> String {
+    if shape.optional {
+        format!("Option<{}>", target)
+    } else {
+        target
+    }
+}
+
+const KEYWORDS: &[&str] = &["as", "async", "await", "break", "const", "continue", "dyn", "else",
+    "enum", "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use",
+    "where", "while", "abstract", "become", "box", "do", "final", "macro", "override", "priv",
+    "try", "typeof", "unsized", "virtual", "yield"];
+
+/// Snake case field name for `key`, e.g. `first_name` for `first-name`.
+fn field_ident(key: &str) -> String {
+    let mut ident: String = key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    match ident.as_str() {
+        "_" | "self" | "super" | "crate" => ident.push('_'),
+        _ if KEYWORDS.contains(&ident.as_str()) => ident.insert_str(0, "r#"),
+        _ => {}
+    }
+    ident
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rust_types;
+    use crate::parse_red;
+
+    #[test]
+    fn test_rust_types() {
+        let sample = parse_red(r#"[
+            name: "main" port: 8080 weight: 1 type: 'primary
+            first-name: "Ann" tags: ["a" "b"] limits: [cpu: 1.5]
+            servers: [
+                [host: "a" load: 0.5]
+                [host: "b" load: 1.0 backup: #[true]]
+            ]
+            note: #[none]
+        ]"#).unwrap();
+        assert_eq!(rust_types(&sample, "config"), "use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    pub name: String,
+    pub port: i32,
+    pub weight: i32,
+    pub r#type: redbin::Value,
+    #[serde(rename = \"first-name\")]
+    pub first_name: String,
+    pub tags: Vec<String>,
+    pub limits: Limits,
+    pub servers: Vec<Servers>,
+    pub note: Option<redbin::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Limits {
+    pub cpu: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Servers {
+    pub host: String,
+    pub load: f64,
+    pub backup: Option<bool>,
+}
+");
+        assert_eq!(rust_types(&parse_red("[1 2.5]").unwrap(), "Numbers"),
+            "use serde::{Deserialize, Serialize};\n\npub type Numbers = Vec<redbin::Value>;\n");
+        assert_eq!(rust_types(&parse_red("[[a: 1] [a: 2]]").unwrap(), "Points"),
+            "use serde::{Deserialize, Serialize};\n\n\
+            #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\npub struct Item {\n    pub a: i32,\n}\n\n\
+            pub type Points = Vec<Item>;\n");
+    }
+}
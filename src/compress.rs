@@ -0,0 +1,70 @@
+//! Whole documents compressed with zstd, for archiving big payloads.
+//!
+//! This is outside the Redbin format, unlike its own zlib flag: the zstd
+//! frame holds the complete document, header included. Red can't read it
+//! before it's decompressed.
+
+#[cfg(feature = "de")]
+use crate::error::DeError;
+use crate::error::text_of;
+#[cfg(feature = "ser")]
+use crate::error::SerError;
+#[cfg(feature = "de")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "ser")]
+use serde::ser::Serialize;
+
+/// Leading bytes of a zstd frame.
+#[cfg(feature = "de")]
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Serializes `value` and compresses the document with zstd's default level.
+#[cfg(feature = "ser")]
+pub fn to_bytes_zstd<T>(value: &T) -> Result<Vec<u8>, SerError>
+where
+    T: Serialize,
+{
+    to_bytes_zstd_level(value, 0)
+}
+
+/// Serializes `value` and compresses the document with zstd at `level`, 1 to
+/// 22, or 0 for the default.
+#[cfg(feature = "ser")]
+pub fn to_bytes_zstd_level<T>(value: &T, level: i32) -> Result<Vec<u8>, SerError>
+where
+    T: Serialize,
+{
+    let document = crate::to_bytes(value)?;
+    zstd::encode_all(document.as_slice(), level).map_err(|e| SerError::Io(text_of(e, "zstd compression failed")))
+}
+
+/// Deserializes a zstd-compressed document. Plain documents are read as well.
+#[cfg(feature = "de")]
+pub fn from_bytes_zstd<T>(bytes: &[u8]) -> Result<T, DeError>
+where
+    T: DeserializeOwned,
+{
+    if !bytes.starts_with(&ZSTD_MAGIC) {
+        return crate::from_bytes(bytes);
+    }
+    let document = zstd::decode_all(bytes).map_err(|e| DeError::Io(text_of(e, "zstd decompression failed")))?;
+    crate::from_bytes(&document)
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use super::{from_bytes_zstd, to_bytes_zstd, to_bytes_zstd_level};
+    use crate::to_bytes;
+
+    #[test]
+    fn test_zstd() {
+        let value: Vec<String> = (0..1000).map(|i| format!("item {}", i % 10)).collect();
+        let bytes = to_bytes_zstd(&value).unwrap();
+        assert!(bytes.len() < to_bytes(&value).unwrap().len() / 10);
+        assert_eq!(value, from_bytes_zstd::<Vec<String>>(&bytes).unwrap());
+        assert_eq!(value, from_bytes_zstd::<Vec<String>>(&to_bytes_zstd_level(&value, 19).unwrap()).unwrap());
+        // plain documents pass through
+        assert_eq!(value, from_bytes_zstd::<Vec<String>>(&to_bytes(&value).unwrap()).unwrap());
+        assert!(from_bytes_zstd::<Vec<String>>(&bytes[..bytes.len() / 2]).is_err());
+    }
+}
@@ -1,66 +1,669 @@
-use crate::error::{Error, Result};
+use crate::error::{DeError, EncodingError};
 use serde::de::{
-    self, Deserialize, DeserializeSeed, EnumAccess, IntoDeserializer,
+    self, Deserialize, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer,
     MapAccess, SeqAccess, VariantAccess, Visitor,
 };
-use std::convert::TryInto;
-use iconv::{Iconv, IconvError};
-use crate::iconv_tools::iconv;
+use std::borrow::Cow;
+use std::fmt;
+use std::marker::PhantomData;
+use std::convert::{TryFrom, TryInto};
+use std::sync::Arc;
+use crate::types::byte as types;
+use crate::types::VariantCase;
+use crate::adapters;
+use crate::encoding;
+use serde::de::value::{BorrowedStrDeserializer, CharDeserializer, MapAccessDeserializer, SeqAccessDeserializer, SeqDeserializer, StringDeserializer};
+use crate::value::{self, Value};
+
+type Result<T> = std::result::Result<T, DeError>;
+
+const SYMBOL_TABLE_FLAG: u8 = 0x04;
+
+/// Set in the third byte of a series record that refers to another one.
+const REFERENCE_FLAG: u8 = 0x08;
+
+/// What to do with a key appearing more than once in a block read as a map
+/// or struct. Keys are compared by their encoded records.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DuplicateKeys {
+    /// Hand every entry to the visitor. Maps keep the last value, derived
+    /// structs fail with a "duplicate field" error.
+    #[default]
+    Pass,
+    /// Keep the first entry, skip later ones.
+    FirstWins,
+    /// Keep the last entry, skip earlier ones.
+    LastWins,
+    /// Fail with `DeError::DuplicateKey`.
+    Error,
+}
+
+/// Kinds of values a type asks the deserializer for, to pick coercion
+/// rules by, see `DeserializerOptions::coerce`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Requested {
+    Bool,
+    /// any integer type
+    Integer,
+    /// `f32` or `f64`
+    Float,
+    Char,
+    /// `&str` or `String`
+    String,
+    /// `&[u8]` or `Vec<u8>`, e.g. with `serde_bytes`
+    Bytes,
+}
+
+/// A conversion of a Red value into one the requested type can read.
+#[derive(Clone)]
+struct Coercion {
+    /// Red type name without the `!`
+    found: String,
+    requested: Requested,
+    convert: Arc<dyn Fn(Value) -> Result<Value> + Send + Sync>,
+}
+
+impl fmt::Debug for Coercion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Coercion({}! to {:?})", self.found, self.requested)
+    }
+}
+
+/// Deserialization settings, see `from_bytes_with`.
+#[derive(Clone, Debug, Default)]
+pub struct DeserializerOptions {
+    wrap_some: bool,
+    positional_structs: bool,
+    binary_as_string: bool,
+    string_as_bytes: bool,
+    lossy_strings: bool,
+    duplicate_keys: DuplicateKeys,
+    none_as_empty: bool,
+    hyphenated_variants: bool,
+    variant_case: VariantCase,
+    strict_padding: bool,
+    unwrap_root: bool,
+    coercions: Vec<Coercion>,
+}
+
+impl DeserializerOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect `Some` values wrapped in a one-element paren, as written by
+    /// `SerializerOptions::wrap_some`.
+    pub fn wrap_some(mut self, wrap: bool) -> Self {
+        self.wrap_some = wrap;
+        self
+    }
+
+    /// Read all structs from blocks of bare values in field order, e.g.
+    /// `[12.5 "sdf"]`, like `adapters::Positional` does for single fields.
+    pub fn positional_structs(mut self, positional: bool) -> Self {
+        self.positional_structs = positional;
+        self
+    }
+
+    /// Let strings be read from `binary!` records holding valid UTF-8.
+    pub fn binary_as_string(mut self, lenient: bool) -> Self {
+        self.binary_as_string = lenient;
+        self
+    }
+
+    /// Let byte buffers be read from 1-byte unit `string!` records.
+    pub fn string_as_bytes(mut self, lenient: bool) -> Self {
+        self.string_as_bytes = lenient;
+        self
+    }
+
+    /// Replace invalid UTF-8 in 1-byte unit strings with U+FFFD, like
+    /// `String::from_utf8_lossy`, instead of failing.
+    pub fn lossy_strings(mut self, lossy: bool) -> Self {
+        self.lossy_strings = lossy;
+        self
+    }
+
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
 
+    /// Read `none` as an empty sequence or map, as Red code often uses it
+    /// for missing collections. See `SerializerOptions::empty_as_none`.
+    pub fn none_as_empty(mut self, lenient: bool) -> Self {
+        self.none_as_empty = lenient;
+        self
+    }
+
+    /// Match enum variant names ignoring hyphens and underscores too, so
+    /// that Red style `'unit-struct` selects `UnitStruct`. Variant names
+    /// always match regardless of case, as Red words do.
+    pub fn hyphenated_variants(mut self, lenient: bool) -> Self {
+        self.hyphenated_variants = lenient;
+        self
+    }
+
+    /// Match enum variant names written in `case` too, as with
+    /// `SerializerOptions::variant_case`.
+    pub fn variant_case(mut self, case: VariantCase) -> Self {
+        self.variant_case = case;
+        self
+    }
+
+    /// Only accept padding aligning the next record to 32 bits, or a
+    /// `float!` or `time!` to 64 bits as Red may write them, and fail with
+    /// `DeError::UnexpectedPadding` on any other zero bytes between records,
+    /// instead of skipping them.
+    pub fn strict_padding(mut self, strict: bool) -> Self {
+        self.strict_padding = strict;
+        self
+    }
+
+    /// Read the value of a document from inside a one-element block, as
+    /// written by `SerializerOptions::wrap_root`. Documents holding anything
+    /// else are read as they are, so a one-element block can't be read into
+    /// a sequence this way.
+    pub fn unwrap_root(mut self, unwrap: bool) -> Self {
+        self.unwrap_root = unwrap;
+        self
+    }
 
-mod types {
-    pub const NONE: u8 = 0x03;
-    pub const LOGIC: u8 = 0x04;
-    pub const BLOCK: u8 = 0x05;
-    pub const PAREN: u8 = 0x06;
-    pub const STRING: u8 = 0x07;
-    pub const CHAR: u8 = 0x0A;
-    pub const INTEGER: u8 = 0x0B;
-    pub const FLOAT: u8 = 0x0C;
-    pub const BINARY: u8 = 0x29;
+    /// Read `found` records, given by their Red type name, with `convert`
+    /// where a `requested` kind of value is expected, e.g. `issue!`s as
+    /// integers:
+    ///
+    /// ```
+    /// # use redbin::{from_bytes_with, DeserializerOptions, Requested, Value};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let bytes = redbin::to_bytes(&Value::Issue("42".to_string()))?;
+    /// let options = DeserializerOptions::new().coerce("issue!", Requested::Integer, |value| match value {
+    ///     Value::Issue(digits) => digits.parse().map(Value::Integer)
+    ///         .map_err(|_| serde::de::Error::custom(format!("not a number: #{}", digits))),
+    ///     _ => unreachable!(),
+    /// });
+    /// assert_eq!(42, from_bytes_with::<i32>(&bytes, options)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// The record is read as a `Value`, and what `convert` gives is read as
+    /// the requested kind, e.g. an `integer!` as any integer type it fits.
+    /// Rules take precedence over built-in leniency; the first one added
+    /// for a pair of types applies.
+    pub fn coerce<F>(mut self, found: &str, requested: Requested, convert: F) -> Self
+    where
+        F: Fn(Value) -> Result<Value> + Send + Sync + 'static,
+    {
+        let found = found.trim_end_matches('!').to_string();
+        self.coercions.push(Coercion { found, requested, convert: Arc::new(convert) });
+        self
+    }
 }
 
 pub struct Deserializer<'de> {
     input: &'de [u8],
-	ucs4_decoder: Iconv,
-	ucs2_decoder: Iconv,
+    options: DeserializerOptions,
+    symbol_offsets: &'de [u8],
+    symbol_names: &'de [u8],
+    /// root records of the document, where references are looked up
+    records: &'de [u8],
+    /// set by `adapters::Positional` for the next compound value
+    positional: bool,
+    /// next record is read as a `Value`, keeping its record type
+    typed: bool,
+    /// next series is read whole instead of from its head on
+    keep_head: bool,
+    /// a value for a coercion rule is being read, without applying rules
+    coercing: bool,
+    /// length of the whole document, to tell offsets of records
+    document_len: usize,
+    /// where wide strings are decoded, kept from string to string
+    scratch: String,
+    #[cfg(feature = "tracing")]
+    traced_offset: Option<usize>,
+    warnings: Vec<Warning>,
+}
+
+/// Something off about a document that didn't stop it from being read: an
+/// unknown struct field skipped, a coercion the options allow, unusual
+/// padding, an unexpected format version or a field replaced by its default
+/// by `adapters::OrDefault`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Warning {
+    /// where in the document the problem was found
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at byte {}: {}", self.offset, self.message)
+    }
 }
 
 impl<'de> Deserializer<'de> {
     pub fn from_bytes(input: &'de [u8]) -> Self {
+        Self::with_options(input, DeserializerOptions::default())
+    }
+
+    pub fn with_options(input: &'de [u8], options: DeserializerOptions) -> Self {
         Deserializer {
             input,
-			ucs4_decoder: decoder("UCS-4LE").unwrap(),
-			ucs2_decoder: decoder("UCS-2LE").unwrap(),
+            options,
+            symbol_offsets: &[],
+            symbol_names: &[],
+            records: input,
+            positional: false,
+            typed: false,
+            keep_head: false,
+            coercing: false,
+            document_len: input.len(),
+            scratch: String::new(),
+            #[cfg(feature = "tracing")]
+            traced_offset: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Points the deserializer at a new `input`, keeping its options and the
+    /// buffer wide strings are decoded in, for reading many messages in a row. Call `read_header` next if `input` is a whole document.
+    pub fn reset(&mut self, input: &'de [u8]) {
+        self.input = input;
+        self.document_len = input.len();
+        self.symbol_offsets = &[];
+        self.symbol_names = &[];
+        self.records = input;
+        self.positional = false;
+        self.typed = false;
+        self.keep_head = false;
+        #[cfg(feature = "tracing")]
+        {
+            self.traced_offset = None;
+        }
+        self.warnings.clear();
+    }
+
+    /// Reads the document header, leaving the deserializer at the first
+    /// record. Needed before `enter_block` and `skip_value`.
+    pub fn read_header(&mut self) -> Result<()> {
+        self.parse_header()
+    }
+
+    /// Steps into the block or paren at the current position, returning the
+    /// number of its values. They're read next, one after the other.
+    pub fn enter_block(&mut self) -> Result<usize> {
+        self.parse_padding()?;
+        match self.input.first() {
+            Some(&record_type @ (types::BLOCK | types::PAREN)) => {
+                Ok(self.parse_any_block_header(record_type)? as usize)
+            }
+            Some(_) => Err(DeError::ExpectedBlock),
+            None => Err(DeError::Eof),
+        }
+    }
+
+    /// Skips the next record whole, nested blocks included, using only the
+    /// lengths in record headers. Nothing in it is decoded.
+    ///
+    /// ```
+    /// # use redbin::Deserializer;
+    /// # use serde::Deserialize;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let bytes = redbin::to_bytes(&(vec![0.5; 1000], "name"))?;
+    /// let mut deserializer = Deserializer::from_bytes(&bytes);
+    /// deserializer.read_header()?;
+    /// deserializer.enter_block()?;
+    /// deserializer.skip_value()?; // a large sibling
+    /// let name = String::deserialize(&mut deserializer)?;
+    /// # assert_eq!("name", name);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn skip_value(&mut self) -> Result<()> {
+        let n = record_len(self.input)?;
+        self.input = &self.input[n..];
+        Ok(())
+    }
+
+    /// Checks that the whole input has been read, failing with
+    /// `DeError::TrailingBytes` otherwise. `from_bytes` does it after the
+    /// value; call it when driving a `Deserializer` by hand.
+    pub fn end(&self) -> Result<()> {
+        if self.input.is_empty() {
+            Ok(())
+        } else {
+            Err(DeError::TrailingBytes)
+        }
+    }
+
+    /// Warnings about the input read so far.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Offset in the document of `part`, a slice of it not read yet.
+    fn offset_of(&self, part: &[u8]) -> usize {
+        let end = self.input.as_ptr() as usize + self.input.len();
+        self.document_len - (end - part.as_ptr() as usize)
+    }
+
+    fn warn(&mut self, offset: usize, message: String) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(offset, %message, "decode warning");
+        self.warnings.push(Warning { offset, message });
+    }
+
+    /// Carries on at `rest`, a later part of the same document, e.g. the
+    /// record after one that failed to decode.
+    pub(crate) fn resume_at(&mut self, rest: &'de [u8]) {
+        self.input = rest;
+        self.positional = false;
+        self.typed = false;
+        self.keep_head = false;
+    }
+
+    /// Number of bytes read so far, the header included.
+    pub fn position(&self) -> usize {
+        self.document_len - self.input.len()
+    }
+
+    /// Bytes not read yet.
+    pub fn remaining(&self) -> &'de [u8] {
+        self.input
+    }
+
+    /// Gives up the deserializer, e.g. after reading a record from the front
+    /// of a larger buffer, for what comes after it.
+    pub fn into_remaining(self) -> &'de [u8] {
+        self.input
+    }
+}
+
+/// Red type name of a record type, without the `!`.
+fn type_name(record_type: u8) -> Option<&'static str> {
+    match record_type {
+        types::NONE => Some("none"),
+        types::LOGIC => Some("logic"),
+        types::INTEGER => Some("integer"),
+        types::FLOAT => Some("float"),
+        types::CHAR => Some("char"),
+        types::STRING => Some("string"),
+        types::BINARY => Some("binary"),
+        types::BLOCK => Some("block"),
+        t => value::kind_name(t),
+    }
+}
+
+/// Hands a value converted by a coercion rule to `visitor`.
+fn visit_coerced<'de, V: Visitor<'de>>(value: Value, visitor: V) -> Result<V::Value> {
+    match value {
+        Value::None => visitor.visit_unit(),
+        Value::Logic(b) => visitor.visit_bool(b),
+        Value::Integer(i) => visitor.visit_i32(i),
+        Value::Float(x) => visitor.visit_f64(x),
+        Value::Char(c) => visitor.visit_char(c),
+        Value::Binary(bytes) => visitor.visit_byte_buf(bytes),
+        Value::String(s) | Value::File(s) | Value::Url(s) | Value::Tag(s) | Value::Email(s) | Value::Word(s)
+            | Value::SetWord(s) | Value::LitWord(s) | Value::GetWord(s) | Value::Refinement(s)
+            | Value::Issue(s) => visitor.visit_string(s),
+        other => Err(DeError::Message(message!("coercion gave an unreadable {}", other.type_name()))),
+    }
+}
+
+fn is_word(record_type: u8) -> bool {
+    matches!(record_type, types::WORD | types::SET_WORD | types::LIT_WORD | types::GET_WORD
+        | types::REFINEMENT | types::ISSUE)
+}
+
+/// A length or position read from a record. Redbin can't declare more than
+/// 2 GiB, so negative ones come from a corrupt or foreign document.
+fn series_size(declared: i32) -> Result<usize> {
+    usize::try_from(declared).map_err(|_| DeError::TooLarge)
+}
+
+fn read_i32(bytes: &[u8], at: usize) -> i32 {
+    i32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+/// Size in bytes of the record at the start of `input`, including any
+/// padding before it and any nested records.
+pub(crate) fn record_len(input: &[u8]) -> Result<usize> {
+    let padding = input.iter().take_while(|&&b| b == 0x00).count();
+    let record = &input[padding..];
+    if record.len() < 4 {
+        return Err(DeError::Eof);
+    }
+    let count = || record.get(8..12).map(|_| read_i32(record, 8)).ok_or(DeError::Eof);
+    // a length past the input is caught below, as a truncated record
+    let series = |unit: usize, header: usize| -> Result<usize> {
+        Ok(series_size(count()?)?.checked_mul(unit)
+            .and_then(|n| n.checked_add(header + 3))
+            .map_or(usize::MAX, |n| n / 4 * 4))
+    };
+    let len = match record[0] {
+        types::NONE => 4,
+        types::LOGIC | types::INTEGER | types::CHAR => 8,
+        types::FLOAT | types::TIME | types::PAIR | types::POINT2D => 12,
+        types::DATE | types::POINT3D | types::TUPLE => 16,
+        t if is_word(t) => 16,
+        types::STRING | types::FILE | types::URL | types::TAG | types::EMAIL | types::BINARY
+            if record[2] & REFERENCE_FLAG != 0 => series(4, 12)?,
+        types::STRING | types::FILE | types::URL | types::TAG | types::EMAIL | types::BINARY =>
+            series(record[1] as usize, 12)?,
+        types::VECTOR => series(record[1] as usize, 16)?,
+        types::BLOCK | types::PAREN | types::PATH..=types::GET_PATH | types::MAP => {
+            // maps have no head, so their length comes first
+            let (mut n, count) = if record[0] == types::MAP {
+                (8, record.get(4..8).map(|_| read_i32(record, 4)).ok_or(DeError::Eof)?)
+            } else {
+                (12, count()?)
+            };
+            for _ in 0..series_size(count)? {
+                n += record_len(record.get(n..).ok_or(DeError::Eof)?)?;
+            }
+            n
         }
+        t => return Err(DeError::Message(message!("Unsupported record type {}.", t))),
+    };
+    if len > record.len() {
+        return Err(DeError::Eof);
     }
+    Ok(padding + len)
 }
 
-fn decoder(from_encoding: &str) -> std::result::Result<Iconv, IconvError> {
-	Iconv::new(from_encoding, "UTF-8")
+/// Header length and number of values of a whole block, paren, path or
+/// map record, whose values follow the header.
+pub(crate) fn nested_values(record: &[u8]) -> Option<(usize, usize)> {
+    let (at, count) = match record[0] {
+        types::BLOCK | types::PAREN | types::PATH..=types::GET_PATH => (12, read_i32(record, 8)),
+        types::MAP => (8, read_i32(record, 4)),
+        _ => return None,
+    };
+    Some((at, series_size(count).ok()?))
+}
+
+/// Whether the block at the start of `input` reads as key/value pairs: an
+/// even number of elements with a set-word in every key position, as
+/// structs are written. Maps are written as `map!`s, so blocks of strings
+/// stay sequences.
+fn is_map_like(input: &[u8]) -> Result<bool> {
+    let padding = input.iter().take_while(|&&b| b == 0x00).count();
+    let block = &input[padding..];
+    let len = block.get(8..12).map(|_| read_i32(block, 8)).ok_or(DeError::Eof)?;
+    if len == 0 || len % 2 != 0 {
+        return Ok(false);
+    }
+    let mut at = 12;
+    for i in 0..len {
+        if i % 2 == 0 {
+            at += block[at..].iter().take_while(|&&b| b == 0x00).count();
+            // the declared length may run past the last record
+            match block.get(at) {
+                Some(&types::SET_WORD) => {}
+                Some(_) => return Ok(false),
+                None => return Err(DeError::Eof),
+            }
+        }
+        at += record_len(&block[at..])?;
+    }
+    Ok(true)
+}
+
+/// For each entry of the `len`-element map at the start of `input`, whether
+/// `policy` drops it.
+fn skipped_entries(input: &[u8], len: i32, policy: DuplicateKeys) -> Result<Vec<bool>> {
+    if policy == DuplicateKeys::Pass {
+        return Ok(Vec::new());
+    }
+    let mut keys = Vec::new();
+    let mut at = 0;
+    for i in 0..len {
+        let n = record_len(&input[at..])?;
+        if i % 2 == 0 {
+            let padding = input[at..].iter().take_while(|&&b| b == 0x00).count();
+            keys.push(&input[at + padding..at + n]);
+        }
+        at += n;
+    }
+    (0..keys.len()).map(|i| {
+        let earlier = keys[..i].contains(&keys[i]);
+        let later = keys[i + 1..].contains(&keys[i]);
+        match policy {
+            DuplicateKeys::Error if earlier => Err(DeError::DuplicateKey),
+            DuplicateKeys::FirstWins => Ok(earlier),
+            DuplicateKeys::LastWins => Ok(later),
+            _ => Ok(false),
+        }
+    }).collect()
 }
 
 
+/// Reads a value from a whole Redbin document.
+///
+/// Types made only of borrowed `&str` and `&[u8]`, numbers, fixed-size
+/// arrays, tuples and structs of these are read without any heap allocation,
+/// as long as their strings are stored with 1-byte units, which is what
+/// `to_bytes` produces for ASCII text. The same holds for `from_bytes_with`
+/// unless a `DuplicateKeys` policy is set.
+///
+/// Struct fields left out of a block, like the trailing ones Red code often
+/// omits, take their `#[serde(default)]` values, as do fields past the end
+/// of a shorter block read by `adapters::Positional`.
 pub fn from_bytes<'de, T>(s: &'de [u8]) -> Result<T>
 where
     T: Deserialize<'de>,
 {
-    let mut deserializer = Deserializer::from_bytes(s);
+    from_bytes_with(s, DeserializerOptions::default())
+}
+
+/// Reads a value that owns all its data from a whole Redbin document, so
+/// `s` can be reused or dropped right after, e.g. a network buffer.
+/// `from_bytes` gives the same for owned types; this one rejects types
+/// borrowing from the input at compile time.
+pub fn from_slice_owned<T>(s: &[u8]) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    from_bytes(s)
+}
+
+/// Like `from_bytes_with`, but `adapters::SharedBytes` fields come out as
+/// cheap slices of `bytes` instead of copies.
+#[cfg(feature = "bytes")]
+pub fn from_shared_bytes<'de, T>(bytes: &'de bytes::Bytes, options: DeserializerOptions) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    adapters::with_shared_source(bytes, || from_bytes_with(bytes, options))
+}
+
+pub fn from_bytes_with<'de, T>(s: &'de [u8], options: DeserializerOptions) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    from_bytes_seed(s, PhantomData, options)
+}
+
+/// Reads a whole Redbin document with a stateful `seed`, e.g.
+/// `stream::WriteBinary`.
+pub fn from_bytes_seed<'de, S>(s: &'de [u8], seed: S, options: DeserializerOptions) -> Result<S::Value>
+where
+    S: DeserializeSeed<'de>,
+{
+    read_document(s, seed, options).map(|(value, _)| value)
+}
+
+/// Like `from_bytes_with`, also giving the warnings about the document, see
+/// `Warning`.
+pub fn from_bytes_with_warnings<'de, T>(s: &'de [u8], options: DeserializerOptions) -> Result<(T, Vec<Warning>)>
+where
+    T: Deserialize<'de>,
+{
+    read_document(s, PhantomData, options)
+}
+
+fn read_document<'de, S>(s: &'de [u8], seed: S, options: DeserializerOptions) -> Result<(S::Value, Vec<Warning>)>
+where
+    S: DeserializeSeed<'de>,
+{
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("redbin::decode", size = s.len()).entered();
+    adapters::with_shared_series(|| read_records(s, seed, options))
+}
+
+fn read_records<'de, S>(s: &'de [u8], seed: S, options: DeserializerOptions) -> Result<(S::Value, Vec<Warning>)>
+where
+    S: DeserializeSeed<'de>,
+{
+    let mut deserializer = Deserializer::with_options(s, options);
     deserializer.parse_header()?;
-    let t = T::deserialize(&mut deserializer)?;
-    if deserializer.input.is_empty() {
-        Ok(t)
-    } else {
-        Err(Error::TrailingBytes)
+    deserializer.parse_padding()?;
+    if deserializer.options.unwrap_root && deserializer.input.get(..12).is_some_and(|header| {
+        header[0] == types::BLOCK && read_i32(header, 8) == 1
+    }) {
+        deserializer.parse_block_header()?;
+    }
+    let result = seed.deserialize(&mut deserializer);
+    #[cfg(feature = "tracing")]
+    if let Err(error) = &result {
+        tracing::debug!(offset = deserializer.position(), %error, "decode failed");
     }
+    let t = result?;
+    deserializer.end()?;
+    Ok((t, deserializer.warnings))
 }
 
 
 impl<'de> Deserializer<'de> {
 
     fn parse_padding(&mut self) -> Result<()> {
-        while self.input.len() > 0 && self.input[0] == 0x00 {
-            self.input = &self.input[1..];
+        let padding = self.input.iter().take_while(|&&b| b == 0x00).count();
+        if self.options.strict_padding && padding < self.input.len() {
+            let (at, next) = (self.position(), self.input[padding]);
+            let aligned = at + padding == at.next_multiple_of(4)
+                || (matches!(next, types::FLOAT | types::TIME) && at + padding == at.next_multiple_of(8));
+            if !aligned {
+                return Err(DeError::UnexpectedPadding { offset: at });
+            }
+        }
+        if padding > 3 {
+            // records are aligned to 32 bits, so padding takes 3 bytes at most
+            self.warn(self.position(), format!("{} bytes of padding", padding));
+        }
+        self.input = &self.input[padding..];
+        #[cfg(feature = "tracing")]
+        if let Some(&record_type) = self.input.first() {
+            // records are looked at more than once on their way to a visitor
+            let offset = self.position();
+            if self.traced_offset != Some(offset) {
+                self.traced_offset = Some(offset);
+                tracing::trace!(offset, record_type, "decode record");
+            }
         }
         Ok(())
     }
@@ -72,30 +675,244 @@ impl<'de> Deserializer<'de> {
             0x01, 0x00, 0x00, 0x00,  // length (number of records)
             0x08, 0x00, 0x00, 0x00]  // size of payload
             .len();
+        if self.input.len() < header_len {
+            return Err(DeError::Eof);
+        }
+        if &self.input[..6] != b"REDBIN" {
+            return Err(DeError::Syntax);
+        }
+        if self.input[6] != 2 {
+            self.warn(6, format!("Redbin version {}, expected 2", self.input[6]));
+        }
+        let flags = self.input[7];
         self.input = &self.input[header_len..];
+        if flags & SYMBOL_TABLE_FLAG != 0 {
+            self.parse_symbol_table()?;
+        }
+        self.records = self.input;
+        Ok(())
+    }
+
+    fn parse_symbol_table(&mut self) -> Result<()> {
+        if self.input.len() < 8 {
+            return Err(DeError::Eof);
+        }
+        let count = read_i32(self.input, 0) as usize;
+        let size = read_i32(self.input, 4) as usize;
+        let strings_at = count.checked_mul(4).and_then(|n| n.checked_add(8)).ok_or(DeError::Syntax)?;
+        if strings_at.checked_add(size).is_none_or(|end| end > self.input.len()) {
+            return Err(DeError::Eof);
+        }
+        self.symbol_offsets = &self.input[8..strings_at];
+        self.symbol_names = &self.input[strings_at..strings_at + size];
+        self.input = &self.input[strings_at + size..];
         Ok(())
     }
+
+    /// Name of the `index`th symbol, looked up in place.
+    fn symbol(&self, index: usize) -> Result<&'de str> {
+        if index >= self.symbol_offsets.len() / 4 {
+            return Err(DeError::Message(message!("Symbol {} not in symbol table.", index)));
+        }
+        let offset = read_i32(self.symbol_offsets, index * 4) as usize;
+        let name = self.symbol_names.get(offset..).ok_or(DeError::Syntax)?;
+        let end = name.iter().position(|&b| b == 0x00).ok_or(DeError::Syntax)?;
+        std::str::from_utf8(&name[..end]).map_err(|e| DeError::Encoding(EncodingError::new("symbol isn't UTF-8", e)))
+    }
+
+    /// The type of the record next.
+    fn peek(&self) -> Result<u8> {
+        self.input.first().copied().ok_or(DeError::Eof)
+    }
+
+    /// The first `len` bytes of the record next, failing if it's cut short.
+    fn fixed(&self, len: usize) -> Result<&'de [u8]> {
+        self.input.get(..len).ok_or(DeError::Eof)
+    }
+
+    fn parse_word(&mut self, record_type: u8) -> Result<&'de str> {
+        self.parse_padding()?;
+        if self.peek()? == record_type {
+            let symbol = read_i32(self.fixed(16)?, 4) as usize;
+            self.input = &self.input[16..];
+            self.symbol(symbol)
+        } else {
+            Err(DeError::ExpectedWord)
+        }
+    }
+
+    /// Elements of the series record at the start of the input, from its
+    /// head on unless `whole`, moving past the record. Declared sizes are
+    /// checked against the input before anything is sliced.
+    fn parse_series_data(&mut self, header_len: usize, whole: bool) -> Result<&'de [u8]> {
+        if self.input.len() < header_len {
+            return Err(DeError::Eof);
+        }
+        let unit = self.input[1] as usize;
+        let head = series_size(read_i32(self.input, 4))?;
+        let length = series_size(read_i32(self.input, 8))?;
+        let n = length.checked_mul(unit).ok_or(DeError::TooLarge)?;
+        let start = if whole {0} else {head.checked_mul(unit).ok_or(DeError::TooLarge)?};
+        let data = self.input[header_len..].get(..n).ok_or(DeError::Eof)?;
+        let bytes = data.get(start..).ok_or(DeError::Syntax)?;
+        self.input = &self.input[header_len + n..];
+        self.parse_padding()?;
+        Ok(bytes)
+    }
+
+    /// Unit and elements of the string or binary record at the start of the
+    /// input, following it if it's a reference.
+    fn parse_series(&mut self) -> Result<(usize, &'de [u8])> {
+        let keep_head = std::mem::take(&mut self.keep_head);
+        if self.input.get(2).is_none_or(|&flags| flags & REFERENCE_FLAG == 0) {
+            let unit = *self.fixed(2)?.last().unwrap() as usize;
+            return Ok((unit, self.parse_series_data(12, keep_head)?));
+        }
+        let (target, head) = self.parse_reference()?;
+        let unit = target[1] as usize;
+        let length = series_size(read_i32(target, 8))?;
+        let data = &target[12..12 + length * unit];
+        let start = if keep_head {0} else {head.checked_mul(unit).ok_or(DeError::TooLarge)?};
+        let bytes = data.get(start..).ok_or(DeError::Syntax)?;
+        self.parse_padding()?;
+        Ok((unit, bytes))
+    }
+
+    /// Moves past the reference record at the start of the input, giving
+    /// the series record it refers to and the reference's own head.
+    ///
+    /// A reference has the header of the series it refers to, with
+    /// `REFERENCE_FLAG` set, then a head position, a count and that many
+    /// zero-based indexes: of a root record first, then of a value in each
+    /// nested block or map down to the series.
+    fn parse_reference(&mut self) -> Result<(&'de [u8], usize)> {
+        let n = record_len(self.input)?;
+        let head = series_size(read_i32(self.input, 4))?;
+        let path = &self.input[12..n];
+        let target = self.referenced(path)?;
+        if target[0] != self.input[0] || target[2] & REFERENCE_FLAG != 0 {
+            return Err(DeError::Message(message!("Reference at byte {} to an unexpected record.", self.position())));
+        }
+        self.input = &self.input[n..];
+        Ok((target, head))
+    }
+
+    /// The record at the end of a reference's `path`, checked to be whole.
+    fn referenced(&self, path: &[u8]) -> Result<&'de [u8]> {
+        let invalid = || DeError::Message(message!("Invalid reference path."));
+        let mut record: Option<&'de [u8]> = None;
+        for index in path.chunks_exact(4).map(|index| read_i32(index, 0)) {
+            let index = series_size(index)?;
+            let mut values = match record {
+                None => self.records,
+                Some(record) => {
+                    let (at, count) = nested_values(record).ok_or_else(invalid)?;
+                    if index >= count {
+                        return Err(invalid());
+                    }
+                    &record[at..]
+                }
+            };
+            for _ in 0..index {
+                values = &values[record_len(values)?..];
+            }
+            let padding = values.iter().take_while(|&&b| b == 0x00).count();
+            let found = &values[padding..];
+            record = Some(&found[..record_len(found)?]);
+        }
+        record.ok_or_else(invalid)
+    }
+
+    /// Where the series `adapters::Shared` reads next is stored, after
+    /// following references, and from which head on.
+    fn series_key(&self) -> Option<(usize, usize)> {
+        let record = self.input;
+        if !matches!(*record.first()?, types::STRING | types::FILE | types::URL | types::TAG | types::EMAIL
+            | types::BINARY) || record.len() < 12 {
+            return None;
+        }
+        let head = series_size(read_i32(record, 4)).ok()?;
+        if record[2] & REFERENCE_FLAG == 0 {
+            return Some((self.position(), head));
+        }
+        let n = record_len(record).ok()?;
+        let target = self.referenced(&record[12..n]).ok()?;
+        Some((self.offset_of(target), head))
+    }
+
+    /// Vector element type, unit and packed elements.
+    fn parse_vector(&mut self) -> Result<(u8, usize, &'de [u8])> {
+        self.parse_padding()?;
+        if self.peek()? == types::VECTOR {
+            let element_type = *self.input.get(12).ok_or(DeError::Eof)?;
+            let unit = self.input[1] as usize;
+            if !matches!(unit, 1 | 2 | 4 | 8) {
+                return Err(DeError::Message(message!("Unexpected unit size {}.", unit)));
+            }
+            let bytes = self.parse_series_data(16, false)?;
+            Ok((element_type, unit, bytes))
+        } else {
+            Err(DeError::ExpectedVector)
+        }
+    }
     
     fn parse_integer(&mut self) -> Result<i32> {
         self.parse_padding()?;
-        if &self.input[..4] == &[types::INTEGER, 0x00, 0x00, 0x00] {
-            let bytes = &self.input[4..8];
-            //println!("bytes: {:?}", bytes); // DEBUG
+        if self.input.get(..4) == Some(&[types::INTEGER, 0x00, 0x00, 0x00]) {
+            let bytes = &self.fixed(8)?[4..];
             self.input = &self.input[8..];
             Ok(i32::from_le_bytes(bytes.try_into().unwrap()))
         } else {
-            Err(Error::ExpectedInteger)
+            Err(DeError::ExpectedInteger)
         }
     }
     
+    /// An integer written as a decimal `string!`, as with
+    /// `SerializerOptions::large_integers_as_string`, if one is next.
+    fn parse_decimal<T: std::str::FromStr>(&mut self) -> Result<Option<T>> {
+        self.parse_padding()?;
+        if self.input.first() != Some(&types::STRING) {
+            return Ok(None);
+        }
+        let text = self.parse_string()?;
+        text.parse().map(Some)
+            .map_err(|_| DeError::Message(message!("invalid integer string {:?}", text)))
+    }
+
     fn parse_any_block_header(&mut self, record_type: u8) -> Result<i32> {
         self.parse_padding()?;
-        if &self.input[..4] == &[record_type, 0x00, 0x00, 0x00] {
-            let len = &self.input[8..12];
+        self.keep_head = false; // blocks are always read whole
+        if self.input.get(..4) == Some(&[record_type, 0x00, 0x00, 0x00]) {
+            let len = &self.fixed(12)?[8..];
             self.input = &self.input[12..];
-            Ok(i32::from_le_bytes(len.try_into().unwrap()))
+            self.values_count(i32::from_le_bytes(len.try_into().unwrap()))
+        } else {
+            Err(DeError::ExpectedBlock)
+        }
+    }
+
+    /// Number of values a block declares, checked against the input left,
+    /// as every record takes at least 4 bytes. Size hints given to visitors
+    /// come from it.
+    fn values_count(&self, len: i32) -> Result<i32> {
+        if series_size(len)? > self.input.len() / 4 {
+            return Err(DeError::Eof);
+        }
+        Ok(len)
+    }
+
+    /// Number of keys and values of a `map!`.
+    fn parse_map_header(&mut self) -> Result<i32> {
+        self.parse_padding()?;
+        if self.input.get(..4) == Some(&[types::MAP, 0x00, 0x00, 0x00]) && self.input.len() >= 8 {
+            let len = read_i32(self.input, 4);
+            self.input = &self.input[8..];
+            if len % 2 != 0 {
+                return Err(DeError::ExpectedEvenLength);
+            }
+            self.values_count(len)
         } else {
-            Err(Error::ExpectedBlock)
+            Err(DeError::ExpectedBlock)
         }
     }
 
@@ -107,174 +924,320 @@ impl<'de> Deserializer<'de> {
         self.parse_any_block_header(types::PAREN)
     }
 
+    /// Length of a `block!`, or of a `paren!`, which Red data often groups
+    /// values with too.
+    fn parse_list_header(&mut self) -> Result<i32> {
+        self.parse_padding()?;
+        match self.input.first() {
+            Some(&types::PAREN) => self.parse_paren_header(),
+            _ => self.parse_block_header(),
+        }
+    }
+
     fn parse_logic(&mut self) -> Result<bool> {
         self.parse_padding()?;
-        if &self.input[..4] == &[types::LOGIC, 0x00, 0x00, 0x00] {
-            let bytes = &self.input[4..8];
+        if self.input.get(..4) == Some(&[types::LOGIC, 0x00, 0x00, 0x00]) {
+            let bytes = &self.fixed(8)?[4..];
             self.input = &self.input[8..];
             Ok(i32::from_le_bytes(bytes.try_into().unwrap()) != 0)
         } else {
-            Err(Error::ExpectedLogic)
+            Err(DeError::ExpectedLogic)
         }
     }
 
     fn parse_float(&mut self) -> Result<f64> {
+        self.parse_any_float(types::FLOAT)
+    }
+
+    fn parse_any_float(&mut self, record_type: u8) -> Result<f64> {
         self.parse_padding()?;
-        if &self.input[..4] == &[types::FLOAT, 0x00, 0x00, 0x00] {
-            let bytes = [&self.input[8..12], &self.input[4..8]].concat(); // swap words
+        if self.input.get(..4) == Some(&[record_type, 0x00, 0x00, 0x00]) {
+            let record = self.fixed(12)?;
+            let mut bytes = [0x00; 8];
+            bytes[..4].copy_from_slice(&record[8..12]); // swap words
+            bytes[4..].copy_from_slice(&record[4..8]);
             self.input = &self.input[12..];
-            Ok(f64::from_le_bytes(bytes.try_into().unwrap()))
+            Ok(f64::from_le_bytes(bytes))
         } else {
-            Err(Error::ExpectedFloat)
+            Err(DeError::ExpectedFloat)
         }
     }
 
-    fn parse_s<S, F1, F2, F4>(&mut self, f1: F1, f2: F2, f4: F4) -> Result<S>
+    fn parse_s<S, F1, F2, F4>(&mut self, record_type: u8, f1: F1, f2: F2, f4: F4) -> Result<S>
     where
         F1: FnOnce(&'de [u8], &mut Deserializer<'de>) -> Result<S>,
         F2: FnOnce(&'de [u8], &mut Deserializer<'de>) -> Result<S>,
         F4: FnOnce(&'de [u8], &mut Deserializer<'de>) -> Result<S>,
     {
         self.parse_padding()?;
-        if self.input[0] == types::STRING {
-            let unit: usize = self.input[1] as usize;
-            let refer: bool = (&self.input[2] & 0b_00001000) != 0;
-            if refer {
-                unimplemented!("Redbin references not supported yet.");
+        if self.peek()? == record_type {
+            let (unit, bytes) = self.parse_series()?;
+            if unit == 1 {
+                f1(bytes, self)
+            } else if cfg!(feature = "ascii-only") {
+                Err(DeError::Message(message!("UCS-{} string! read by an ascii-only build", unit)))
             } else {
-                let head: usize = i32::from_le_bytes((&self.input[4..8]).try_into().unwrap()) as usize;
-                let length: usize = i32::from_le_bytes((&self.input[8..12]).try_into().unwrap()) as usize;
-                self.input = &self.input[12..];
-
-                let n = length * unit;
-                let bytes = &self.input[head..n];
-
-                self.input = &self.input[n..];
-                self.parse_padding()?;
-                
-                if unit == 1 {
-                    f1(bytes, self)
+                if unit == 2 {
+                    f2(bytes, self)
                 } else {
-                    if unit == 2 {
-                        f2(bytes, self)
-                    } else {
-                        f4(bytes, self)
-                    }
+                    f4(bytes, self)
                 }
             }
         } else {
-            Err(Error::ExpectedString)
+            Err(DeError::ExpectedString)
+        }
+    }
+
+    /// Text of a 1-byte unit string.
+    fn utf8(&mut self, bytes: &'de [u8]) -> Result<Cow<'de, str>> {
+        match std::str::from_utf8(bytes) {
+            Ok(s) => Ok(Cow::Borrowed(s)),
+            Err(e) if self.options.lossy_strings => {
+                let offset = self.offset_of(&bytes[e.valid_up_to()..]);
+                self.warn(offset, "invalid UTF-8 replaced with U+FFFD".to_string());
+                Ok(String::from_utf8_lossy(bytes))
+            }
+            Err(e) => Err(DeError::Encoding(EncodingError::new("invalid UTF-8 in string!", e))),
         }
     }
 
     #[allow(unused)]
-    fn parse_str(&mut self) -> Result<&'de str> {
-        self.parse_s(
-            |bytes, de| std::str::from_utf8(bytes).map_err(|e| Error::Message(e.to_string())),
-            |bytes, de| Err(Error::Message(String::from(
+    fn parse_str(&mut self) -> Result<Cow<'de, str>> {
+        self.parse_s(types::STRING,
+            |bytes, de| de.utf8(bytes),
+            |bytes, de| Err(DeError::Message(message!(
                 "Deserialization into &str possible only for ASCII (unit=1) Redbin strings."))),
-            |bytes, de| Err(Error::Message(String::from(
+            |bytes, de| Err(DeError::Message(message!(
                 "Deserialization into &str possible only for ASCII (unit=1) Redbin strings."))),
         )
     }
 
     fn parse_string(&mut self) -> Result<String> {
-        self.parse_s(
-            |bytes, _de| String::from_utf8(bytes.to_vec()).map_err(|e| Error::Message(e.to_string())),
-            |bytes, de| de.ucs2_decode(bytes).map_err(|e| Error::Message(e.to_string())),
-            |bytes, de| de.ucs4_decode(bytes).map_err(|e| Error::Message(e.to_string()))
+        self.parse_any_string(types::STRING)
+    }
+
+    fn parse_any_string(&mut self, record_type: u8) -> Result<String> {
+        self.parse_s(record_type,
+            |bytes, de| de.utf8(bytes).map(Cow::into_owned),
+            |bytes, _| encoding::decode_ucs2(bytes),
+            |bytes, _| encoding::decode_ucs4(bytes)
         )
     }
+
+    /// Visits the text of a string, borrowed from the input if it's of
+    /// 1-byte units, else decoded into `scratch`, cleared and reused.
+    fn visit_any_string<V>(&mut self, record_type: u8, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let text = self.parse_s(record_type,
+            |bytes, de| de.utf8(bytes).map(Some),
+            |bytes, de| {
+                de.scratch.clear();
+                encoding::decode_ucs2_into(bytes, &mut de.scratch).map(|_| None)
+            },
+            |bytes, de| {
+                de.scratch.clear();
+                encoding::decode_ucs4_into(bytes, &mut de.scratch).map(|_| None)
+            },
+        )?;
+        match text {
+            Some(Cow::Borrowed(s)) => visitor.visit_borrowed_str(s),
+            Some(Cow::Owned(s)) => visitor.visit_string(s),
+            None => visitor.visit_str(&self.scratch),
+        }
+    }
     
     fn parse_char(&mut self) -> Result<char> {
         self.parse_padding()?;
-        if self.input[0] == types::CHAR {
-            let bytes = &self.input[4..8];
+        if self.peek()? == types::CHAR {
+            let code = read_i32(self.fixed(8)?, 4) as u32;
             self.input = &self.input[8..];
-            self.ucs4_decode(bytes)
-                .map(|v| v.chars().next().unwrap())
-                .map_err(|e| Error::Message(e.to_string()))
+            char::from_u32(code).ok_or_else(|| DeError::Message(message!("invalid char! code point {:#X}", code)))
         } else {
-            Err(Error::ExpectedChar)
+            Err(DeError::ExpectedChar)
         }
     }
 
     fn parse_binary(&mut self) -> Result<&'de [u8]> {
         self.parse_padding()?;
-        if self.input[0] == types::BINARY {
-            let unit: usize = self.input[1] as usize;
-            let refer: bool = (&self.input[2] & 0b_00001000) != 0;
-            if refer {
-                unimplemented!("Redbin references not supported yet.");
+        if self.peek()? == types::BINARY {
+            let (unit, bytes) = self.parse_series()?;
+            if unit == 1 {
+                Ok(bytes)
             } else {
-                let head: usize = i32::from_le_bytes((&self.input[4..8]).try_into().unwrap()) as usize;
-                let length: usize = i32::from_le_bytes((&self.input[8..12]).try_into().unwrap()) as usize;
-                self.input = &self.input[12..];
+                Err(DeError::Message(message!("Unexpected unit size {}.", unit)))
+            }
+        } else {
+            Err(DeError::ExpectedBinary)
+        }
+    }
 
-                let n = length * unit;
-                let bytes = &self.input[head..n];
+    /// `pair!`, `point2D!` or `point3D!` coordinates, as vector element type,
+    /// unit and packed coordinates.
+    fn parse_point(&mut self) -> Result<(u8, usize, &'de [u8])> {
+        self.parse_padding()?;
+        let (element_type, n) = match self.peek()? {
+            types::PAIR => (types::INTEGER, 2),
+            types::POINT2D => (types::FLOAT, 2),
+            types::POINT3D => (types::FLOAT, 3),
+            _ => return Err(DeError::ExpectedPoint),
+        };
+        let bytes = &self.fixed(4 + n * 4)?[4..];
+        self.input = &self.input[4 + n * 4..];
+        Ok((element_type, 4, bytes))
+    }
 
-                self.input = &self.input[n..];
-                self.parse_padding()?;
-                
-                if unit == 1 {
-                    Ok(bytes)
-                } else {
-                    unimplemented!("Unexpected unit size <> 1.");
-                }
-            }
+    /// `tuple!` components; the record always carries 12 bytes of storage.
+    fn parse_tuple(&mut self) -> Result<&'de [u8]> {
+        self.parse_padding()?;
+        if self.peek()? == types::TUPLE {
+            let record = self.fixed(16)?;
+            let size = (record[1] as usize).min(12);
+            let bytes = &record[4..4 + size];
+            self.input = &self.input[16..];
+            Ok(bytes)
         } else {
-            Err(Error::ExpectedBinary)
+            Err(DeError::ExpectedTuple)
         }
     }
 
-    fn parse_binary_owned(&mut self) -> Result<Vec<u8>> {
-        self.parse_binary().map(|bytes| bytes.to_vec())
+    /// `binary!` read as a string, if allowed by the options
+    fn parse_binary_text(&mut self) -> Result<Option<&'de str>> {
+        self.parse_padding()?;
+        if self.options.binary_as_string && self.input.first() == Some(&types::BINARY) {
+            self.warn(self.position(), "binary! read as a string".to_string());
+            let bytes = self.parse_binary()?;
+            std::str::from_utf8(bytes).map(Some).map_err(|e| DeError::Encoding(EncodingError::new("binary! isn't UTF-8 text", e)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// `binary!`, or `string!` if allowed by the options
+    fn parse_bytes(&mut self) -> Result<&'de [u8]> {
+        self.parse_padding()?;
+        if self.options.string_as_bytes && self.input.first() == Some(&types::STRING) {
+            self.warn(self.position(), "string! read as bytes".to_string());
+            let wide = || Err(DeError::Message(message!(
+                "Deserialization into bytes possible only for unit=1 Redbin strings.")));
+            self.parse_s(types::STRING, |bytes, _de| Ok(bytes), |_, _| wide(), |_, _| wide())
+        } else {
+            self.parse_binary()
+        }
     }
     
+    /// Skips a `none` standing for an empty collection, if there is one and
+    /// that's allowed.
+    fn parse_none_as_empty(&mut self) -> Result<bool> {
+        self.parse_padding()?;
+        if self.options.none_as_empty && self.input.first() == Some(&types::NONE) {
+            self.warn(self.position(), "none read as an empty collection".to_string());
+            self.input = &self.input[self.fixed(4)?.len()..];
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// The next value converted by the first coercion rule for its type and
+    /// `requested`, if there is one.
+    fn parse_coerced(&mut self, requested: Requested) -> Result<Option<Value>> {
+        if self.options.coercions.is_empty() || self.coercing {
+            return Ok(None);
+        }
+        self.parse_padding()?;
+        let Some(found) = self.input.first().and_then(|&t| type_name(t)) else { return Ok(None) };
+        let Some(coercion) = self.options.coercions.iter()
+            .find(|coercion| coercion.found == found && coercion.requested == requested)
+            .cloned() else { return Ok(None) };
+        self.warn(self.position(), format!("{}! coerced to {:?}", found, requested));
+        self.coercing = true;
+        let value = Value::deserialize(&mut *self);
+        self.coercing = false;
+        (coercion.convert)(value?).map(Some)
+    }
+
     fn parse_none(&mut self) -> Result<()> {
         self.parse_padding()?;
-        if self.input[0] == types::NONE {
-            self.input = &self.input[4..];
+        if self.peek()? == types::NONE {
+            self.input = &self.input[self.fixed(4)?.len()..];
             Ok(())
         } else {
-            Err(Error::ExpectedNone)
+            Err(DeError::ExpectedNone)
         }
     }
-
-	fn ucs4_decode(&mut self, input: &[u8]) -> std::result::Result<String, IconvError> {
-		decode(&mut self.ucs4_decoder, input)
-	}
-	
-	fn ucs2_decode(&mut self, input: &[u8]) -> std::result::Result<String, IconvError> {
-		decode(&mut self.ucs2_decoder, input)
-	}
 }
 
-
-/// convert `input` from `encoding` to UTF-8
-fn decode(c: &mut Iconv, input: &[u8]) -> std::result::Result<String, IconvError> {
-	iconv(c, input).map(|v| unsafe { String::from_utf8_unchecked(v) })
-}
-
-
-impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
-    type Error = Error;
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = DeError;
 
     // Look at the input data to decide what Serde data model type to
     // deserialize as. Not all data formats are able to support this operation.
     // Formats that support `deserialize_any` are known as self-describing.
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    //
+    // Blocks hold both sequences and structs, whose keys are set-words. A
+    // block is visited as a map when `is_map_like` finds those keys, which is
+    // what internally tagged enums and other buffered content need; maps are
+    // `map!`s.
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!();
+        self.parse_padding()?;
+        if self.input.is_empty() {
+            return Err(DeError::Eof);
+        }
+        if std::mem::take(&mut self.typed) {
+            return self.deserialize_typed(visitor);
+        }
+        match self.input[0] {
+            types::NONE => {
+                self.parse_none()?;
+                visitor.visit_unit()
+            }
+            types::LOGIC => self.deserialize_bool(visitor),
+            types::INTEGER => self.deserialize_i32(visitor),
+            types::FLOAT => self.deserialize_f64(visitor),
+            types::CHAR => self.deserialize_char(visitor),
+            types::STRING => {
+                if self.input.get(1) == Some(&1) {
+                    self.deserialize_str(visitor)
+                } else {
+                    self.deserialize_string(visitor)
+                }
+            }
+            types::BINARY => self.deserialize_bytes(visitor),
+            types::MAP => self.deserialize_map(visitor),
+            t if is_word(t) => visitor.visit_borrowed_str(self.parse_word(t)?),
+            types::BLOCK => {
+                if is_map_like(self.input)? {
+                    self.deserialize_map(visitor)
+                } else {
+                    self.deserialize_seq(visitor)
+                }
+            }
+            types::PAREN => {
+                // enum variant, as `deserialize_enum` expects it
+                let len = self.parse_paren_header()?;
+                if len == 1 {
+                    self.visit_any_string(types::STRING, visitor)
+                } else if len == 2 {
+                    visitor.visit_map(BlockData::new(self, len))
+                } else {
+                    Err(DeError::ExpectedEnum)
+                }
+            }
+            t => Err(DeError::Message(message!("Unsupported record type {}.", t))),
+        }
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        if let Some(value) = self.parse_coerced(Requested::Bool)? {
+            return visit_coerced(value, visitor);
+        }
         visitor.visit_bool(self.parse_logic()?)
     }
 
@@ -282,10 +1245,13 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        if let Some(value) = self.parse_coerced(Requested::Integer)? {
+            return visit_coerced(value, visitor);
+        }
         let v = self.parse_integer()?;
         if v > (i8::MAX as i32)
                 || v < (i8::MIN as i32) {
-            Err(Error::Message(String::from("i8 limit exceeded")))
+            Err(DeError::Message(message!("i8 limit exceeded")))
         } else {
             visitor.visit_i8(v as i8)
         }
@@ -295,10 +1261,13 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        if let Some(value) = self.parse_coerced(Requested::Integer)? {
+            return visit_coerced(value, visitor);
+        }
         let v = self.parse_integer()?;
         if v > (i16::MAX as i32)
                 || v < (i16::MIN as i32) {
-            Err(Error::Message(String::from("i16 limit exceeded")))
+            Err(DeError::Message(message!("i16 limit exceeded")))
         } else {
             visitor.visit_i16(v as i16)
         }
@@ -308,6 +1277,9 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        if let Some(value) = self.parse_coerced(Requested::Integer)? {
+            return visit_coerced(value, visitor);
+        }
         visitor.visit_i32(self.parse_integer()?)
     }
 
@@ -315,6 +1287,12 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        if let Some(value) = self.parse_coerced(Requested::Integer)? {
+            return visit_coerced(value, visitor);
+        }
+        if let Some(v) = self.parse_decimal()? {
+            return visitor.visit_i64(v);
+        }
         visitor.visit_i64(self.parse_integer()? as i64)
     }
 
@@ -322,10 +1300,13 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        if let Some(value) = self.parse_coerced(Requested::Integer)? {
+            return visit_coerced(value, visitor);
+        }
         let v = self.parse_integer()?;
         if v > (u8::MAX as i32)
                 || v < (u8::MIN as i32) {
-            Err(Error::Message(String::from("u8 limit exceeded")))
+            Err(DeError::Message(message!("u8 limit exceeded")))
         } else {
             visitor.visit_u8(v as u8)
         }
@@ -335,10 +1316,13 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        if let Some(value) = self.parse_coerced(Requested::Integer)? {
+            return visit_coerced(value, visitor);
+        }
         let v = self.parse_integer()?;
         if v > (u16::MAX as i32)
                 || v < (u16::MIN as i32) {
-            Err(Error::Message(String::from("u16 limit exceeded")))
+            Err(DeError::Message(message!("u16 limit exceeded")))
         } else {
             visitor.visit_u16(v as u16)
         }
@@ -348,9 +1332,15 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        if let Some(value) = self.parse_coerced(Requested::Integer)? {
+            return visit_coerced(value, visitor);
+        }
+        if let Some(v) = self.parse_decimal()? {
+            return visitor.visit_u32(v);
+        }
         let v = self.parse_integer()?;
         if v < (u32::MIN as i32) {
-            Err(Error::Message(String::from("u32 limit exceeded")))
+            Err(DeError::Message(message!("u32 limit exceeded")))
         } else {
             visitor.visit_u32(v as u32)
         }
@@ -360,14 +1350,33 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        if let Some(value) = self.parse_coerced(Requested::Integer)? {
+            return visit_coerced(value, visitor);
+        }
+        if let Some(v) = self.parse_decimal()? {
+            return visitor.visit_u64(v);
+        }
+        if self.input.first() == Some(&types::INTEGER) {
+            let v = self.parse_integer()?;
+            return match u64::try_from(v) {
+                Ok(v) => visitor.visit_u64(v),
+                Err(_) => Err(DeError::Message(message!("u64 limit exceeded"))),
+            };
+        }
         let bytes = self.parse_binary()?;
-        visitor.visit_u64(u64::from_le_bytes(bytes.try_into().unwrap()))
+        match bytes.try_into() {
+            Ok(bytes) => visitor.visit_u64(u64::from_le_bytes(bytes)),
+            Err(_) => Err(de::Error::invalid_length(bytes.len(), &"a binary! of 8 bytes")),
+        }
     }
 
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        if let Some(value) = self.parse_coerced(Requested::Float)? {
+            return visit_coerced(value, visitor);
+        }
         visitor.visit_f32(self.parse_float()? as f32)
     }
 
@@ -375,6 +1384,9 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        if let Some(value) = self.parse_coerced(Requested::Float)? {
+            return visit_coerced(value, visitor);
+        }
         visitor.visit_f64(self.parse_float()?)
     }
 
@@ -382,6 +1394,9 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        if let Some(value) = self.parse_coerced(Requested::Char)? {
+            return visit_coerced(value, visitor);
+        }
         visitor.visit_char(self.parse_char()?)
     }
 
@@ -389,29 +1404,46 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_borrowed_str(self.parse_str()?)
+        if let Some(value) = self.parse_coerced(Requested::String)? {
+            return visit_coerced(value, visitor);
+        }
+        if let Some(text) = self.parse_binary_text()? {
+            return visitor.visit_borrowed_str(text);
+        }
+        self.visit_any_string(types::STRING, visitor)
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_string(self.parse_string()?)
+        if let Some(value) = self.parse_coerced(Requested::String)? {
+            return visit_coerced(value, visitor);
+        }
+        if let Some(text) = self.parse_binary_text()? {
+            return visitor.visit_borrowed_str(text);
+        }
+        self.visit_any_string(types::STRING, visitor)
     }
 
-    #[allow(unused)]
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_borrowed_bytes(self.parse_binary()?)
+        if let Some(value) = self.parse_coerced(Requested::Bytes)? {
+            return visit_coerced(value, visitor);
+        }
+        visitor.visit_borrowed_bytes(self.parse_bytes()?)
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_byte_buf(self.parse_binary_owned()?)
+        if let Some(value) = self.parse_coerced(Requested::Bytes)? {
+            return visit_coerced(value, visitor);
+        }
+        visitor.visit_byte_buf(self.parse_bytes()?.to_vec())
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
@@ -419,9 +1451,14 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         V: Visitor<'de>,
     {
         self.parse_padding()?;
-        if self.input[0] == types::NONE {
-            self.input = &self.input[4..];
+        if self.peek()? == types::NONE {
+            self.input = &self.input[self.fixed(4)?.len()..];
             visitor.visit_none()
+        } else if self.options.wrap_some {
+            if self.parse_paren_header()? != 1 {
+                return Err(DeError::Syntax);
+            }
+            visitor.visit_some(self)
         } else {
             visitor.visit_some(self)
         }
@@ -448,100 +1485,333 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
 
     fn deserialize_newtype_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_newtype_struct(self)
+        match name {
+            adapters::ISSUE => visitor.visit_borrowed_str(self.parse_word(types::ISSUE)?),
+            adapters::FILE => visitor.visit_string(self.parse_any_string(types::FILE)?),
+            adapters::VECTOR => {
+                self.parse_padding()?;
+                if self.input.first() == Some(&types::BLOCK) {
+                    // numbers in a block read the same
+                    return de::Deserializer::deserialize_seq(self, visitor);
+                }
+                let (element_type, unit, bytes) = self.parse_vector()?;
+                visitor.visit_seq(VectorData { element_type, unit, bytes })
+            }
+            adapters::GEOMETRY => {
+                self.parse_padding()?;
+                let (element_type, unit, bytes) = if self.peek()? == types::VECTOR {
+                    self.parse_vector()?
+                } else {
+                    self.parse_point()?
+                };
+                visitor.visit_seq(VectorData { element_type, unit, bytes })
+            }
+            adapters::TIME => visitor.visit_f64(self.parse_any_float(types::TIME)?),
+            adapters::DATE => {
+                self.parse_padding()?;
+                if self.peek()? == types::DATE {
+                    let date = &self.fixed(16)?[4..];
+                    self.input = &self.input[16..];
+                    visitor.visit_borrowed_bytes(date)
+                } else {
+                    Err(DeError::ExpectedDate)
+                }
+            }
+            adapters::RAW_RECORD => {
+                self.parse_padding()?;
+                let (record, rest) = self.input.split_at(record_len(self.input)?);
+                self.input = rest;
+                visitor.visit_borrowed_bytes(record)
+            }
+            adapters::IP => {
+                self.parse_padding()?;
+                if self.peek()? == types::TUPLE {
+                    visitor.visit_borrowed_bytes(self.parse_tuple()?)
+                } else {
+                    visitor.visit_borrowed_bytes(self.parse_binary()?)
+                }
+            }
+            adapters::WORD => {
+                self.parse_padding()?;
+                match self.input.first() {
+                    Some(&t) if is_word(t) => visitor.visit_borrowed_str(self.parse_word(t)?),
+                    _ => Err(DeError::ExpectedWord),
+                }
+            }
+            adapters::SHARED => {
+                self.parse_padding()?;
+                let key = self.series_key();
+                adapters::with_shared_key(key, || visitor.visit_newtype_struct(self))
+            }
+            adapters::SERIES => {
+                self.parse_padding()?;
+                if self.input.len() < 12 {
+                    return Err(DeError::Eof);
+                }
+                let head = read_i32(self.input, 4);
+                self.keep_head = true;
+                let value = visitor.visit_seq(SeriesParts::new(self, [head, 0], 1));
+                self.keep_head = false;
+                value
+            }
+            adapters::RED_STRING => {
+                self.parse_padding()?;
+                if self.input.len() < 12 {
+                    return Err(DeError::Eof);
+                }
+                let unit = i32::from(self.input[1]);
+                let head = read_i32(self.input, 4);
+                self.keep_head = true;
+                let value = visitor.visit_seq(SeriesParts::new(self, [unit, head], 2));
+                self.keep_head = false;
+                value
+            }
+            adapters::UTF16 => {
+                let units = self.parse_s(types::STRING,
+                    |bytes, de| Ok(Cow::Owned(utf16_bytes(de.utf8(bytes)?.encode_utf16()))),
+                    |bytes, _| Ok(Cow::Borrowed(bytes)),
+                    |bytes, _| {
+                        let text = encoding::decode_ucs4(bytes)?;
+                        Ok(Cow::Owned(utf16_bytes(text.encode_utf16())))
+                    })?;
+                match units {
+                    Cow::Borrowed(bytes) => visitor.visit_borrowed_bytes(bytes),
+                    Cow::Owned(bytes) => visitor.visit_byte_buf(bytes),
+                }
+            }
+            adapters::OR_DEFAULT => visitor.visit_seq(Fallible { de: self, done: false }),
+            adapters::VALUE => {
+                self.typed = true;
+                self.deserialize_any(visitor)
+            }
+            adapters::POSITIONAL => {
+                self.positional = true;
+                let value = visitor.visit_newtype_struct(&mut *self);
+                self.positional = false; // not consumed by `none`
+                value
+            }
+            adapters::SET_WORD_KEYS => {
+                let len = self.parse_block_header()?;
+                if len % 2 != 0 {
+                    return Err(DeError::ExpectedEvenLength)
+                }
+                visitor.visit_map(BlockData::map(self, len)?)
+            }
+            _ => visitor.visit_newtype_struct(self),
+        }
     }
 
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let len = self.parse_block_header()?;
+        self.positional = false;
+        if self.parse_none_as_empty()? {
+            return visitor.visit_seq(BlockData::new(self, 0));
+        }
+        if self.input.first() == Some(&types::STRING) {
+            // e.g. `Vec<char>`, read a character at a time
+            let chars = self.parse_s(types::STRING,
+                |bytes, de| Ok(StringChars::Utf8(de.utf8(bytes)?, 0)),
+                |bytes, _| Ok(StringChars::Wide(bytes, 2)),
+                |bytes, _| Ok(StringChars::Wide(bytes, 4)))?;
+            return visitor.visit_seq(chars);
+        }
+        let len = self.parse_list_header()?;
         let value = visitor.visit_seq(BlockData::new(self, len))?;
         Ok(value)
     }
 
-    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    // Unlike sequences, tuples must match in length: a visitor would stop
+    // reading early and leave the rest of the block behind.
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        self.positional = false;
+        self.parse_padding()?;
+        if self.input.first() == Some(&types::BINARY) {
+            // fixed-size byte arrays, like hashes or keys
+            let bytes = self.parse_binary()?;
+            if bytes.len() != len {
+                let expected = format!("a binary! of {} bytes", len);
+                return Err(de::Error::invalid_length(bytes.len(), &expected.as_str()));
+            }
+            return visitor.visit_seq(SeqDeserializer::new(bytes.iter().copied()));
+        }
+        // a paren, as `SerializerOptions::tuples_as_paren` writes it
+        let found = self.parse_list_header()?;
+        if found as usize != len {
+            let expected = format!("a block of {} elements", len);
+            return Err(de::Error::invalid_length(found as usize, &expected.as_str()));
+        }
+        visitor.visit_seq(BlockData::new(self, found))
     }
 
     fn deserialize_tuple_struct<V>(
         self,
         _name: &'static str,
-        _len: usize,
+        len: usize,
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_tuple(_len, visitor)
+        self.deserialize_tuple(len, visitor)
     }
 
     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let len = self.parse_block_header()?;
+        self.positional = false;
+        let len = if self.parse_none_as_empty()? {
+            0
+        } else if self.input.first() == Some(&types::MAP) {
+            self.parse_map_header()?
+        } else {
+            self.parse_list_header()?
+        };
         if len % 2 != 0 {
-            return Err(Error::ExpectedEvenLength)
+            return Err(DeError::ExpectedEvenLength)
         }
-        let value = visitor.visit_map(BlockData::new(self, len))?;
+        let value = visitor.visit_map(BlockData::map(self, len)?)?;
         Ok(value)
     }
 
     fn deserialize_struct<V>(
         self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_map(visitor)
+        if std::mem::take(&mut self.positional) || self.options.positional_structs {
+            let len = if self.parse_none_as_empty()? {0} else {self.parse_block_header()?};
+            if len as usize > fields.len() {
+                let expected = format!("a block of at most {} elements", fields.len());
+                return Err(de::Error::invalid_length(len as usize, &expected.as_str()));
+            }
+            visitor.visit_seq(BlockData::new(self, len))
+        } else {
+            self.positional = false;
+            let len = if self.parse_none_as_empty()? {0} else {self.parse_block_header()?};
+            if len % 2 != 0 {
+                return Err(DeError::ExpectedEvenLength)
+            }
+            let mut block = BlockData::map(self, len)?;
+            block.fields = fields;
+            visitor.visit_map(block)
+        }
     }
 
     fn deserialize_enum<V>(
         self,
         _name: &'static str,
-        _variants: &'static [&'static str],
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
         let len = self.parse_paren_header()?;
-        if len == 1 {
-            visitor.visit_enum(self.parse_string()?.into_deserializer())
-        } else if len == 2 {
-            let value = visitor.visit_enum(Enum::new(self))?;
+        if len == 1 || len == 2 {
+            let value = visitor.visit_enum(Enum::new(self, len == 2, variants))?;
             Ok(value)
         } else {
-            Err(Error::ExpectedEnum)
+            Err(DeError::ExpectedEnum)
         }
     }
 
+    // Variant and field names are strings, or any kind of word when coming
+    // from Red code. Variants can also be given by index, as written with
+    // `SerializerOptions::variants_by_index`.
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_string(visitor)
+        self.parse_padding()?;
+        match self.input.first() {
+            Some(&t) if is_word(t) => visitor.visit_borrowed_str(self.parse_word(t)?),
+            Some(&types::INTEGER) => self.deserialize_u32(visitor),
+            Some(&types::STRING) if self.input.get(1) == Some(&1) => self.deserialize_str(visitor),
+            _ => self.deserialize_string(visitor),
+        }
     }
 
-    #[allow(unused)]
     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!("TODO");
+        let n = record_len(self.input)?;
+        self.input = &self.input[n..];
+        visitor.visit_unit()
+    }
+}
+
+/// UTF-16 code units as little endian bytes, as UCS-2 strings store them.
+fn utf16_bytes(units: impl Iterator<Item = u16>) -> Vec<u8> {
+    units.flat_map(u16::to_le_bytes).collect()
+}
+
+/// Characters of a `string!` read as a sequence, decoded as they're visited.
+enum StringChars<'de> {
+    /// text of a 1-byte unit string, and the offset of the next character
+    Utf8(Cow<'de, str>, usize),
+    /// UCS-2 or UCS-4 characters left, and their unit
+    Wide(&'de [u8], usize),
+}
+
+impl StringChars<'_> {
+    fn next_char(&mut self) -> Result<Option<char>> {
+        match self {
+            StringChars::Utf8(text, at) => {
+                let c = text[*at..].chars().next();
+                *at += c.map_or(0, char::len_utf8);
+                Ok(c)
+            }
+            StringChars::Wide(bytes, unit) => {
+                if bytes.len() < *unit {
+                    return Ok(None);
+                }
+                let (c, rest) = bytes.split_at(*unit);
+                *bytes = rest;
+                let mut code = [0; 4];
+                code[..c.len()].copy_from_slice(c);
+                let code = u32::from_le_bytes(code);
+                char::from_u32(code).map(Some)
+                    .ok_or_else(|| DeError::Message(message!("invalid character {:#X}", code)))
+            }
+        }
+    }
+}
+
+impl<'de> SeqAccess<'de> for StringChars<'_> {
+    type Error = DeError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.next_char()? {
+            Some(c) => seed.deserialize(CharDeserializer::new(c)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self {
+            StringChars::Utf8(..) => None,
+            StringChars::Wide(bytes, unit) => Some(bytes.len() / unit),
+        }
     }
 }
 
@@ -551,18 +1821,53 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
 struct BlockData<'a, 'de> {
     de: &'a mut Deserializer<'de>,
     elements: i32,
+    /// map entries dropped by the duplicate key policy, front first
+    skipped: std::vec::IntoIter<bool>,
+    /// fields of the struct read from the block, if it's one
+    fields: &'static [&'static str],
+    /// keys keep their record type, as in a `Value::Map`
+    typed_keys: bool,
+}
+
+impl<'a, 'de> BlockData<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>, len: i32) -> Self {
+        BlockData { de, elements: len, skipped: Vec::new().into_iter(), fields: &[], typed_keys: false }
+    }
+
+    fn map(de: &'a mut Deserializer<'de>, len: i32) -> Result<Self> {
+        let skipped = skipped_entries(de.input, len, de.options.duplicate_keys)?;
+        let mut block = Self::new(de, len);
+        block.skipped = skipped.into_iter();
+        Ok(block)
+    }
 }
 
 impl<'a, 'de> BlockData<'a, 'de> {
-    fn new(de: &'a mut Deserializer<'de>, len: i32) -> Self {
-        BlockData { de, elements: len }
+    /// Warns about the struct field named by the next key if the struct
+    /// doesn't have it, as it's going to be skipped.
+    fn warn_unknown_field(&mut self) {
+        let (key, warned) = (self.de.input, self.de.warnings.len());
+        let name = match key.first() {
+            Some(&t) if is_word(t) => self.de.parse_word(t).map(Cow::Borrowed),
+            Some(&types::STRING) if key.get(1) == Some(&1) => self.de.parse_str(),
+            Some(&types::STRING) => self.de.parse_string().map(Cow::Owned),
+            _ => return,
+        };
+        self.de.input = key;
+        self.de.warnings.truncate(warned);
+        if let Ok(name) = name {
+            if !self.fields.contains(&&*name) {
+                let offset = self.de.offset_of(key);
+                self.de.warn(offset, format!("unknown field `{}` skipped", name));
+            }
+        }
     }
 }
 
 // `SeqAccess` is provided to the `Visitor` to give it the ability to iterate
 // through elements of the sequence.
 impl<'de, 'a> SeqAccess<'de> for BlockData<'a, 'de> {
-    type Error = Error;
+    type Error = DeError;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
     where
@@ -585,16 +1890,35 @@ impl<'de, 'a> SeqAccess<'de> for BlockData<'a, 'de> {
 // `MapAccess` is provided to the `Visitor` to give it the ability to iterate
 // through entries of the map.
 impl<'de, 'a> MapAccess<'de> for BlockData<'a, 'de> {
-    type Error = Error;
+    type Error = DeError;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
     where
         K: DeserializeSeed<'de>,
     {
+        while self.skipped.next() == Some(true) {
+            for _ in 0..2 {
+                de::Deserializer::deserialize_ignored_any(&mut *self.de, de::IgnoredAny)?;
+            }
+            self.elements -= 2;
+        }
         if self.elements < 2 {
             return Ok(None);
         }
-        let k = seed.deserialize(&mut *self.de).map(Some)?;
+        // Words of any kind stand for their names, so they can become
+        // string keys as well as `Word`s and struct fields. Other keys are
+        // deserialized as whatever type the map asks for.
+        self.de.parse_padding()?;
+        if !self.fields.is_empty() {
+            self.warn_unknown_field();
+        }
+        let k = match self.de.input.first() {
+            Some(&t) if is_word(t) && !self.typed_keys => {
+                let word = self.de.parse_word(t)?;
+                seed.deserialize(BorrowedStrDeserializer::new(word)).map(Some)?
+            }
+            _ => seed.deserialize(&mut *self.de).map(Some)?,
+        };
         self.elements -= 1;
         Ok(k)
     }
@@ -604,22 +1928,233 @@ impl<'de, 'a> MapAccess<'de> for BlockData<'a, 'de> {
         V: DeserializeSeed<'de>,
     {
         if self.elements < 1 {
-            return Err(Error::NoMapValue);
+            return Err(DeError::NoMapValue);
         }
         let v = seed.deserialize(&mut *self.de)?;
         self.elements -= 1;
         Ok(v)
     }
+
+    // entries the duplicate key policy drops don't count
+    fn size_hint(&self) -> Option<usize> {
+        let skipped = self.skipped.as_slice().iter().filter(|&&skip| skip).count();
+        Some((self.elements as usize / 2).saturating_sub(skipped))
+    }
+}
+
+/// Numbers from a series header, e.g. its head position, followed by the
+/// whole series.
+struct SeriesParts<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    header: [i32; 2],
+    header_len: usize,
+    read: usize,
+}
+
+impl<'a, 'de> SeriesParts<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>, header: [i32; 2], header_len: usize) -> Self {
+        SeriesParts { de, header, header_len, read: 0 }
+    }
+}
+
+impl<'de, 'a> SeqAccess<'de> for SeriesParts<'a, 'de> {
+    type Error = DeError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.read += 1;
+        match self.read - 1 {
+            i if i < self.header_len => seed.deserialize(self.header[i].into_deserializer()).map(Some),
+            i if i == self.header_len => seed.deserialize(&mut *self.de).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// The one record of an `adapters::OrDefault` field, or nothing if it
+/// doesn't decode. The record is skipped then, with a warning.
+struct Fallible<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    done: bool,
+}
+
+impl<'de, 'a> SeqAccess<'de> for Fallible<'a, 'de> {
+    type Error = DeError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if std::mem::replace(&mut self.done, true) {
+            return Ok(None);
+        }
+        self.de.parse_padding()?;
+        let record = self.de.input;
+        match seed.deserialize(&mut *self.de) {
+            Ok(value) => Ok(Some(value)),
+            Err(error) => {
+                let len = record_len(record)?;
+                self.de.warn(self.de.offset_of(record), format!("replaced by default: {}", error));
+                self.de.resume_at(&record[len..]);
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Elements of a `vector!` record, handed out as plain numbers.
+struct VectorData<'de> {
+    element_type: u8,
+    unit: usize,
+    bytes: &'de [u8],
+}
+
+impl<'de> SeqAccess<'de> for VectorData<'de> {
+    type Error = DeError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.bytes.is_empty() {
+            return Ok(None);
+        }
+        let (element, rest) = self.bytes.split_at(self.unit);
+        self.bytes = rest;
+        let v = match (self.element_type, self.unit) {
+            (types::INTEGER, 1) => seed.deserialize(element[0].into_deserializer()),
+            (types::INTEGER, 2) => seed.deserialize(i16::from_le_bytes(element.try_into().unwrap()).into_deserializer()),
+            (types::INTEGER, 4) => seed.deserialize(i32::from_le_bytes(element.try_into().unwrap()).into_deserializer()),
+            (types::FLOAT, 4) => seed.deserialize(f32::from_le_bytes(element.try_into().unwrap()).into_deserializer()),
+            (types::FLOAT, 8) => seed.deserialize(f64::from_le_bytes(element.try_into().unwrap()).into_deserializer()),
+            _ => Err(DeError::Message(message!("Unsupported vector! of type {} and unit {}.", self.element_type, self.unit))),
+        };
+        v.map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.bytes.len() / self.unit)
+    }
+}
+
+impl<'de> Deserializer<'de> {
+    /// Visits records `Value` tells apart by their type as variants named
+    /// after it, e.g. `set-word`.
+    fn deserialize_typed<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let record_type = self.input[0];
+        let kind = match value::kind_name(record_type) {
+            Some(kind) => kind,
+            None if record_type == types::BLOCK => return de::Deserializer::deserialize_seq(self, visitor),
+            None => return de::Deserializer::deserialize_any(self, visitor),
+        };
+        if matches!(record_type, types::PAREN | types::PATH..=types::GET_PATH) {
+            let len = self.parse_any_block_header(record_type)?;
+            let content = SeqAccessDeserializer::new(BlockData::new(self, len));
+            visitor.visit_enum(Tagged { kind, content })
+        } else if record_type == types::MAP {
+            let len = self.parse_map_header()?;
+            let mut entries = BlockData::map(self, len)?;
+            entries.typed_keys = true;
+            let content = MapAccessDeserializer::new(entries);
+            visitor.visit_enum(Tagged { kind, content })
+        } else if is_word(record_type) {
+            let content = BorrowedStrDeserializer::new(self.parse_word(record_type)?);
+            visitor.visit_enum(Tagged { kind, content })
+        } else {
+            let content = StringDeserializer::new(self.parse_any_string(record_type)?);
+            visitor.visit_enum(Tagged { kind, content })
+        }
+    }
+}
+
+/// A value tagged with its kind of record, see `Value`.
+struct Tagged<D> {
+    kind: &'static str,
+    content: D,
+}
+
+impl<'de, D> EnumAccess<'de> for Tagged<D>
+where
+    D: de::Deserializer<'de, Error = DeError>,
+{
+    type Error = DeError;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let kind = seed.deserialize(BorrowedStrDeserializer::new(self.kind))?;
+        Ok((kind, self))
+    }
+}
+
+impl<'de, D> VariantAccess<'de> for Tagged<D>
+where
+    D: de::Deserializer<'de, Error = DeError>,
+{
+    type Error = DeError;
+
+    fn unit_variant(self) -> Result<()> {
+        Err(de::Error::invalid_type(de::Unexpected::NewtypeVariant, &"a unit variant"))
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.content)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::invalid_type(de::Unexpected::NewtypeVariant, &"a tuple variant"))
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::invalid_type(de::Unexpected::NewtypeVariant, &"a struct variant"))
+    }
 }
 
 struct Enum<'a, 'de> {
     de: &'a mut Deserializer<'de>,
+    with_value: bool,
+    variants: &'static [&'static str],
 }
 
 impl<'a, 'de> Enum<'a, 'de> {
-    fn new(de: &'a mut Deserializer<'de>) -> Self {
-        Enum { de }
+    fn new(de: &'a mut Deserializer<'de>, with_value: bool, variants: &'static [&'static str]) -> Self {
+        Enum { de, with_value, variants }
     }
+
+    /// The variant `name` refers to, when it differs from it only in case
+    /// (or, if allowed, in hyphens and underscores), or is written in the
+    /// case the options give.
+    fn similar_variant(&self, name: &str) -> Option<&'static str> {
+        let (hyphens, case) = (self.de.options.hyphenated_variants, self.de.options.variant_case);
+        if self.variants.contains(&name) {
+            return None;
+        }
+        self.variants.iter().copied()
+            .find(|variant| case.apply(variant) == name || fold_name(variant, hyphens).eq(fold_name(name, hyphens)))
+    }
+}
+
+/// Characters of a name compared regardless of case, as Red compares words.
+fn fold_name(name: &str, hyphens: bool) -> impl Iterator<Item = char> + '_ {
+    name.chars()
+        .filter(move |&c| !(hyphens && (c == '-' || c == '_')))
+        .flat_map(char::to_lowercase)
 }
 
 // `EnumAccess` is provided to the `Visitor` to give it the ability to determine
@@ -628,14 +2163,27 @@ impl<'a, 'de> Enum<'a, 'de> {
 // Note that all enum deserialization methods in Serde refer exclusively to the
 // "externally tagged" enum representation.
 impl<'de, 'a> EnumAccess<'de> for Enum<'a, 'de> {
-    type Error = Error;
+    type Error = DeError;
     type Variant = Self;
 
     fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
     where
         V: DeserializeSeed<'de>,
     {
-        let val = seed.deserialize(&mut *self.de)?;
+        self.de.parse_padding()?;
+        let name = match self.de.input.first() {
+            Some(&t) if is_word(t) => Cow::Borrowed(self.de.parse_word(t)?),
+            Some(&types::STRING) => Cow::Owned(self.de.parse_string()?),
+            _ => {
+                let val = seed.deserialize(&mut *self.de)?;
+                return Ok((val, self));
+            }
+        };
+        let val = match (self.similar_variant(&name), name) {
+            (Some(variant), _) => seed.deserialize(variant.into_deserializer())?,
+            (None, Cow::Borrowed(name)) => seed.deserialize(BorrowedStrDeserializer::new(name))?,
+            (None, Cow::Owned(name)) => seed.deserialize(StringDeserializer::new(name))?,
+        };
         Ok((val, self))
     }
 }
@@ -643,18 +2191,24 @@ impl<'de, 'a> EnumAccess<'de> for Enum<'a, 'de> {
 // `VariantAccess` is provided to the `Visitor` to give it the ability to see
 // the content of the single variant that it decided to deserialize.
 impl<'de, 'a> VariantAccess<'de> for Enum<'a, 'de> {
-    type Error = Error;
+    type Error = DeError;
 
-    // If the `Visitor` expected this variant to be a unit variant, the input
-    // should have been the plain string case handled in `deserialize_enum`.
+    // A value following a unit variant is skipped, so that unknown variants
+    // carrying data can still fall back to a `#[serde(other)]` variant.
     fn unit_variant(self) -> Result<()> {
-        Err(Error::ExpectedString)
+        if self.with_value {
+            de::Deserializer::deserialize_ignored_any(self.de, de::IgnoredAny)?;
+        }
+        Ok(())
     }
 
     fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
     where
         T: DeserializeSeed<'de>,
     {
+        if !self.with_value {
+            return Err(DeError::ExpectedEnum);
+        }
         seed.deserialize(self.de)
     }
 
@@ -662,6 +2216,9 @@ impl<'de, 'a> VariantAccess<'de> for Enum<'a, 'de> {
     where
         V: Visitor<'de>,
     {
+        if !self.with_value {
+            return Err(DeError::ExpectedEnum);
+        }
         de::Deserializer::deserialize_tuple(self.de, _len, visitor)
     }
 
@@ -673,6 +2230,9 @@ impl<'de, 'a> VariantAccess<'de> for Enum<'a, 'de> {
     where
         V: Visitor<'de>,
     {
+        if !self.with_value {
+            return Err(DeError::ExpectedEnum);
+        }
         de::Deserializer::deserialize_struct(self.de, "NameIsIrrelevant", _fields, visitor)
     }
 }
@@ -688,14 +2248,21 @@ Redbin values can be generated in Red:
 , 0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x0B, 0x00, 0x00, 0x00, 0x37, 0x00, 0x00, 0x00
 */
 
-#[cfg(test)]
+#[cfg(all(test, feature = "ser", feature = "de"))]
 mod tests {
-    use super::from_bytes;
+    use super::{from_bytes, from_bytes_with, DeserializerOptions, DuplicateKeys, Requested};
+    use crate::error::DeError;
+    use crate::value::Value;
+    use std::borrow::Cow;
+    use std::collections::HashMap;
+    use crate::{to_bytes, to_bytes_with, SerializerOptions};
     use serde_derive::Deserialize;
     use serde_bytes::ByteBuf;
     use std::path::Path;
 
     #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    #[allow(clippy::type_complexity)]
     fn test_seq() {
 
         // rust-redbin-helper reduce [-2 299 66666 [5 6] yes 122234.23425 12.5 "aa" "ą" "💖" #"a" #{CAFE}]
@@ -727,6 +2294,11 @@ mod tests {
         let expected: (u32, u64) = (66666, 18_446_744_073_709_551_614u64);
         assert_eq!(expected, from_bytes(j).unwrap());
 
+        // rust-redbin-helper #{FEFFFFFF}
+        let j = &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00,
+            0x29, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0xFE, 0xFF, 0xFF, 0xFF];
+        assert!(matches!(from_bytes::<u64>(j), Err(DeError::Message(_))));
+
 
         // rust-redbin-helper none
         assert_eq!((), from_bytes::<()>(
@@ -864,4 +2436,688 @@ mod tests {
 
     }
 
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_tagged_enums() {
+        use serde_derive::Serialize;
+        use crate::to_bytes;
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        #[serde(tag = "type")]
+        enum Internal {
+            Unit,
+            Struct { a: u32, b: Vec<String>, c: Option<f64> },
+            Newtype(Inner),
+        }
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Inner { name: String }
+
+        // rust-redbin-helper [type: "Struct" a: 1 b: ["x"] c: none]
+        let bytes = to_bytes(&Internal::Struct { a: 1, b: vec![String::from("x")], c: None }).unwrap();
+        assert_eq!(bytes,
+            &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x04, 0x01, 0x00, 0x00, 0x00, 0x88, 0x00, 0x00, 0x00,
+            0x04, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00,
+            0x07, 0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00,
+            0x74, 0x79, 0x70, 0x65, 0x00, 0x61, 0x00, 0x62, 0x00, 0x63, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00,
+                0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x53, 0x74, 0x72, 0x75, 0x63, 0x74, 0x00, 0x00,
+                0x10, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                0x0B, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                0x10, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                    0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x78, 0x00, 0x00, 0x00,
+                0x10, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                0x03, 0x00, 0x00, 0x00]);
+
+        for value in [
+            Internal::Unit,
+            Internal::Struct { a: 1, b: vec![String::from("x"), String::from("y"), String::from("z")], c: Some(2.5) },
+            // an even number of strings isn't taken for a map
+            Internal::Struct { a: 1, b: vec![String::from("x"), String::from("y")], c: None },
+            Internal::Newtype(Inner { name: String::from("n") }),
+        ] {
+            assert_eq!(value, from_bytes::<Internal>(&to_bytes(&value).unwrap()).unwrap());
+        }
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        #[serde(tag = "t", content = "c")]
+        enum Adjacent {
+            Unit,
+            Newtype(i32),
+            Tuple(u8, String),
+            Struct { a: u32 },
+        }
+        for value in [
+            Adjacent::Unit,
+            Adjacent::Newtype(-3),
+            Adjacent::Tuple(1, String::from("ą")),
+            Adjacent::Struct { a: 7 },
+        ] {
+            assert_eq!(value, from_bytes::<Adjacent>(&to_bytes(&value).unwrap()).unwrap());
+        }
+
+        // a block of 4 values with only 2 in it, taken for a map at first
+        #[derive(Serialize)]
+        struct Tag { r#type: &'static str }
+        let mut bytes = to_bytes(&Tag { r#type: "Unit" }).unwrap();
+        // after the symbol table of `type`
+        bytes[44] = 0x04;
+        assert_eq!(Err(DeError::Eof), from_bytes::<Internal>(&bytes));
+    }
+
+    #[test]
+    fn test_other_variant() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        enum Shape {
+            Circle(f64),
+            Square,
+            #[serde(other)]
+            Unknown,
+        }
+
+        // rust-redbin-helper [("Square") ("Circle" 1.5) ("Hexagon") ("Star" [5 2.5]) ('Circle 0.5)]
+        let bytes = &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x04, 0x01, 0x00, 0x00, 0x00, 0xD4, 0x00, 0x00, 0x00,
+            0x01, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x43, 0x69, 0x72, 0x63, 0x6C, 0x65, 0x00, 0x00,
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00,
+                0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                    0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x53, 0x71, 0x75, 0x61, 0x72, 0x65, 0x00, 0x00,
+                0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+                    0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x43, 0x69, 0x72, 0x63, 0x6C, 0x65, 0x00, 0x00,
+                    0x0C, 0x00, 0x00, 0x00, 0x00, 0x00, 0xF8, 0x3F, 0x00, 0x00, 0x00, 0x00,
+                0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                    0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x48, 0x65, 0x78, 0x61, 0x67, 0x6F, 0x6E, 0x00,
+                0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+                    0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x53, 0x74, 0x61, 0x72,
+                    0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+                        0x0B, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00,
+                        0x0C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x40, 0x00, 0x00, 0x00, 0x00,
+                0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+                    0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                    0x0C, 0x00, 0x00, 0x00, 0x00, 0x00, 0xE0, 0x3F, 0x00, 0x00, 0x00, 0x00];
+        assert_eq!(vec![Shape::Square, Shape::Circle(1.5), Shape::Unknown, Shape::Unknown, Shape::Circle(0.5)],
+            from_bytes::<Vec<Shape>>(bytes).unwrap());
+    }
+
+    #[test]
+    fn test_variant_case() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        enum En {
+            Unit,
+            UnitStruct,
+            Newtype(i32),
+        }
+
+        let bytes = to_bytes(&crate::parse_red("[('UNIT) (unit) (\"Newtype\" 1) (newTYPE 2)]").unwrap()).unwrap();
+        assert_eq!(vec![En::Unit, En::Unit, En::Newtype(1), En::Newtype(2)],
+            from_bytes::<Vec<En>>(&bytes).unwrap());
+        let bytes = to_bytes(&crate::parse_red("[('unit-struct)]").unwrap()).unwrap();
+        assert!(from_bytes::<Vec<En>>(&bytes).is_err());
+        let options = DeserializerOptions::new().hyphenated_variants(true);
+        assert_eq!(vec![En::UnitStruct], from_bytes_with::<Vec<En>>(&bytes, options).unwrap());
+    }
+
+    #[test]
+    fn test_wrap_root() {
+        let bytes = to_bytes_with(&5, SerializerOptions::new().wrap_root(true)).unwrap();
+        assert_eq!(to_bytes(&[5]).unwrap(), bytes);
+        let options = DeserializerOptions::new().unwrap_root(true);
+        assert_eq!(5, from_bytes_with::<i32>(&bytes, options.clone()).unwrap());
+        assert!(from_bytes::<i32>(&bytes).is_err());
+
+        let bytes = to_bytes_with(&vec![1, 2], SerializerOptions::new().wrap_root(true)).unwrap();
+        assert_eq!(vec![1, 2], from_bytes_with::<Vec<i32>>(&bytes, options.clone()).unwrap());
+        // anything else is read as it is
+        assert_eq!(vec![1, 2], from_bytes_with::<Vec<i32>>(&to_bytes(&[1, 2]).unwrap(), options).unwrap());
+    }
+
+    #[test]
+    fn test_wrap_some() {
+        let options = DeserializerOptions::new().wrap_some(true);
+
+        // rust-redbin-helper [#(none) (#(none)) ((5))]
+        let bytes = &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00,
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00,
+                0x03, 0x00, 0x00, 0x00,
+                0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                    0x03, 0x00, 0x00, 0x00,
+                0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                    0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                        0x0B, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00];
+        let value = vec![None, Some(None), Some(Some(5))];
+        assert_eq!(value, from_bytes_with::<Vec<Option<Option<i32>>>>(bytes, options.clone()).unwrap());
+        assert_eq!(&to_bytes_with(&value, SerializerOptions::new().wrap_some(true)).unwrap()[..], bytes);
+
+        // plain `none` still reads as `None`, anything else must be wrapped
+        assert!(from_bytes_with::<Option<i32>>(&to_bytes(&5).unwrap(), options).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct S {
+            a: i32,
+            b: i32,
+        }
+
+        // rust-redbin-helper ["a" 1 "b" 2 "a" 3]
+        let bytes = &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x54, 0x00, 0x00, 0x00,
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00,
+                0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x61, 0x00, 0x00, 0x00,
+                0x0B, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x62, 0x00, 0x00, 0x00,
+                0x0B, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+                0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x61, 0x00, 0x00, 0x00,
+                0x0B, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00];
+        let options = |policy| DeserializerOptions::new().duplicate_keys(policy);
+
+        assert!(from_bytes::<S>(bytes).is_err());
+        assert_eq!(Some(&3), from_bytes::<HashMap<String, i32>>(bytes).unwrap().get("a"));
+
+        assert_eq!(S { a: 1, b: 2 }, from_bytes_with::<S>(bytes, options(DuplicateKeys::FirstWins)).unwrap());
+        assert_eq!(S { a: 3, b: 2 }, from_bytes_with::<S>(bytes, options(DuplicateKeys::LastWins)).unwrap());
+        let first = from_bytes_with::<HashMap<String, i32>>(bytes, options(DuplicateKeys::FirstWins)).unwrap();
+        assert_eq!((Some(&1), 2), (first.get("a"), first.len()));
+
+        assert_eq!(Err(DeError::DuplicateKey), from_bytes_with::<S>(bytes, options(DuplicateKeys::Error)));
+        assert_eq!(Err(DeError::DuplicateKey), from_bytes_with::<HashMap<String, i32>>(bytes, options(DuplicateKeys::Error)));
+    }
+
+    #[test]
+    fn test_integer_keys() {
+        // rust-redbin-helper [1 "one" 2 "two"]
+        let bytes = &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x3C, 0x00, 0x00, 0x00,
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
+                0x0B, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x6F, 0x6E, 0x65, 0x00,
+                0x0B, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+                0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x74, 0x77, 0x6F, 0x00];
+        let map = HashMap::from([(1, String::from("one")), (2, String::from("two"))]);
+        assert_eq!(map, from_bytes::<HashMap<i32, String>>(bytes).unwrap());
+        assert_eq!(map, from_bytes::<HashMap<i32, String>>(&to_bytes(&map).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_paren_as_block() {
+        let bytes = to_bytes(&crate::parse_red("[(1 2) (\"a\" 3)]").unwrap()).unwrap();
+        let (seq, map) = from_bytes::<(Vec<i32>, HashMap<String, i32>)>(&bytes).unwrap();
+        assert_eq!(vec![1, 2], seq);
+        assert_eq!(HashMap::from([(String::from("a"), 3)]), map);
+    }
+
+    #[test]
+    fn test_size_hints() {
+        use serde::de::{Deserializer as _, MapAccess, SeqAccess, Visitor};
+        struct Hint;
+        impl<'de> Visitor<'de> for Hint {
+            type Value = Option<usize>;
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a block")
+            }
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+                Ok(seq.size_hint())
+            }
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                Ok(map.size_hint())
+            }
+        }
+
+        let bytes = to_bytes(&["a", "b", "a", "c"]).unwrap();
+        let mut deserializer = super::Deserializer::from_bytes(&bytes);
+        deserializer.read_header().unwrap();
+        assert_eq!(Some(4), deserializer.deserialize_seq(Hint).unwrap());
+        let options = DeserializerOptions::new().duplicate_keys(DuplicateKeys::FirstWins);
+        let mut deserializer = super::Deserializer::with_options(&bytes, options);
+        deserializer.read_header().unwrap();
+        assert_eq!(Some(1), deserializer.deserialize_map(Hint).unwrap());
+
+        // a count beyond what the input can hold
+        let mut bytes = to_bytes(&[1, 2]).unwrap();
+        bytes[24..28].copy_from_slice(&1_000_000_i32.to_le_bytes());
+        assert_eq!(Err(DeError::Eof), from_bytes::<Vec<i32>>(&bytes));
+        bytes[24..28].copy_from_slice(&(-1_i32).to_le_bytes());
+        assert_eq!(Err(DeError::TooLarge), from_bytes::<Vec<i32>>(&bytes));
+    }
+
+    #[test]
+    fn test_set_word_keys() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct S {
+            a: i32,
+            b: String,
+        }
+        let bytes = to_bytes(&crate::parse_red("[[a: 1 b: \"x\"] [b: \"y\" a: 2]]").unwrap()).unwrap();
+        assert_eq!(vec![S { a: 1, b: String::from("x") }, S { a: 2, b: String::from("y") }],
+            from_bytes::<Vec<S>>(&bytes).unwrap());
+        assert_eq!(HashMap::from([(String::from("a"), 1), (String::from("b"), 2)]),
+            from_bytes::<HashMap<String, i32>>(&to_bytes(&crate::parse_red("[a: 1 b: 2]").unwrap()).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_tuple_length() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Pair(i32, i32);
+
+        let bytes = to_bytes(&[1, 2, 3]).unwrap();
+        assert_eq!((1, 2, 3), from_bytes::<(i32, i32, i32)>(&bytes).unwrap());
+        // messages of `Deserialize` impls are fixed with `static-errors`
+        #[cfg(not(feature = "static-errors"))]
+        assert_eq!(Err(DeError::Message(message!("invalid length 3, expected a block of 2 elements"))),
+            from_bytes::<(i32, i32)>(&bytes));
+        #[cfg(not(feature = "static-errors"))]
+        assert_eq!(Err(DeError::Message(message!("invalid length 3, expected a block of 2 elements"))),
+            from_bytes::<Pair>(&bytes));
+        assert!(matches!(from_bytes::<Pair>(&bytes), Err(DeError::Message(_))));
+        assert!(from_bytes::<[i32; 4]>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_byte_array() {
+        let bytes = to_bytes(&serde_bytes::Bytes::new(&[1, 2, 3, 4])).unwrap();
+        assert_eq!([1u8, 2, 3, 4], from_bytes::<[u8; 4]>(&bytes).unwrap());
+        #[cfg(not(feature = "static-errors"))]
+        assert_eq!(Err(DeError::Message(message!("invalid length 4, expected a binary! of 3 bytes"))),
+            from_bytes::<[u8; 3]>(&bytes));
+        assert!(matches!(from_bytes::<[u8; 3]>(&bytes), Err(DeError::Message(_))));
+        // blocks of integers still work
+        assert_eq!([1u8, 2], from_bytes::<[u8; 2]>(&to_bytes(&[1, 2]).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_binary_as_string() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Text<'a> {
+            owned: String,
+            borrowed: &'a str,
+        }
+        let options = DeserializerOptions::new().binary_as_string(true);
+
+        // rust-redbin-helper ["owned" #{C485} "borrowed" #{6162}]
+        let bytes = &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x50, 0x00, 0x00, 0x00,
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
+                0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x6F, 0x77, 0x6E, 0x65, 0x64, 0x00, 0x00, 0x00,
+                0x29, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0xC4, 0x85, 0x00, 0x00,
+                0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x62, 0x6F, 0x72, 0x72, 0x6F, 0x77, 0x65, 0x64,
+                0x29, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x61, 0x62, 0x00, 0x00];
+        assert_eq!(Text { owned: String::from("ą"), borrowed: "ab" }, from_bytes_with::<Text>(bytes, options.clone()).unwrap());
+        assert!(from_bytes::<Text>(bytes).is_err());
+
+        // rust-redbin-helper #{FF}
+        let invalid = &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00,
+            0x29, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xFF, 0x00, 0x00, 0x00];
+        assert!(from_bytes_with::<String>(invalid, options).is_err());
+
+        // a binary! of 2-byte units, which Red doesn't write
+        let wide = &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00,
+            0x29, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x61, 0x62, 0x00, 0x00];
+        assert_eq!(Err(DeError::Message(message!("Unexpected unit size {}.", 2))), from_bytes::<ByteBuf>(wide));
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_string_as_bytes() {
+        let options = DeserializerOptions::new().string_as_bytes(true);
+
+        let bytes = to_bytes(&("ab", "ą")).unwrap();
+        assert_eq!(ByteBuf::from("ab"),
+            from_bytes_with::<(ByteBuf, String)>(&bytes, options.clone()).unwrap().0);
+        assert_eq!(b"ab", from_bytes_with::<(&serde_bytes::Bytes, String)>(&bytes, options.clone()).unwrap().0.as_ref());
+        assert!(from_bytes::<(ByteBuf, String)>(&bytes).is_err());
+
+        // UCS-4 strings are not reinterpreted
+        assert!(from_bytes_with::<(String, ByteBuf)>(&bytes, options).is_err());
+    }
+
+    #[test]
+    fn test_coerce() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Ticket {
+            id: u16,
+            title: String,
+            done: bool,
+        }
+        let issue_number = |value: Value| match value {
+            Value::Issue(digits) => digits.parse().map(Value::Integer)
+                .map_err(|_| DeError::Message(message!("not a number: #{}", digits))),
+            _ => unreachable!(),
+        };
+        let options = DeserializerOptions::new()
+            .coerce("issue!", Requested::Integer, issue_number)
+            .coerce("word", Requested::String, |value| Ok(Value::String(value.to_string().to_uppercase())))
+            .coerce("logic!", Requested::Bool, |value| Ok(Value::Logic(value != Value::Logic(true))));
+
+        let bytes = to_bytes(&crate::parse_red("[id: #123 title: fix done: #[false]]").unwrap()).unwrap();
+        assert_eq!(Ticket { id: 123, title: "FIX".to_string(), done: true },
+            from_bytes_with(&bytes, options.clone()).unwrap());
+        assert!(from_bytes::<Ticket>(&bytes).is_err());
+        let bytes = to_bytes(&crate::parse_red("[id: #70000 title: \"a\" done: #[true]]").unwrap()).unwrap();
+        assert!(from_bytes_with::<Ticket>(&bytes, options.clone()).is_err());
+        let bytes = to_bytes(&crate::parse_red("[id: #abc title: \"a\" done: #[true]]").unwrap()).unwrap();
+        assert_eq!(DeError::Message(message!("not a number: #{}", "abc")),
+            from_bytes_with::<Ticket>(&bytes, options).unwrap_err());
+    }
+
+    #[test]
+    fn test_lossy_strings() {
+        let options = DeserializerOptions::new().lossy_strings(true);
+
+        // rust-redbin-helper to string! #{61FF62}
+        let bytes = &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00,
+            0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x61, 0xFF, 0x62, 0x00];
+        assert!(from_bytes::<String>(bytes).is_err());
+        assert_eq!("a\u{FFFD}b", from_bytes_with::<String>(bytes, options.clone()).unwrap());
+        assert_eq!("a\u{FFFD}b", from_bytes_with::<Cow<str>>(bytes, options.clone()).unwrap());
+        assert!(from_bytes_with::<&str>(bytes, options.clone()).is_err());
+
+        // valid strings are still borrowed
+        let bytes = to_bytes(&"ab").unwrap();
+        assert_eq!("ab", from_bytes_with::<&str>(&bytes, options).unwrap());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_chars() {
+        for text in ["abc", "żółw", "a😀"] {
+            let chars: Vec<char> = text.chars().collect();
+            assert_eq!(chars, from_bytes::<Vec<char>>(&to_bytes(&text).unwrap()).unwrap());
+        }
+        // next "żółw"
+        let bytes = to_bytes(&crate::adapters::RedSeries { head: 1, data: "żółw" }).unwrap();
+        assert_eq!(vec!['ó', 'ł', 'w'], from_bytes::<Vec<char>>(&bytes).unwrap());
+        assert!(from_bytes::<Vec<u32>>(&to_bytes(&"abc").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_skip_value() {
+        use serde::Deserialize;
+        let bytes = crate::to_bytes(&crate::parse_red("[[1 [2 \"żółw\"] a/b] #{CAFE} (x) \"name\"]").unwrap()).unwrap();
+        let mut deserializer = super::Deserializer::from_bytes(&bytes);
+        deserializer.read_header().unwrap();
+        assert_eq!(4, deserializer.enter_block().unwrap());
+        for _ in 0..3 {
+            deserializer.skip_value().unwrap();
+        }
+        assert_eq!("name", String::deserialize(&mut deserializer).unwrap());
+        assert!(matches!(deserializer.skip_value(), Err(DeError::Eof)));
+
+        let mut deserializer = super::Deserializer::from_bytes(&bytes[..bytes.len() - 4]);
+        deserializer.read_header().unwrap();
+        assert!(matches!(deserializer.skip_value(), Err(DeError::Eof)));
+    }
+
+    #[test]
+    fn test_strict_padding() {
+        let options = DeserializerOptions::new().strict_padding(true);
+
+        // rust-redbin-helper ["owned" #{C485}]
+        let bytes = &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x2C, 0x00, 0x00, 0x00,
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+                0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x6F, 0x77, 0x6E, 0x65, 0x64, 0x00, 0x00, 0x00,
+                0x29, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0xC4, 0x85, 0x00, 0x00];
+        let value = (String::from("owned"), ByteBuf::from(vec![0xC4, 0x85]));
+        assert_eq!(value, from_bytes_with(bytes, options.clone()).unwrap());
+
+        // a float! aligned to 64 bits
+        let mut bytes = to_bytes(&(1, 1.5)).unwrap();
+        bytes.splice(36..36, [0x00; 4]);
+        assert_eq!((1, 1.5), from_bytes_with(&bytes, options.clone()).unwrap());
+
+        let mut bytes = to_bytes(&(1, 1.5)).unwrap();
+        bytes.splice(28..28, [0x00; 4]);
+        assert_eq!((1, 1.5), from_bytes(&bytes).unwrap());
+        assert_eq!(Err(DeError::UnexpectedPadding { offset: 28 }), from_bytes_with::<(i32, f64)>(&bytes, options));
+    }
+
+    #[test]
+    fn test_oversize() {
+        let mut bytes = to_bytes(&"abcd").unwrap();
+        bytes[16 + 8..16 + 12].copy_from_slice(&(-1_i32).to_le_bytes());
+        assert_eq!(Err(DeError::TooLarge), from_bytes::<String>(&bytes));
+        bytes[16 + 8..16 + 12].copy_from_slice(&i32::MAX.to_le_bytes());
+        assert_eq!(Err(DeError::Eof), from_bytes::<String>(&bytes));
+        assert_eq!(Err(DeError::Eof), from_bytes::<crate::Value>(&bytes));
+        // head past the end
+        bytes[16 + 4..16 + 12].copy_from_slice(&[0x05, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00]);
+        assert_eq!(Err(DeError::Syntax), from_bytes::<String>(&bytes));
+
+        let mut bytes = to_bytes(&ByteBuf::from(vec![1, 2])).unwrap();
+        bytes[16 + 8..16 + 12].copy_from_slice(&(-2_i32).to_le_bytes());
+        assert_eq!(Err(DeError::TooLarge), from_bytes::<ByteBuf>(&bytes));
+    }
+
+    #[test]
+    fn test_partial_blocks() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Config {
+            name: String,
+            #[serde(default)]
+            retries: i32,
+            #[serde(default = "default_tags")]
+            tags: Vec<String>,
+        }
+        fn default_tags() -> Vec<String> {
+            vec!["none".to_string()]
+        }
+        let expected = Config { name: "db".to_string(), retries: 0, tags: default_tags() };
+        let bytes = to_bytes(&crate::parse_red(r#"[name: "db"]"#).unwrap()).unwrap();
+        assert_eq!(expected, from_bytes::<Config>(&bytes).unwrap());
+
+        // trailing fields left out of a positional block
+        let positional = DeserializerOptions::new().positional_structs(true);
+        let bytes = to_bytes(&crate::parse_red(r#"["db" 3]"#).unwrap()).unwrap();
+        assert_eq!(Config { retries: 3, ..expected }, from_bytes_with::<Config>(&bytes, positional.clone()).unwrap());
+        let bytes = to_bytes(&crate::parse_red("[]").unwrap()).unwrap();
+        assert!(matches!(from_bytes_with::<Config>(&bytes, positional.clone()), Err(DeError::Message(_))));
+
+        #[derive(Deserialize, PartialEq, Debug, Default)]
+        #[serde(default)]
+        struct Limits {
+            low: i32,
+            high: i32,
+        }
+        let bytes = to_bytes(&crate::parse_red("#[none]").unwrap()).unwrap();
+        assert_eq!(Err(DeError::ExpectedBlock), from_bytes_with::<Limits>(&bytes, positional.clone()));
+        assert_eq!(Limits::default(), from_bytes_with(&bytes, positional.none_as_empty(true)).unwrap());
+    }
+
+    #[test]
+    fn test_warnings() {
+        use crate::from_bytes_with_warnings;
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct S {
+            a: Vec<i32>,
+            b: String,
+        }
+        let mut bytes = to_bytes(&crate::parse_red(r#"[a: #[none] b: "x" c: 1]"#).unwrap()).unwrap();
+        let options = DeserializerOptions::new().none_as_empty(true);
+        let (s, warnings) = from_bytes_with_warnings::<S>(&bytes, options.clone()).unwrap();
+        assert_eq!(S { a: vec![], b: "x".to_string() }, s);
+        let messages: Vec<String> = warnings.iter().map(|w| w.message.clone()).collect();
+        assert_eq!(vec!["none read as an empty collection", "unknown field `c` skipped"], messages);
+
+        bytes[6] = 3;
+        let (_, warnings) = from_bytes_with_warnings::<S>(&bytes, options).unwrap();
+        assert_eq!("at byte 6: Redbin version 3, expected 2", warnings[0].to_string());
+
+        let mut bytes = to_bytes(&ByteBuf::from(vec![b'a', 0xFF])).unwrap();
+        bytes[16] = 0x07;
+        let lossy = DeserializerOptions::new().lossy_strings(true);
+        let (text, warnings) = from_bytes_with_warnings::<String>(&bytes, lossy).unwrap();
+        assert_eq!("a\u{FFFD}", text);
+        assert_eq!(vec![super::Warning { offset: 29, message: "invalid UTF-8 replaced with U+FFFD".to_string() }], warnings);
+        assert!(from_bytes_with_warnings::<(i32, i32)>(&to_bytes(&(1, 2)).unwrap(), DeserializerOptions::new()).unwrap().1.is_empty());
+    }
+
+    #[test]
+    fn test_error_source() {
+        use crate::error::Error;
+        use std::error::Error as _;
+        let mut bytes = to_bytes(&ByteBuf::from(vec![b'a', 0xFF])).unwrap();
+        bytes[16] = 0x07;
+        let error = from_bytes::<String>(&bytes).unwrap_err();
+        assert!(matches!(error, DeError::Encoding(_)));
+        assert_eq!("invalid UTF-8 in string!", error.to_string());
+        assert!(error.source().unwrap().is::<std::str::Utf8Error>());
+
+        let error = Error::File { path: "a.redbin".into(), error: Box::new(error.into()) };
+        let chain: Vec<String> = std::iter::successors(Some(&error as &dyn std::error::Error), |&e| e.source())
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(vec![
+            "a.redbin: invalid UTF-8 in string!".to_string(),
+            "invalid UTF-8 in string!".to_string(),
+            "invalid utf-8 sequence of 1 bytes from index 1".to_string(),
+        ], chain);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_reset() {
+        use serde::Deserialize;
+        let messages: Vec<Vec<u8>> = ["żółw", "a", "💖"].iter().map(|s| to_bytes(&(s, 1)).unwrap()).collect();
+        let mut deserializer = super::Deserializer::from_bytes(&[]);
+        for (message, text) in messages.iter().zip(["żółw", "a", "💖"]) {
+            deserializer.reset(message);
+            deserializer.read_header().unwrap();
+            assert_eq!((String::from(text), 1), <(String, i32)>::deserialize(&mut deserializer).unwrap());
+            assert!(deserializer.remaining().is_empty());
+            assert_eq!(Ok(()), deserializer.end());
+        }
+        let mut message = to_bytes(&1).unwrap();
+        message.extend_from_within(16..);
+        deserializer.reset(&message);
+        deserializer.read_header().unwrap();
+        assert_eq!(1, i32::deserialize(&mut deserializer).unwrap());
+        assert_eq!(Err(DeError::TrailingBytes), deserializer.end());
+
+        let message = to_bytes(&("💖💖💖", "ż", "💖")).unwrap();
+        deserializer.reset(&message);
+        deserializer.read_header().unwrap();
+        assert_eq!(("💖💖💖".to_string(), "ż".to_string(), "💖".to_string()),
+            <(String, String, String)>::deserialize(&mut deserializer).unwrap());
+    }
+
+    #[cfg(feature = "ascii-only")]
+    #[test]
+    fn test_ascii_only() {
+        assert_eq!("ascii", from_bytes::<String>(&to_bytes(&"ascii").unwrap()).unwrap());
+        let err = from_bytes::<String>(&to_bytes(&"💖").unwrap()).unwrap_err();
+        assert_eq!(DeError::Message(message!("UCS-{} string! read by an ascii-only build", 4)), err);
+        assert!(from_bytes::<Vec<char>>(&to_bytes(&"💖").unwrap()).is_err());
+        assert!(crate::encoding::decode_ucs4(&crate::encoding::encode_ucs4("a")).is_err());
+    }
+
+    #[test]
+    fn test_position() {
+        use serde::Deserialize;
+        let mut bytes = to_bytes(&(7, "a")).unwrap();
+        bytes.extend_from_slice(b"tail");
+        let mut deserializer = super::Deserializer::from_bytes(&bytes);
+        assert_eq!((0, bytes.len()), (deserializer.position(), deserializer.remaining().len()));
+        deserializer.read_header().unwrap();
+        assert_eq!(16, deserializer.position());
+        assert_eq!((7, String::from("a")), <(i32, String)>::deserialize(&mut deserializer).unwrap());
+        assert_eq!(bytes.len() - 4, deserializer.position());
+        assert_eq!(b"tail", deserializer.into_remaining());
+    }
+
+    #[test]
+    fn test_truncated() {
+        use serde_derive::Serialize;
+        let source = "[a: 1 b: 2 \"x\" 2.5 [c 'd :e] #\"z\" #{0102} #(k: 1) %file.txt a/b/c <tag> /ref]";
+        let mut value = crate::parse_red(source).unwrap();
+        if let Value::Block(values) = &mut value {
+            values.extend(vec![Value::Logic(true), Value::None]);
+        }
+        let bytes = to_bytes(&value).unwrap();
+        assert!(from_bytes::<Value>(&bytes).is_ok());
+        assert_eq!(Err(DeError::Eof), from_bytes::<Value>(&bytes[..41]));
+        for n in 0..bytes.len() {
+            assert!(from_bytes::<Value>(&bytes[..n]).is_err(), "{} bytes", n);
+        }
+
+        #[derive(Serialize, Deserialize, Debug)]
+        struct Record {
+            a: i32,
+            b: Option<f64>,
+            c: (String, bool, char),
+            d: Vec<u8>,
+            #[serde(with = "crate::adapters::Vector")]
+            e: Vec<i32>,
+            #[serde(with = "crate::adapters::Date")]
+            f: std::time::SystemTime,
+            #[serde(with = "crate::adapters::Ip")]
+            g: std::net::Ipv4Addr,
+        }
+        let record = Record {
+            a: 1,
+            b: Some(2.5),
+            c: (String::from("x"), true, 'y'),
+            d: vec![1, 2],
+            e: vec![3, 4],
+            f: std::time::UNIX_EPOCH,
+            g: std::net::Ipv4Addr::LOCALHOST,
+        };
+        let bytes = to_bytes(&record).unwrap();
+        assert!(from_bytes::<Record>(&bytes).is_ok());
+        for n in 0..bytes.len() {
+            assert!(from_bytes::<Record>(&bytes[..n]).is_err(), "{} bytes", n);
+            let _ = crate::reader::Records::<Value>::new(&bytes[..n]).map(|records| records.recover(true).count());
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        /// Keeps `message offset=.. record_type=..` of every event.
+        #[derive(Clone, Default)]
+        struct Events(Arc<Mutex<Vec<String>>>);
+
+        struct Line(String);
+
+        impl Visit for Line {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                if field.name() == "message" {
+                    self.0.insert_str(0, &format!("{:?}", value));
+                } else {
+                    self.0.push_str(&format!(" {}={:?}", field.name(), value));
+                }
+            }
+        }
+
+        impl tracing::Subscriber for Events {
+            fn enabled(&self, _: &Metadata) -> bool { true }
+            fn new_span(&self, _: &Attributes) -> Id { Id::from_u64(1) }
+            fn record(&self, _: &Id, _: &Record) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event) {
+                let mut line = Line(String::new());
+                event.record(&mut line);
+                self.0.lock().unwrap().push(line.0);
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let events = Events::default();
+        let bytes = to_bytes(&(1, "a")).unwrap();
+        tracing::subscriber::with_default(events.clone(), || {
+            from_bytes::<(i32, String)>(&bytes).unwrap();
+            assert!(from_bytes::<(i32, i32)>(&bytes).is_err());
+        });
+        let events = events.0.lock().unwrap();
+        assert_eq!(&events[..4], &[
+            "decode record offset=16 record_type=5",
+            "decode record offset=28 record_type=11",
+            "decode record offset=36 record_type=7",
+            "decode record offset=16 record_type=5",
+        ]);
+        assert!(events.last().unwrap().starts_with("decode failed offset=36 error="), "{:?}", events);
+    }
 }
@@ -0,0 +1,109 @@
+//! Conversions between UTF-8 and the little-endian UCS-2 and UCS-4 Red
+//! stores wide strings in, for tools working with records directly:
+//!
+//! ```
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let content = redbin::encoding::encode_ucs2("żółw")?;
+//! # #[cfg(not(feature = "ascii-only"))]
+//! assert_eq!("żółw", redbin::encoding::decode_ucs2(&content)?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The deserializer reads wide strings with these too.
+
+use crate::error::{DeError, SerError};
+use std::convert::TryFrom;
+
+/// `text` as UCS-2LE, failing on characters beyond U+FFFF.
+pub fn encode_ucs2(text: &str) -> Result<Vec<u8>, SerError> {
+    let mut bytes = Vec::with_capacity(text.len() * 2);
+    for c in text.chars() {
+        let unit = u16::try_from(c as u32)
+            .map_err(|_| SerError::Message(message!("{:?} doesn't fit a UCS-2 character", c)))?;
+        bytes.extend_from_slice(&unit.to_le_bytes());
+    }
+    Ok(bytes)
+}
+
+/// `text` as UCS-4LE.
+pub fn encode_ucs4(text: &str) -> Vec<u8> {
+    text.chars().flat_map(|c| (c as u32).to_le_bytes()).collect()
+}
+
+/// Text of UCS-2LE `bytes`, failing on surrogates, which UCS-2 doesn't
+/// have, and on a trailing odd byte.
+pub fn decode_ucs2(bytes: &[u8]) -> Result<String, DeError> {
+    let mut text = String::with_capacity(bytes.len());
+    decode_ucs2_into(bytes, &mut text)?;
+    Ok(text)
+}
+
+/// Like `decode_ucs2`, appending to `text` so that a buffer can be reused
+/// from string to string.
+#[cfg(not(feature = "ascii-only"))]
+pub fn decode_ucs2_into(bytes: &[u8], text: &mut String) -> Result<(), DeError> {
+    if !bytes.len().is_multiple_of(2) {
+        return Err(DeError::Message(message!("UCS-2 text of an odd number of bytes")));
+    }
+    for unit in bytes.chunks_exact(2) {
+        let unit = u16::from_le_bytes([unit[0], unit[1]]);
+        text.push(char::from_u32(u32::from(unit)).ok_or_else(|| DeError::Message(message!("invalid UCS-2 character {:#X}", unit)))?);
+    }
+    Ok(())
+}
+
+/// Text of UCS-4LE `bytes`, failing on code points that aren't characters
+/// and on trailing bytes.
+pub fn decode_ucs4(bytes: &[u8]) -> Result<String, DeError> {
+    let mut text = String::with_capacity(bytes.len());
+    decode_ucs4_into(bytes, &mut text)?;
+    Ok(text)
+}
+
+/// Like `decode_ucs4`, appending to `text`.
+#[cfg(not(feature = "ascii-only"))]
+pub fn decode_ucs4_into(bytes: &[u8], text: &mut String) -> Result<(), DeError> {
+    if !bytes.len().is_multiple_of(4) {
+        return Err(DeError::Message(message!("UCS-4 text of a number of bytes not a multiple of 4")));
+    }
+    for unit in bytes.chunks_exact(4) {
+        let code = u32::from_le_bytes([unit[0], unit[1], unit[2], unit[3]]);
+        text.push(char::from_u32(code).ok_or_else(|| DeError::Message(message!("invalid UCS-4 character {:#X}", code)))?);
+    }
+    Ok(())
+}
+
+// An `ascii-only` build has no decoders.
+#[cfg(feature = "ascii-only")]
+pub fn decode_ucs2_into(_bytes: &[u8], _text: &mut String) -> Result<(), DeError> {
+    Err(DeError::Message(message!("UCS-2 string! read by an ascii-only build")))
+}
+
+#[cfg(feature = "ascii-only")]
+pub fn decode_ucs4_into(_bytes: &[u8], _text: &mut String) -> Result<(), DeError> {
+    Err(DeError::Message(message!("UCS-4 string! read by an ascii-only build")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_ucs2, decode_ucs4, encode_ucs2, encode_ucs4};
+    use crate::error::DeError;
+
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_encoding() {
+        let ucs2 = encode_ucs2("żółw").unwrap();
+        assert_eq!(vec![0x7C, 0x01, 0xF3, 0x00, 0x42, 0x01, 0x77, 0x00], ucs2);
+        assert_eq!(Ok("żółw".to_string()), decode_ucs2(&ucs2));
+        assert!(encode_ucs2("💖").is_err());
+
+        let ucs4 = encode_ucs4("a💖");
+        assert_eq!(vec![0x61, 0x00, 0x00, 0x00, 0x96, 0xF4, 0x01, 0x00], ucs4);
+        assert_eq!(Ok("a💖".to_string()), decode_ucs4(&ucs4));
+
+        assert_eq!(Err(DeError::Message(message!("invalid UCS-2 character {:#X}", 0xD800))), decode_ucs2(&[0x00, 0xD8]));
+        assert!(decode_ucs2(&ucs2[1..]).is_err());
+        assert!(decode_ucs4(&[0x00, 0x00, 0x11, 0x00]).is_err());
+    }
+}
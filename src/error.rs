@@ -6,69 +6,317 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+//! Errors of writing documents, `SerError`, of reading them, `DeError`, and
+//! of everything else, `Error`, which holds either of the first two.
+//!
+//! Each variant keeps its meaning across releases; new ones may be added,
+//! so matches need a wildcard arm.
+//!
+//! For devices logging without a heap, `static_message` describes an error
+//! with a `&'static str`, with the numbers of some variants in their fields.
+//! The `static-errors` feature keeps the templates of `Message` and `Io`
+//! texts, e.g. `"Unexpected unit size {}."`, instead of formatting them, so
+//! these variants don't allocate; `DeError::Encoding` still keeps its source
+//! in an `Arc`, and `Error` its texts in `String`s. The `defmt` feature
+//! implements `defmt::Format` for `SerError` and `DeError` on targets other
+//! than desktop systems and wasm.
+
 use serde::{de, ser};
+use std::borrow::Cow;
+use std::error::Error as StdError;
 use std::fmt::{self, Display};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-// This is a bare-bones implementation. A real library would provide additional
-// information in its error type, for example the line and column at which the
-// error occurred, the byte offset into the input, or the current key being
-// processed.
+/// Why a value couldn't be written as Redbin.
 #[derive(Clone, Debug, PartialEq)]
-pub enum Error {
-    // One or more variants that can be created by data structures through the
-    // `ser::Error` and `de::Error` traits. For example the Serialize impl for
-    // Mutex<T> might return an error because the mutex is poisoned, or the
-    // Deserialize impl for a struct may return an error because a required
-    // field is missing.
-    Message(String),
-
-    // Zero or more variants that can be created directly by the Serializer and
-    // Deserializer without going through `ser::Error` and `de::Error`. These
-    // are specific to the format, in this case JSON.
+#[non_exhaustive]
+pub enum SerError {
+    /// Raised by a `Serialize` impl, or a value Redbin has no record for,
+    /// e.g. an integer beyond 32 bits; the message is for people only.
+    Message(Cow<'static, str>),
+    /// A series, block or document beyond the 2 GiB Redbin sizes allow.
+    TooLarge,
+    /// A document not fitting the memory it's written to, with its size.
+    OutputTooSmall { needed: usize },
+    /// The stream written to failed.
+    Io(Cow<'static, str>),
+}
+
+/// Why a document couldn't be read.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum DeError {
+    /// Raised by a `Deserialize` impl, like a missing struct field, or
+    /// content that's invalid in a way no other variant covers; the message
+    /// is for people only.
+    Message(Cow<'static, str>),
+    /// The input ends before the document or record does.
     Eof,
+    /// The header or symbol table is malformed.
     Syntax,
+    /// A record of another type than the one to read, e.g. a `string!`
+    /// where an integer is expected.
     ExpectedLogic,
     ExpectedInteger,
     ExpectedFloat,
     ExpectedBlock,
     ExpectedString,
-    ExpectedNull,
-    ExpectedArray,
-    ExpectedArrayEnd,
-    ExpectedVariantColon,
+    /// Not a `paren!` of an enum variant and its value.
     ExpectedEnum,
     ExpectedChar,
     ExpectedBinary,
     ExpectedNone,
+    ExpectedWord,
+    ExpectedVector,
+    ExpectedTuple,
+    ExpectedPoint,
+    ExpectedDate,
+    /// A block of keys and values of an odd length.
     ExpectedEvenLength,
+    /// A key found twice where `DuplicateKeys::Error` forbids it.
+    DuplicateKey,
+    /// A key at the end of a block read as a map, without its value.
     NoMapValue,
+    /// Bytes left after the value read.
     TrailingBytes,
-    SizeMismatch
+    /// A document whose checksum trailer doesn't match its content.
+    ChecksumMismatch,
+    /// A size declared beyond the 2 GiB limit of Redbin.
+    TooLarge,
+    /// Zero bytes where records can't be padded, with their offset.
+    UnexpectedPadding { offset: usize },
+    /// The stream read from failed.
+    Io(Cow<'static, str>),
+    /// Text that can't be converted to UTF-8, with the converter's error as
+    /// its `source()`.
+    Encoding(EncodingError),
+}
+
+/// A failure converting text, keeping the error of the converter, like a
+/// `std::str::Utf8Error`, that caused it.
+#[derive(Clone, Debug)]
+pub struct EncodingError {
+    message: &'static str,
+    source: Arc<dyn StdError + Send + Sync>,
+}
+
+impl EncodingError {
+    #[cfg_attr(not(feature = "de"), allow(dead_code))]
+    pub(crate) fn new(message: &'static str, source: impl StdError + Send + Sync + 'static) -> Self {
+        EncodingError { message, source: Arc::new(source) }
+    }
+}
+
+// errors of converters rarely compare, so their messages do
+impl PartialEq for EncodingError {
+    fn eq(&self, other: &Self) -> bool {
+        self.message == other.message && self.source.to_string() == other.source.to_string()
+    }
+}
+
+/// Failures of anything but plain writing or reading of documents, like
+/// parsing Red source or working with files, or either of those.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    Ser(SerError),
+    De(DeError),
+    /// Red source text that `parse_red` can't read, with where it is.
+    Parse { line: usize, column: usize, message: String },
+    /// Failures opening, reading or writing a file.
+    Io(String),
+    /// Any failure with a file, with the file's path.
+    File { path: PathBuf, error: Box<Error> },
+}
+
+/// The text of `error`, e.g. a `std::io::Error`, or with `static-errors`,
+/// `fallback`.
+#[cfg(not(feature = "static-errors"))]
+pub(crate) fn text_of(error: impl Display, _fallback: &'static str) -> Cow<'static, str> {
+    Cow::Owned(error.to_string())
+}
+
+#[cfg(feature = "static-errors")]
+pub(crate) fn text_of(_error: impl Display, fallback: &'static str) -> Cow<'static, str> {
+    Cow::Borrowed(fallback)
 }
 
-impl ser::Error for Error {
+impl ser::Error for SerError {
     fn custom<T: Display>(msg: T) -> Self {
-        Error::Message(msg.to_string())
+        SerError::Message(text_of(msg, "rejected by a Serialize impl"))
     }
 }
 
-impl de::Error for Error {
+impl de::Error for DeError {
     fn custom<T: Display>(msg: T) -> Self {
-        Error::Message(msg.to_string())
+        DeError::Message(text_of(msg, "rejected by a Deserialize impl"))
+    }
+}
+
+impl From<SerError> for Error {
+    fn from(error: SerError) -> Self {
+        Error::Ser(error)
+    }
+}
+
+impl From<DeError> for Error {
+    fn from(error: DeError) -> Self {
+        Error::De(error)
+    }
+}
+
+impl SerError {
+    /// What went wrong, without the text or numbers some variants have,
+    /// e.g. the message of `SerError::Message`.
+    pub fn static_message(&self) -> &'static str {
+        match self {
+            SerError::Message(_) => "value can't be written",
+            SerError::TooLarge => "size beyond the 2 GiB limit of Redbin",
+            SerError::OutputTooSmall { .. } => "document doesn't fit the output",
+            SerError::Io(_) => "writing failed",
+        }
+    }
+}
+
+impl DeError {
+    /// What went wrong, without the text or numbers some variants have,
+    /// e.g. the message of `DeError::Message`.
+    pub fn static_message(&self) -> &'static str {
+        match self {
+            DeError::Message(_) => "value can't be read",
+            DeError::Eof => "unexpected end of input",
+            DeError::Syntax => "malformed input",
+            DeError::ExpectedLogic => "expected logic!",
+            DeError::ExpectedInteger => "expected integer!",
+            DeError::ExpectedFloat => "expected float!",
+            DeError::ExpectedBlock => "expected block!",
+            DeError::ExpectedString => "expected string!",
+            DeError::ExpectedEnum => "expected enum variant paren!",
+            DeError::ExpectedChar => "expected char!",
+            DeError::ExpectedBinary => "expected binary!",
+            DeError::ExpectedNone => "expected none!",
+            DeError::ExpectedWord => "expected word!",
+            DeError::ExpectedVector => "expected vector!",
+            DeError::ExpectedTuple => "expected tuple!",
+            DeError::ExpectedPoint => "expected pair! or point!",
+            DeError::ExpectedDate => "expected date!",
+            DeError::ExpectedEvenLength => "expected block of even length",
+            DeError::DuplicateKey => "duplicate key",
+            DeError::NoMapValue => "key without value",
+            DeError::TrailingBytes => "trailing bytes after value",
+            DeError::ChecksumMismatch => "checksum mismatch",
+            DeError::TooLarge => "size beyond the 2 GiB limit of Redbin",
+            DeError::UnexpectedPadding { .. } => "unexpected padding",
+            DeError::Io(_) => "reading failed",
+            DeError::Encoding(_) => "text can't be converted to UTF-8",
+        }
+    }
+}
+
+impl Display for SerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SerError::Message(msg) | SerError::Io(msg) => f.write_str(msg),
+            SerError::OutputTooSmall { needed } => write!(f, "document of {} bytes doesn't fit the output", needed),
+            _ => f.write_str(self.static_message()),
+        }
+    }
+}
+
+impl Display for DeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeError::Message(msg) | DeError::Io(msg) => f.write_str(msg),
+            DeError::UnexpectedPadding { offset } => write!(f, "unexpected padding at byte {}", offset),
+            DeError::Encoding(error) => error.fmt(f),
+            _ => f.write_str(self.static_message()),
+        }
+    }
+}
+
+// defmt's symbols break the exports of host cdylibs like `redbin-capi`
+#[cfg(all(feature = "defmt", not(any(windows, target_os = "linux", target_os = "macos", target_family = "wasm"))))]
+impl defmt::Format for SerError {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            SerError::Message(msg) | SerError::Io(msg) => defmt::write!(f, "{=str}", &**msg),
+            SerError::OutputTooSmall { needed } => defmt::write!(f, "document of {=usize} bytes doesn't fit the output", *needed),
+            _ => defmt::write!(f, "{=str}", self.static_message()),
+        }
+    }
+}
+
+#[cfg(all(feature = "defmt", not(any(windows, target_os = "linux", target_os = "macos", target_family = "wasm"))))]
+impl defmt::Format for DeError {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            DeError::Message(msg) | DeError::Io(msg) => defmt::write!(f, "{=str}", &**msg),
+            DeError::UnexpectedPadding { offset } => defmt::write!(f, "unexpected padding at byte {=usize}", *offset),
+            DeError::Encoding(error) => defmt::write!(f, "{=str}", error.message),
+            _ => defmt::write!(f, "{=str}", self.static_message()),
+        }
     }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::Message(msg) => write!(f, "{}", msg),
-            Error::Eof => f.write_str("unexpected end of input"),
-            /* and so forth */
-            _ => unimplemented!(),
+            Error::Ser(error) => error.fmt(f),
+            Error::De(error) => error.fmt(f),
+            Error::Parse { line, column, message } => write!(f, "{}:{}: {}", line, column, message),
+            Error::Io(msg) => f.write_str(msg),
+            Error::File { path, error } => write!(f, "{}: {}", path.display(), error),
+        }
+    }
+}
+
+impl Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.message)
+    }
+}
+
+impl StdError for EncodingError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+impl StdError for SerError {}
+
+impl StdError for DeError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            // shown as the encoding error itself
+            DeError::Encoding(error) => error.source(),
+            _ => None,
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Ser(error) => error.source(),
+            Error::De(error) => error.source(),
+            Error::File { error, .. } => Some(&**error),
+            _ => None,
         }
     }
 }
 
-impl std::error::Error for Error {}
+#[cfg(test)]
+mod tests {
+    use super::{DeError, SerError};
+
+    #[test]
+    fn test_static_message() {
+        assert_eq!("document doesn't fit the output", SerError::OutputTooSmall { needed: 20 }.static_message());
+        assert_eq!("value can't be read", DeError::Message(message!("missing field `x`")).static_message());
+        assert_eq!(DeError::Eof.static_message(), DeError::Eof.to_string());
+        assert_eq!("unexpected padding at byte 8", DeError::UnexpectedPadding { offset: 8 }.to_string());
+    }
+}
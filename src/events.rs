@@ -0,0 +1,434 @@
+//! A pull parser going through a Redbin document record by record, for
+//! filters and converters that shouldn't build whole values, e.g. of
+//! documents larger than memory.
+//!
+//! ```no_run
+//! # use redbin::events::{Event, EventReader};
+//! # use std::fs::File;
+//! # use std::io::BufReader;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut strings = 0;
+//! for event in EventReader::new(BufReader::new(File::open("data.redbin")?))? {
+//!     if let Event::StringStart(..) = event? {
+//!         strings += 1;
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::encoding;
+use crate::error::{text_of, DeError};
+use crate::reader::Header;
+use crate::types::byte::*;
+use crate::types::HEADER_LEN;
+use std::io::{ErrorKind, Read};
+
+type Result<T> = std::result::Result<T, DeError>;
+
+/// Size of the pieces strings and binaries are read in.
+const CHUNK: usize = 64 * 1024;
+
+/// Kinds of text series.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Text {
+    String,
+    File,
+    Url,
+    Tag,
+    Email,
+}
+
+/// Kinds of series of values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum List {
+    Block,
+    Paren,
+    Path,
+    SetPath,
+    GetPath,
+    LitPath,
+    /// keys followed by their values
+    Map,
+}
+
+/// One step through a document. A block is its `BlockStart`, the events of
+/// its elements and a `BlockEnd`; text and binaries come in chunks between
+/// their start and end.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    None,
+    Logic(bool),
+    Integer(i32),
+    Float(f64),
+    Char(char),
+    Word(String),
+    SetWord(String),
+    LitWord(String),
+    GetWord(String),
+    Refinement(String),
+    Issue(String),
+    /// start of a text series, with its length in characters
+    StringStart(Text, usize),
+    StringChunk(String),
+    StringEnd,
+    /// start of a `binary!`, with its length in bytes
+    BinaryStart(usize),
+    BinaryChunk(Vec<u8>),
+    BinaryEnd,
+    /// start of a block-like series, with its number of elements
+    BlockStart(List, usize),
+    BlockEnd,
+    /// a record of another type, e.g. `date!` or `vector!`, as it's written
+    Record(Vec<u8>),
+}
+
+/// The series being read a chunk at a time.
+struct Series {
+    text: bool,
+    unit: usize,
+    /// bytes of content still to read
+    left: usize,
+    padding: usize,
+    /// start of a UTF-8 sequence cut by the end of the last chunk
+    partial: Vec<u8>,
+}
+
+/// Reads a document from `R` as a sequence of events, reading no more than
+/// a record, or a chunk of a series, at a time.
+///
+/// Failing to read the stream or a broken record is the last event.
+pub struct EventReader<R> {
+    reader: R,
+    header: Header,
+    symbols: Vec<String>,
+    /// root records left to read
+    roots: u32,
+    /// elements left in the open blocks, innermost last
+    open: Vec<usize>,
+    series: Option<Series>,
+    done: bool,
+}
+
+impl<R: Read> EventReader<R> {
+    /// Reads the header and the symbol table, which words are looked up in.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut bytes = [0x00; HEADER_LEN];
+        read_exact(&mut reader, &mut bytes)?;
+        let header = Header::parse(&bytes)?;
+        let mut symbols = Vec::new();
+        if header.has_symbol_table() {
+            let mut counts = [0x00; 8];
+            read_exact(&mut reader, &mut counts)?;
+            let count = read_u32(&counts, 0) as usize;
+            let size = read_u32(&counts, 4) as usize;
+            let offsets = read_vec(&mut reader, count.checked_mul(4).ok_or(DeError::Syntax)?)?;
+            let names = read_vec(&mut reader, size)?;
+            for i in 0..count {
+                let name = names.get(read_u32(&offsets, i * 4) as usize..).ok_or(DeError::Syntax)?;
+                let end = name.iter().position(|&b| b == 0x00).ok_or(DeError::Syntax)?;
+                symbols.push(std::str::from_utf8(&name[..end]).map_err(|e| DeError::Message(text_of(e, "symbol isn't UTF-8")))?.to_string());
+            }
+        }
+        Ok(EventReader { reader, header, symbols, roots: header.length, open: Vec::new(), series: None, done: false })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Depth of the innermost open block, 0 between root records.
+    pub fn depth(&self) -> usize {
+        self.open.len()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn next_event(&mut self) -> Result<Option<Event>> {
+        if self.series.is_some() {
+            return self.read_chunk().map(Some);
+        }
+        match self.open.last_mut() {
+            Some(0) => {
+                self.open.pop();
+                return Ok(Some(Event::BlockEnd));
+            }
+            Some(left) => *left -= 1,
+            None if self.roots == 0 => return Ok(None),
+            None => self.roots -= 1,
+        }
+        self.read_record().map(Some)
+    }
+
+    fn read_record(&mut self) -> Result<Event> {
+        let mut record = [0x00; 4];
+        read_exact(&mut self.reader, &mut record)?;
+        // padding is zeros, and no record type is 0
+        while record[0] == 0x00 {
+            let padding = record.iter().take_while(|&&b| b == 0x00).count();
+            record.copy_within(padding.., 0);
+            read_exact(&mut self.reader, &mut record[4 - padding..])?;
+        }
+        let record_type = record[0];
+        let event = match record_type {
+            NONE => Event::None,
+            LOGIC => Event::Logic(self.read_word()? != 0),
+            INTEGER => Event::Integer(self.read_word()? as i32),
+            CHAR => {
+                let code = self.read_word()?;
+                Event::Char(char::from_u32(code).ok_or_else(|| DeError::Message(message!("invalid char! {:#X}", code)))?)
+            }
+            FLOAT => {
+                let high = u64::from(self.read_word()?);
+                let low = u64::from(self.read_word()?);
+                Event::Float(f64::from_bits(high << 32 | low))
+            }
+            WORD..=ISSUE => {
+                let symbol = self.read_word()? as usize;
+                let mut context = [0x00; 8];
+                read_exact(&mut self.reader, &mut context)?;
+                let name = self.symbols.get(symbol)
+                    .ok_or_else(|| DeError::Message(message!("Symbol {} not in symbol table.", symbol)))?.clone();
+                match record_type {
+                    WORD => Event::Word(name),
+                    SET_WORD => Event::SetWord(name),
+                    LIT_WORD => Event::LitWord(name),
+                    GET_WORD => Event::GetWord(name),
+                    REFINEMENT => Event::Refinement(name),
+                    _ => Event::Issue(name),
+                }
+            }
+            BLOCK | PAREN | PATH..=GET_PATH => {
+                let _head = self.read_word()?;
+                let len = series_size(self.read_word()?)?;
+                self.open.push(len);
+                let list = match record_type {
+                    BLOCK => List::Block,
+                    PAREN => List::Paren,
+                    PATH => List::Path,
+                    LIT_PATH => List::LitPath,
+                    SET_PATH => List::SetPath,
+                    _ => List::GetPath,
+                };
+                Event::BlockStart(list, len)
+            }
+            MAP => {
+                let len = series_size(self.read_word()?)?;
+                self.open.push(len);
+                Event::BlockStart(List::Map, len)
+            }
+            STRING | FILE | URL | TAG | EMAIL | BINARY => return self.start_series(record),
+            _ => Event::Record(self.read_other(record)?),
+        };
+        Ok(event)
+    }
+
+    fn read_word(&mut self) -> Result<u32> {
+        let mut word = [0x00; 4];
+        read_exact(&mut self.reader, &mut word)?;
+        Ok(u32::from_le_bytes(word))
+    }
+
+    fn start_series(&mut self, record: [u8; 4]) -> Result<Event> {
+        let unit = record[1] as usize;
+        if record[2] & 0b_00001000 != 0 {
+            return Err(DeError::Message(message!("Redbin references not supported yet.")));
+        }
+        let text = record[0] != BINARY;
+        if !matches!((text, unit), (true, 1) | (true, 2) | (true, 4) | (false, 1)) {
+            return Err(DeError::Message(message!("Unexpected unit size {}.", unit)));
+        }
+        let head = series_size(self.read_word()?)?;
+        let length = series_size(self.read_word()?)?;
+        if head > length {
+            return Err(DeError::Syntax);
+        }
+        let size = length.checked_mul(unit).ok_or(DeError::TooLarge)?;
+        // elements before the head aren't part of the value
+        skip(&mut self.reader, head * unit)?;
+        let len = length - head;
+        self.series = Some(Series { text, unit, left: len * unit, padding: (4 - size % 4) % 4, partial: Vec::new() });
+        Ok(match record[0] {
+            BINARY => Event::BinaryStart(len),
+            STRING => Event::StringStart(Text::String, len),
+            FILE => Event::StringStart(Text::File, len),
+            URL => Event::StringStart(Text::Url, len),
+            TAG => Event::StringStart(Text::Tag, len),
+            _ => Event::StringStart(Text::Email, len),
+        })
+    }
+
+    /// The next chunk of the open series, or its end.
+    fn read_chunk(&mut self) -> Result<Event> {
+        let series = self.series.as_mut().expect("a series is open");
+        if series.left == 0 {
+            let series = self.series.take().expect("a series is open");
+            skip(&mut self.reader, series.padding)?;
+            if !series.partial.is_empty() {
+                return Err(DeError::Message(message!("string ends inside a UTF-8 sequence")));
+            }
+            return Ok(if series.text {Event::StringEnd} else {Event::BinaryEnd});
+        }
+        let n = series.left.min(CHUNK / series.unit * series.unit);
+        let mut bytes = std::mem::take(&mut series.partial);
+        let start = bytes.len();
+        bytes.resize(start + n, 0x00);
+        read_exact(&mut self.reader, &mut bytes[start..])?;
+        series.left -= n;
+        if !series.text {
+            return Ok(Event::BinaryChunk(bytes));
+        }
+        let text = match series.unit {
+            1 => match String::from_utf8(bytes) {
+                Ok(text) => text,
+                Err(e) if e.utf8_error().error_len().is_none() => {
+                    // carried over to the next chunk
+                    let valid = e.utf8_error().valid_up_to();
+                    let mut bytes = e.into_bytes();
+                    series.partial = bytes.split_off(valid);
+                    String::from_utf8(bytes).expect("valid up to here")
+                }
+                Err(e) => return Err(DeError::Message(text_of(e.utf8_error(), "invalid UTF-8 in string!"))),
+            },
+            2 => encoding::decode_ucs2(&bytes)?,
+            _ => encoding::decode_ucs4(&bytes)?,
+        };
+        Ok(Event::StringChunk(text))
+    }
+
+    /// Bytes of a record of a type without events of its own.
+    fn read_other(&mut self, record: [u8; 4]) -> Result<Vec<u8>> {
+        let len = match record[0] {
+            TIME | PAIR | POINT2D => 12,
+            DATE | POINT3D | TUPLE => 16,
+            VECTOR => 16,
+            t => return Err(DeError::Message(message!("Unsupported record type {}.", t))),
+        };
+        let mut bytes = record.to_vec();
+        bytes.resize(len, 0x00);
+        read_exact(&mut self.reader, &mut bytes[4..])?;
+        if record[0] == VECTOR {
+            let unit = record[1] as usize;
+            let size = series_size(read_u32(&bytes, 8))?.checked_mul(unit).ok_or(DeError::TooLarge)?;
+            bytes.extend(read_vec(&mut self.reader, size.div_ceil(4) * 4)?);
+        }
+        Ok(bytes)
+    }
+}
+
+impl<R: Read> Iterator for EventReader<R> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let event = self.next_event().transpose();
+        if !matches!(event, Some(Ok(_))) {
+            self.done = true;
+        }
+        event
+    }
+}
+
+/// A length or position read from a record, at most 2 GiB.
+fn series_size(declared: u32) -> Result<usize> {
+    if declared > i32::MAX as u32 {
+        return Err(DeError::TooLarge);
+    }
+    Ok(declared as usize)
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+fn read_exact<R: Read>(reader: &mut R, buffer: &mut [u8]) -> Result<()> {
+    reader.read_exact(buffer).map_err(|e| match e.kind() {
+        ErrorKind::UnexpectedEof => DeError::Eof,
+        _ => DeError::Io(text_of(e, "reading failed")),
+    })
+}
+
+fn read_vec<R: Read>(reader: &mut R, len: usize) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let read = reader.take(len as u64).read_to_end(&mut bytes).map_err(|e| DeError::Io(text_of(e, "reading failed")))?;
+    if read < len {
+        return Err(DeError::Eof);
+    }
+    Ok(bytes)
+}
+
+fn skip<R: Read>(reader: &mut R, len: usize) -> Result<()> {
+    let skipped = std::io::copy(&mut reader.take(len as u64), &mut std::io::sink())
+        .map_err(|e| DeError::Io(text_of(e, "reading failed")))?;
+    if skipped < len as u64 {
+        return Err(DeError::Eof);
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "ser"))]
+mod tests {
+    use super::{Event, EventReader, List, Text};
+    use crate::adapters::RedString;
+    use crate::error::DeError;
+    use crate::{parse_red, to_bytes, to_bytes_with, SerializerOptions};
+    use serde_bytes::ByteBuf;
+
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_events() {
+        let value = parse_red(r#"[a: 1 "żółw" [#[none] 2.5 #"x"] b/c]"#).unwrap();
+        let document = to_bytes_with(&value, SerializerOptions::new()).unwrap();
+        let events: Vec<Event> = EventReader::new(&document[..]).unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(vec![
+            Event::BlockStart(List::Block, 5),
+            Event::SetWord("a".to_string()),
+            Event::Integer(1),
+            Event::StringStart(Text::String, 4),
+            Event::StringChunk("żółw".to_string()),
+            Event::StringEnd,
+            Event::BlockStart(List::Block, 3),
+            Event::None,
+            Event::Float(2.5),
+            Event::Char('x'),
+            Event::BlockEnd,
+            Event::BlockStart(List::Path, 2),
+            Event::Word("b".to_string()),
+            Event::Word("c".to_string()),
+            Event::BlockEnd,
+            Event::BlockEnd,
+        ], events);
+
+        let wide = RedString { unit: 2, head: 1, content: "żó".to_string() };
+        let wide = to_bytes(&(RedString::new("😀a".to_string()), wide)).unwrap();
+        let events: Vec<Event> = EventReader::new(&wide[..]).unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(Event::StringChunk("😀a".to_string()), events[2]);
+        assert_eq!(vec![Event::StringStart(Text::String, 1), Event::StringChunk("ó".to_string())], events[4..6]);
+
+        let mut events = EventReader::new(&document[..document.len() - 4]).unwrap();
+        assert_eq!(Some(Err(DeError::Eof)), events.by_ref().last());
+        assert_eq!(None, events.next());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_event_chunks() {
+        // a multibyte character across the end of the first chunk
+        let text = format!("{}ł{}", "a".repeat(super::CHUNK - 1), "b".repeat(10));
+        let document = to_bytes(&(&text, ByteBuf::from(vec![0x01; super::CHUNK + 1]))).unwrap();
+        let mut read = String::new();
+        let mut binary = 0;
+        for event in EventReader::new(&document[..]).unwrap() {
+            match event.unwrap() {
+                Event::StringChunk(chunk) => read.push_str(&chunk),
+                Event::BinaryChunk(chunk) => binary += chunk.len(),
+                _ => {}
+            }
+        }
+        assert_eq!((text, super::CHUNK + 1), (read, binary));
+    }
+}
@@ -0,0 +1,70 @@
+//! Conversions as methods of the values converted:
+//!
+//! ```
+//! use redbin::RedbinExt;
+//! # use serde_derive::{Deserialize, Serialize};
+//! # #[derive(Serialize, Deserialize)]
+//! # struct Config { name: String }
+//! # fn main() -> redbin::Result<()> {
+//! # let config = Config { name: "x".to_string() };
+//!
+//! let bytes = config.to_redbin()?;
+//! let config = Config::from_redbin(&bytes)?;
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "de")]
+use crate::error::DeError;
+#[cfg(feature = "ser")]
+use crate::error::SerError;
+#[cfg(feature = "de")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "ser")]
+use serde::Serialize;
+
+/// `to_bytes` and `from_slice_owned` as methods, for every type that
+/// implements `Serialize` or `DeserializeOwned` respectively.
+pub trait RedbinExt {
+    /// The value as a Redbin document, like `to_bytes`.
+    #[cfg(feature = "ser")]
+    fn to_redbin(&self) -> Result<Vec<u8>, SerError>
+    where
+        Self: Serialize,
+    {
+        crate::to_bytes(&self)
+    }
+
+    /// A value read from a whole Redbin document, like `from_slice_owned`.
+    #[cfg(feature = "de")]
+    fn from_redbin(bytes: &[u8]) -> Result<Self, DeError>
+    where
+        Self: DeserializeOwned,
+    {
+        crate::from_slice_owned(bytes)
+    }
+}
+
+impl<T: ?Sized> RedbinExt for T {}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use super::RedbinExt;
+    use crate::{to_bytes, DeError};
+    use serde_derive::{Deserialize, Serialize};
+
+    #[test]
+    fn test_redbin_ext() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+        let point = Point { x: 1, y: -2 };
+        let bytes = point.to_redbin().unwrap();
+        assert_eq!(to_bytes(&point).unwrap(), bytes);
+        assert_eq!(Ok(point), Point::from_redbin(&bytes));
+        assert_eq!("abc".to_redbin(), to_bytes(&"abc"));
+        assert_eq!(Err(DeError::ExpectedInteger), Vec::<i32>::from_redbin(&bytes));
+    }
+}
@@ -0,0 +1,98 @@
+//! Reading and writing whole Redbin files.
+
+#[cfg(feature = "de")]
+use crate::de::{from_bytes_with, DeserializerOptions};
+use crate::error::{Error, Result};
+#[cfg(feature = "ser")]
+use crate::ser::{to_bytes_with, SerializerOptions};
+#[cfg(feature = "de")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "ser")]
+use serde::ser::Serialize;
+use std::fs::File;
+#[cfg(feature = "de")]
+use std::io::{BufReader, Read};
+#[cfg(feature = "ser")]
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Attaches `path` to errors of `f`.
+fn in_file<T>(path: &Path, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    f().map_err(|error| Error::File { path: path.to_path_buf(), error: Box::new(error) })
+}
+
+fn io(e: std::io::Error) -> Error {
+    Error::Io(e.to_string())
+}
+
+#[cfg(feature = "de")]
+pub fn from_file<T, P>(path: P) -> Result<T>
+where
+    T: DeserializeOwned,
+    P: AsRef<Path>,
+{
+    from_file_with(path, DeserializerOptions::default())
+}
+
+#[cfg(feature = "de")]
+pub fn from_file_with<T, P>(path: P, options: DeserializerOptions) -> Result<T>
+where
+    T: DeserializeOwned,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    in_file(path, || {
+        let mut bytes = Vec::new();
+        BufReader::new(File::open(path).map_err(io)?).read_to_end(&mut bytes).map_err(io)?;
+        Ok(from_bytes_with(&bytes, options)?)
+    })
+}
+
+#[cfg(feature = "ser")]
+pub fn to_file<T, P>(path: P, value: &T) -> Result<()>
+where
+    T: Serialize,
+    P: AsRef<Path>,
+{
+    to_file_with(path, value, SerializerOptions::default())
+}
+
+#[cfg(feature = "ser")]
+pub fn to_file_with<T, P>(path: P, value: &T, options: SerializerOptions) -> Result<()>
+where
+    T: Serialize,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    in_file(path, || {
+        let bytes = to_bytes_with(value, options)?;
+        let mut file = BufWriter::new(File::create(path).map_err(io)?);
+        file.write_all(&bytes).map_err(io)?;
+        file.flush().map_err(io)
+    })
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use super::{from_file, to_file};
+    use crate::error::{DeError, Error};
+
+    #[test]
+    fn test_file() {
+        let path = std::env::temp_dir().join(format!("redbin-file-{}.redbin", std::process::id()));
+        to_file(&path, &(1, String::from("a"))).unwrap();
+        assert_eq!((1, String::from("a")), from_file(&path).unwrap());
+
+        match from_file::<(i32, i32), _>(&path) {
+            Err(Error::File { path: p, error }) => {
+                assert_eq!(path, p);
+                assert_eq!(Error::De(DeError::ExpectedInteger), *error);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        std::fs::remove_file(&path).unwrap();
+        let error = from_file::<(i32, String), _>(&path).unwrap_err();
+        assert!(error.to_string().starts_with(&path.display().to_string()));
+    }
+}
@@ -0,0 +1,289 @@
+//! Converting Redbin to JSON Lines for log pipelines, one line per root
+//! record, built on `events` so no document is held in memory:
+//!
+//! ```no_run
+//! # fn main() -> redbin::Result<()> {
+//! let stdout = std::io::stdout();
+//! redbin::jsonl::to_json_lines(std::io::stdin().lock(), stdout.lock())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Values are written as:
+//!
+//! - `none` as `null`, `logic!`, `integer!` and `float!` as themselves,
+//!   with infinities and NaN as `null`;
+//! - `char!`, text series and words as strings, words as molded, e.g.
+//!   `"'a"` for a `lit-word!`;
+//! - `binary!` as a string of its bytes in hexadecimal;
+//! - blocks of `set-word!`s each followed by its value as objects, other
+//!   blocks, parens and paths as arrays; blocks starting with a `set-word!`
+//!   are read ahead to their end to check every key, so only those are
+//!   held in memory, as events;
+//! - `map!` as objects, its keys as strings;
+//! - records of other types, like `date!`, as hexadecimal strings of their
+//!   Redbin bytes.
+
+use crate::error::{DeError, Error, Result};
+use crate::events::{Event, EventReader, List};
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read, Write};
+
+/// An open block, and whether its next element is the first, and a key.
+#[derive(Clone, Copy)]
+enum Frame {
+    Array { first: bool },
+    /// a block of `set-word!`s each followed by its value
+    Object { first: bool, key: bool },
+    Map { first: bool, key: bool },
+}
+
+/// Writes every root record of the Redbin documents read from `reader`, one
+/// after another, to `writer` as a line of JSON, returning the number of
+/// lines written.
+///
+/// Lines are written as their records are read; on a failure, the line of
+/// the record failing is left unfinished.
+pub fn to_json_lines<R: Read, W: Write>(mut reader: R, mut writer: W) -> Result<u64> {
+    let mut lines = 0;
+    loop {
+        let mut first = [0x00];
+        match reader.read(&mut first) {
+            Ok(0) => return Ok(lines),
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(Error::Io(e.to_string())),
+        }
+        let events = EventReader::new((&first[..]).chain(&mut reader))?;
+        lines += Lines { events, pending: VecDeque::new(), writer: &mut writer, open: Vec::new(), series: false, key: false }.write()?;
+    }
+}
+
+struct Lines<R, W> {
+    events: EventReader<R>,
+    /// events read ahead
+    pending: VecDeque<Event>,
+    writer: W,
+    open: Vec<Frame>,
+    /// whether a string or binary is open
+    series: bool,
+    /// whether the open string is a key of a map
+    key: bool,
+}
+
+impl<R: Read, W: Write> Lines<R, W> {
+    fn next(&mut self) -> Option<Result<Event>> {
+        self.pending.pop_front().map(Ok).or_else(|| Some(self.events.next()?.map_err(Error::from)))
+    }
+
+    /// Writes the root records of the document, returning their number.
+    fn write(mut self) -> Result<u64> {
+        let mut lines = 0;
+        while let Some(event) = self.next() {
+            match event? {
+                Event::StringChunk(text) => escape(&mut self.writer, &text)?,
+                Event::BinaryChunk(bytes) => self.put(&hex(&bytes))?,
+                Event::StringEnd | Event::BinaryEnd => {
+                    self.series = false;
+                    self.put("\"")?;
+                    if std::mem::take(&mut self.key) {
+                        self.put(":")?;
+                    }
+                }
+                Event::BlockEnd => match self.open.pop() {
+                    Some(Frame::Array { .. }) => self.put("]")?,
+                    _ => self.put("}")?,
+                },
+                event => {
+                    if self.begin(&event)? {
+                        continue;
+                    }
+                    self.value(event)?;
+                }
+            }
+            if self.open.is_empty() && !self.series {
+                self.put("\n")?;
+                lines += 1;
+            }
+        }
+        Ok(lines)
+    }
+
+    /// Writes what goes before a value in the open block, returning whether
+    /// `event` was a key, written already.
+    fn begin(&mut self, event: &Event) -> Result<bool> {
+        let (first, key) = match self.open.last_mut() {
+            None => return Ok(false),
+            Some(Frame::Array { first }) => (std::mem::replace(first, false), None),
+            Some(Frame::Object { first, key }) | Some(Frame::Map { first, key }) => {
+                let at_key = std::mem::replace(key, !*key);
+                (at_key && std::mem::replace(first, false), Some(at_key))
+            }
+        };
+        if key == Some(false) {
+            return Ok(false);
+        }
+        if !first {
+            self.put(",")?;
+        }
+        if key.is_none() {
+            return Ok(false);
+        }
+        let object = matches!(self.open.last(), Some(Frame::Object { .. }));
+        match event {
+            Event::SetWord(name) => self.string(name)?,
+            _ if object => return Err(DeError::Message(message!("expected a set-word! key of an object")).into()),
+            Event::Word(name) | Event::LitWord(name) | Event::GetWord(name)
+            | Event::Refinement(name) | Event::Issue(name) => self.string(name)?,
+            Event::Integer(i) => self.string(&i.to_string())?,
+            Event::Float(f) => self.string(&f.to_string())?,
+            Event::Char(c) => self.string(&c.to_string())?,
+            Event::StringStart(..) | Event::BinaryStart(_) => {
+                self.put("\"")?;
+                self.series = true;
+                self.key = true;
+                return Ok(true);
+            }
+            _ => return Err(DeError::Message(message!("map! key can't be written as a JSON string")).into()),
+        }
+        self.put(":")?;
+        Ok(true)
+    }
+
+    fn value(&mut self, event: Event) -> Result<()> {
+        match event {
+            Event::None => self.put("null"),
+            Event::Logic(b) => self.put(if b {"true"} else {"false"}),
+            Event::Integer(i) => self.put(&i.to_string()),
+            Event::Float(f) if f.is_finite() => self.put(&f.to_string()),
+            Event::Float(_) => self.put("null"),
+            Event::Char(c) => self.string(&c.to_string()),
+            Event::Word(name) => self.string(&name),
+            Event::SetWord(name) => self.string(&format!("{}:", name)),
+            Event::LitWord(name) => self.string(&format!("'{}", name)),
+            Event::GetWord(name) => self.string(&format!(":{}", name)),
+            Event::Refinement(name) => self.string(&format!("/{}", name)),
+            Event::Issue(name) => self.string(&format!("#{}", name)),
+            Event::StringStart(..) | Event::BinaryStart(_) => {
+                self.series = true;
+                self.put("\"")
+            }
+            Event::BlockStart(List::Map, _) => {
+                self.open.push(Frame::Map { first: true, key: true });
+                self.put("{")
+            }
+            Event::BlockStart(List::Block, len) if len % 2 == 0 && self.is_object()? => {
+                self.open.push(Frame::Object { first: true, key: true });
+                self.put("{")
+            }
+            Event::BlockStart(..) => {
+                self.open.push(Frame::Array { first: true });
+                self.put("[")
+            }
+            Event::Record(bytes) => self.string(&hex(&bytes)),
+            Event::StringChunk(_) | Event::BinaryChunk(_) | Event::StringEnd | Event::BinaryEnd | Event::BlockEnd => {
+                unreachable!("not the start of a value")
+            }
+        }
+    }
+
+    /// Whether every other element of a block just started, from the first,
+    /// is a `set-word!`. Reads ahead up to the first element that isn't, or
+    /// to the end of the block.
+    fn is_object(&mut self) -> Result<bool> {
+        let mut read = Vec::new();
+        let (mut depth, mut index) = (0, 0);
+        let object = loop {
+            let event = match self.next().transpose()? {
+                Some(event) => event,
+                None => break false,
+            };
+            let starts = depth == 0 && !matches!(event, Event::BlockEnd);
+            if starts && index % 2 == 0 && !matches!(event, Event::SetWord(_)) {
+                read.push(event);
+                break false;
+            }
+            match event {
+                Event::BlockStart(..) | Event::StringStart(..) | Event::BinaryStart(_) => depth += 1,
+                Event::BlockEnd if depth == 0 => {
+                    read.push(event);
+                    break index > 0;
+                }
+                Event::BlockEnd | Event::StringEnd | Event::BinaryEnd => depth -= 1,
+                _ => {}
+            }
+            if starts {
+                index += 1;
+            }
+            read.push(event);
+        };
+        for event in read.into_iter().rev() {
+            self.pending.push_front(event);
+        }
+        Ok(object)
+    }
+
+    fn string(&mut self, text: &str) -> Result<()> {
+        self.put("\"")?;
+        escape(&mut self.writer, text)?;
+        self.put("\"")
+    }
+
+    fn put(&mut self, text: &str) -> Result<()> {
+        self.writer.write_all(text.as_bytes()).map_err(|e| Error::Io(e.to_string()))
+    }
+}
+
+fn escape<W: Write>(writer: &mut W, text: &str) -> Result<()> {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    writer.write_all(escaped.as_bytes()).map_err(|e| Error::Io(e.to_string()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+#[cfg(all(test, feature = "ser"))]
+mod tests {
+    use super::to_json_lines;
+    use crate::{parse_red, to_bytes, DeError, Error, SerializerSession};
+
+    #[test]
+    fn test_json_lines() {
+        let mut input = to_bytes(&parse_red(r#"[level: 'info msg: "say ^"hi^"^/" tags: [a #b] n: 2.5]"#).unwrap()).unwrap();
+        let mut session = SerializerSession::new();
+        session.write(&parse_red("#(k: #[none] 2 #[true])").unwrap()).unwrap();
+        session.write(&vec![1, 2]).unwrap();
+        session.write("x").unwrap();
+        input.extend(session.finish().unwrap());
+        let mut output = Vec::new();
+        assert_eq!(Ok(4), to_json_lines(&input[..], &mut output));
+        assert_eq!(concat!(
+            r##"{"level":"'info","msg":"say \"hi\"\n","tags":["a","#b"],"n":2.5}"##, "\n",
+            r#"{"k":null,"2":true}"#, "\n",
+            "[1,2]\n",
+            "\"x\"\n",
+        ), String::from_utf8(output).unwrap());
+
+        // keys that aren't all set-words make an array
+        let blocks = to_bytes(&parse_red(r#"[[a: 1 2 3] [a: [b: "c" d] e: 4] [a: 1 b:]]"#).unwrap()).unwrap();
+        let mut output = Vec::new();
+        assert_eq!(Ok(1), to_json_lines(&blocks[..], &mut output));
+        assert_eq!(r#"[["a:",1,2,3],{"a":["b:","c","d"],"e":4},["a:",1,"b:"]]"#.to_string() + "\n",
+            String::from_utf8(output).unwrap());
+
+        let mut output = Vec::new();
+        assert_eq!(Err(Error::De(DeError::Eof)), to_json_lines(&input[..input.len() - 4], &mut output));
+    }
+}
@@ -1,38 +1,84 @@
+//! The `ser` and `de` features, both on by default, enable the serializer
+//! and the deserializer. Types in `adapters` work with either. UCS-2 and
+//! UCS-4 strings are transcoded by `encoding`, so even the smallest build,
+//! `default-features = false, features = ["de"]`, reads them without a
+//! system transcoder. Where all text is ASCII, `ascii-only` leaves the
+//! decoders out, failing on wide strings instead.
+//!
+//! The `forbid-unsafe` feature builds the crate under
+//! `#![forbid(unsafe_code)]`, unless `memmap2` is enabled too, whose file
+//! mapping is unsafe. The C functions are in the `redbin-capi` crate of this
+//! workspace.
+//!
+//! For devices without a heap, `static-errors` keeps the messages of errors
+//! as their `&'static str` templates, and `defmt` implements
+//! `defmt::Format` for them on embedded targets.
+
+#![cfg_attr(all(feature = "forbid-unsafe", not(feature = "memmap2")), forbid(unsafe_code))]
+
+#[macro_use]
+mod macros;
+pub mod adapters;
+pub mod checksum;
+pub mod codegen;
+#[cfg(all(feature = "zstd", any(feature = "ser", feature = "de")))]
+mod compress;
+#[cfg(feature = "de")]
 mod de;
+pub mod encoding;
 mod error;
+#[cfg(any(feature = "ser", feature = "de"))]
+mod ext;
+#[cfg(feature = "de")]
+pub mod events;
+#[cfg(any(feature = "ser", feature = "de"))]
+mod file;
+#[cfg(feature = "de")]
+pub mod jsonl;
+#[cfg(all(feature = "memmap2", feature = "de"))]
+mod mmap;
+mod parse;
+#[cfg(feature = "de")]
+mod reader;
+pub mod schema;
+#[cfg(feature = "ser")]
 mod ser;
+pub mod spec;
+pub mod stream;
+#[cfg(all(feature = "ser", feature = "de"))]
+pub mod testing;
+mod types;
+mod value;
+#[cfg(all(feature = "ser", feature = "de"))]
+pub mod versioned;
 
-pub use crate::de::{from_bytes, Deserializer};
-pub use crate::ser::{to_bytes, Serializer};
-
-mod iconv_tools {
-    use iconv::{Iconv, IconvError};
-    use dyn_buf::VecBuf;
-
-    const MIN_WRITE: usize = 4096;
-
-    /// convert `input` from `from_encoding` to `to_encoding`
-    pub fn iconv(c: &mut Iconv, input: &[u8]) -> std::result::Result<Vec<u8>, IconvError> {
-    	let mut read = 0;
-    	let mut output = VecBuf::new(MIN_WRITE);
-    	loop {
-    		match c.convert(&input[read..], output.prepare_at_least(0)) {
-    			Ok((r, w, _)) => {
-    				output.commit(w);
-    				if read >= input.len() {
-    					return Ok(output.into_vec());
-    				}
-    				read += r;
-    			}
-    			Err((r, w, IconvError::NotSufficientOutput)) => {
-    				output.commit(w);
-    				read += r;
-    				output.grow(0);
-    			}
-    			Err((_, _, e)) => return Err(e),
-    		}
-    	}
-    }
-    
-
-}
+#[cfg(feature = "de")]
+pub use crate::de::{from_bytes, from_bytes_seed, from_bytes_with, from_bytes_with_warnings, from_slice_owned, Deserializer, DeserializerOptions, DuplicateKeys, Requested, Warning};
+pub use crate::error::{DeError, EncodingError, Error, Result, SerError};
+#[cfg(any(feature = "ser", feature = "de"))]
+pub use crate::ext::RedbinExt;
+pub use crate::parse::parse_red;
+#[cfg(feature = "de")]
+pub use crate::reader::{from_bytes_with_spans, from_reader, from_reader_with, spans, DocumentReader, Header, Records, Span};
+#[cfg(feature = "de")]
+pub use crate::file::{from_file, from_file_with};
+#[cfg(feature = "ser")]
+pub use crate::file::{to_file, to_file_with};
+#[cfg(feature = "ser")]
+pub use crate::ser::{to_bytes, to_bytes_with, to_bytes_with_metrics, to_slice, to_slice_fixed, Compound, Output, Overflow, Serializer, SerializerMetrics, SerializerOptions, SerializerSession, SliceOutput, StringEncoding, Unencodable};
+pub use crate::value::{Path, PathElement, Value};
+pub use crate::types::VariantCase;
+#[cfg(all(feature = "bumpalo", feature = "ser"))]
+pub use crate::ser::to_bytes_in;
+#[cfg(all(feature = "bytes", feature = "de"))]
+pub use crate::de::from_shared_bytes;
+#[cfg(all(feature = "bytes", feature = "ser"))]
+pub use crate::ser::to_bytes_mut;
+#[cfg(all(feature = "heapless", feature = "ser"))]
+pub use crate::ser::to_heapless;
+#[cfg(all(feature = "zstd", feature = "ser"))]
+pub use crate::compress::{to_bytes_zstd, to_bytes_zstd_level};
+#[cfg(all(feature = "zstd", feature = "de"))]
+pub use crate::compress::from_bytes_zstd;
+#[cfg(all(feature = "memmap2", feature = "de"))]
+pub use crate::mmap::{from_path_mmap, MappedFile};
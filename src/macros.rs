@@ -0,0 +1,86 @@
+/// Builds a `Value` from Red-like syntax, e.g. for tests:
+///
+/// ```
+/// # use redbin::{redbin, Value};
+/// let value = redbin!([1 -2.5 "x" none true (set-word "a") (paren 1 (word "b"))]);
+/// assert_eq!(redbin::parse_red(r#"[1 -2.5 "x" #[none] #[true] a: (1 b)]"#).unwrap(), value);
+/// ```
+///
+/// Literals and other Rust expressions become values through `From`, so
+/// `"x"` is a `string!` and `1` an `integer!`. Words, series other than
+/// strings and parens or paths are written as `(kind content)`, with the
+/// kind named as in Red: `(word "foo")`, `(file "a.txt")`, `(binary [1, 2])`,
+/// `(get-path (word "a") 1)`. Negative numbers need no parentheses, other
+/// expressions do, e.g. `[(x + 1)]`.
+#[macro_export]
+macro_rules! redbin {
+    // elements of a block, paren or path, gathered one at a time
+    (@values [$($done:expr,)*]) => {
+        vec![$($done),*]
+    };
+    (@values [$($done:expr,)*] - $number:literal $($rest:tt)*) => {
+        $crate::redbin!(@values [$($done,)* $crate::Value::from(-$number),] $($rest)*)
+    };
+    (@values [$($done:expr,)*] $value:tt $($rest:tt)*) => {
+        $crate::redbin!(@values [$($done,)* $crate::redbin!($value),] $($rest)*)
+    };
+
+    (none) => { $crate::Value::None };
+    ([$($values:tt)*]) => { $crate::Value::Block($crate::redbin!(@values [] $($values)*)) };
+    ((paren $($values:tt)*)) => { $crate::Value::Paren($crate::redbin!(@values [] $($values)*)) };
+    ((path $($values:tt)*)) => { $crate::Value::Path($crate::redbin!(@values [] $($values)*)) };
+    ((set-path $($values:tt)*)) => { $crate::Value::SetPath($crate::redbin!(@values [] $($values)*)) };
+    ((get-path $($values:tt)*)) => { $crate::Value::GetPath($crate::redbin!(@values [] $($values)*)) };
+    ((lit-path $($values:tt)*)) => { $crate::Value::LitPath($crate::redbin!(@values [] $($values)*)) };
+    ((word $text:expr)) => { $crate::Value::Word($text.to_string()) };
+    ((set-word $text:expr)) => { $crate::Value::SetWord($text.to_string()) };
+    ((lit-word $text:expr)) => { $crate::Value::LitWord($text.to_string()) };
+    ((get-word $text:expr)) => { $crate::Value::GetWord($text.to_string()) };
+    ((refinement $text:expr)) => { $crate::Value::Refinement($text.to_string()) };
+    ((issue $text:expr)) => { $crate::Value::Issue($text.to_string()) };
+    ((file $text:expr)) => { $crate::Value::File($text.to_string()) };
+    ((url $text:expr)) => { $crate::Value::Url($text.to_string()) };
+    ((tag $text:expr)) => { $crate::Value::Tag($text.to_string()) };
+    ((email $text:expr)) => { $crate::Value::Email($text.to_string()) };
+    ((binary $bytes:expr)) => { $crate::Value::Binary(::std::convert::From::from($bytes)) };
+    (- $number:literal) => { $crate::Value::from(-$number) };
+    ($value:expr) => { $crate::Value::from($value) };
+}
+
+// The text of `SerError::Message` or `DeError::Message`: the message
+// formatted, or with `static-errors`, its template as is, e.g.
+// `"Unexpected unit size {}."`, without allocating. Messages without
+// arguments are never allocated.
+macro_rules! message {
+    ($template:literal) => { ::std::borrow::Cow::Borrowed($template) };
+    ($template:literal, $($arg:expr),+ $(,)?) => {{
+        #[cfg(not(feature = "static-errors"))]
+        let text = ::std::borrow::Cow::Owned(format!($template, $($arg),+));
+        #[cfg(feature = "static-errors")]
+        let text = {
+            $(let _ = &$arg;)+
+            ::std::borrow::Cow::Borrowed($template)
+        };
+        text
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Value;
+
+    #[test]
+    fn test_redbin_macro() {
+        let x = 4;
+        let value = redbin!([1 -2 2.5 "x" 'c' none false [] [[3]] (x + 1) (word "foo") (lit-word "b")
+            (refinement "r") (issue "i") (url "http://a") (tag "t") (email "a@b") (file "f")
+            (binary [1, 2]) (path (word "a") 1) (set-path (word "a") (word "b")) (lit-path (word "a") (word "b"))]);
+        let expected = crate::parse_red(concat!(r#"[1 -2 2.5 "x" #"c" #[none] #[false] [] [[3]] 5 foo 'b"#,
+            r#" /r #i http://a <t> a@b %f #{0102} a/1 a/b: 'a/b]"#)).unwrap();
+        assert_eq!(expected, value);
+        assert_eq!(Value::GetPath(vec![Value::Word("a".to_string())]), redbin!((get-path (word "a"))));
+        assert_eq!(Value::Integer(-1), redbin!(-1));
+        assert_eq!(Value::Paren(vec![]), redbin!((paren)));
+        assert_eq!(Value::None, redbin!(None::<i32>));
+    }
+}
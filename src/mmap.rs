@@ -0,0 +1,94 @@
+//! Reading Redbin files through a memory map, so that big datasets saved by
+//! Red are paged in as they are read instead of loaded up front.
+
+use crate::de::{from_bytes_with, DeserializerOptions};
+use crate::error::{DeError, Error, Result};
+use memmap2::Mmap;
+use serde::de::{Deserialize, DeserializeOwned};
+use std::fs::File;
+use std::path::Path;
+
+/// A memory-mapped Redbin file, values borrowing from it live as long as it
+/// does.
+pub struct MappedFile {
+    mmap: Mmap,
+}
+
+impl MappedFile {
+    /// Maps the file at `path`.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this process or any
+    /// other, while it is mapped: the bytes would change under the values
+    /// borrowing them, and reading past a truncated end is undefined
+    /// behavior, as with `memmap2::Mmap::map`.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let io = |e: std::io::Error| Error::File {
+            path: path.to_path_buf(),
+            error: Box::new(Error::Io(e.to_string())),
+        };
+        let file = File::open(path).map_err(io)?;
+        // Safety: upheld by the caller.
+        let mmap = unsafe { Mmap::map(&file) }.map_err(io)?;
+        Ok(MappedFile { mmap })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    pub fn deserialize<'de, T>(&'de self) -> std::result::Result<T, DeError>
+    where
+        T: Deserialize<'de>,
+    {
+        self.deserialize_with(DeserializerOptions::default())
+    }
+
+    pub fn deserialize_with<'de, T>(&'de self, options: DeserializerOptions) -> std::result::Result<T, DeError>
+    where
+        T: Deserialize<'de>,
+    {
+        from_bytes_with(&self.mmap, options)
+    }
+}
+
+/// Reads a value from a memory-mapped Redbin file. Use `MappedFile` for types
+/// borrowing from the file.
+///
+/// # Safety
+///
+/// The file must not be modified or truncated while it's read, see
+/// `MappedFile::open`.
+pub unsafe fn from_path_mmap<T, P>(path: P) -> Result<T>
+where
+    T: DeserializeOwned,
+    P: AsRef<Path>,
+{
+    Ok(MappedFile::open(path)?.deserialize()?)
+}
+
+#[cfg(all(test, feature = "ser"))]
+mod tests {
+    use super::{from_path_mmap, MappedFile};
+    use crate::to_bytes;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_mmap() {
+        let map = BTreeMap::from([("a", "x"), ("b", "y")]);
+        let path = std::env::temp_dir().join(format!("redbin-mmap-{}.redbin", std::process::id()));
+        std::fs::write(&path, to_bytes(&map).unwrap()).unwrap();
+
+        // Safety: the file is only removed after it's unmapped.
+        let file = unsafe { MappedFile::open(&path) }.unwrap();
+        assert_eq!(map, file.deserialize::<BTreeMap<&str, &str>>().unwrap());
+        let owned: BTreeMap<String, String> = unsafe { from_path_mmap(&path) }.unwrap();
+        assert_eq!(Some("x"), owned.get("a").map(String::as_str));
+
+        drop(file);
+        std::fs::remove_file(&path).unwrap();
+        assert!(unsafe { from_path_mmap::<BTreeMap<String, String>, _>(&path) }.is_err());
+    }
+}
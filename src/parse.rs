@@ -0,0 +1,492 @@
+//! Reading Red source text into `Value`s, like Red's `load` does.
+
+use crate::error::{Error, Result};
+use crate::value::Value;
+
+/// Builds a `Value` from Red source text, e.g. `[1 2.5 "x"]`.
+///
+/// Like Red's `load`, a single value is returned as is, more values (or none)
+/// as a block of them. Words, paths, strings, files, urls, emails, tags,
+/// issues, chars, binaries, maps, integers and floats are understood. As in Red,
+/// `none`, `true` and `false` are words; `#[none]`, `#[true]` and `#[false]`
+/// are the values.
+pub fn parse_red(source: &str) -> Result<Value> {
+    let mut parser = Parser { source, at: 0 };
+    let mut values = parser.values(None)?;
+    if values.len() == 1 {
+        Ok(values.remove(0))
+    } else {
+        Ok(Value::Block(values))
+    }
+}
+
+/// The word a one-element path is made of.
+fn first_word(path: &mut Vec<Value>) -> String {
+    match path.pop() {
+        Some(Value::Word(name)) => name,
+        _ => unreachable!("paths start with a word"),
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    /// byte offset of the next character
+    at: usize,
+}
+
+/// Characters ending a word, number or other token.
+pub(crate) fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '[' | ']' | '(' | ')' | '"' | '{' | '}' | ';')
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, message: impl Into<String>) -> Result<T> {
+        let before = &self.source[..self.at];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+        Err(Error::Parse { line, column, message: message.into() })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.at..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.source[self.at..].chars().nth(1)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.at += c.len_utf8();
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => self.error(format!("expected {:?}, found {:?}", expected, c)),
+            None => self.error(format!("expected {:?}, found end of text", expected)),
+        }
+    }
+
+    fn skip_space(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == ';' {
+                while !matches!(self.next(), Some('\n') | None) {}
+            } else if c.is_whitespace() {
+                self.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Text up to the next delimiter or one of `stops`.
+    fn token(&mut self, stops: &[char]) -> &'a str {
+        let start = self.at;
+        while let Some(c) = self.peek() {
+            if is_delimiter(c) || stops.contains(&c) {
+                break;
+            }
+            self.next();
+        }
+        &self.source[start..self.at]
+    }
+
+    /// Values up to `end`, or to the end of text.
+    fn values(&mut self, end: Option<char>) -> Result<Vec<Value>> {
+        let mut values = Vec::new();
+        loop {
+            self.skip_space();
+            match self.peek() {
+                None if end.is_none() => return Ok(values),
+                None => return self.error(format!("missing {:?}", end.unwrap_or(']'))),
+                Some(c) if Some(c) == end => {
+                    self.next();
+                    return Ok(values);
+                }
+                Some(c @ (']' | ')' | '}')) => return self.error(format!("unexpected {:?}", c)),
+                Some(_) => values.push(self.value()?),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        let c = self.peek().expect("called before the end of text");
+        match c {
+            '[' => {
+                self.next();
+                Ok(Value::Block(self.values(Some(']'))?))
+            }
+            '(' => {
+                self.next();
+                Ok(Value::Paren(self.values(Some(')'))?))
+            }
+            '"' => Ok(Value::String(self.quoted_string()?)),
+            '{' => Ok(Value::String(self.braced_string()?)),
+            '#' => self.hash(),
+            '%' => {
+                self.next();
+                let name = if self.peek() == Some('"') {self.quoted_string()?} else {self.percent_decoded()?};
+                Ok(Value::File(name))
+            }
+            '<' if self.peek_second().is_some_and(|c| !c.is_whitespace() && !matches!(c, '=' | '>' | '<')) => {
+                self.next();
+                let start = self.at;
+                while !matches!(self.next(), Some('>') | None) {}
+                if !self.source[..self.at].ends_with('>') {
+                    return self.error("missing '>' closing a tag");
+                }
+                Ok(Value::Tag(self.source[start..self.at - 1].to_string()))
+            }
+            '\'' => {
+                self.next();
+                let word = self.word()?;
+                let mut path = self.path(word)?;
+                Ok(if path.len() == 1 {Value::LitWord(first_word(&mut path))} else {Value::LitPath(path)})
+            }
+            ':' => {
+                self.next();
+                let word = self.word()?;
+                let mut path = self.path(word)?;
+                Ok(if path.len() == 1 {Value::GetWord(first_word(&mut path))} else {Value::GetPath(path)})
+            }
+            '/' => {
+                self.next();
+                match self.token(&['/']) {
+                    "" => {
+                        // `/` and `//` are words
+                        let name = if self.peek() == Some('/') {self.next(); "//"} else {"/"};
+                        Ok(Value::Word(name.to_string()))
+                    }
+                    name => Ok(Value::Refinement(name.to_string())),
+                }
+            }
+            _ => self.word_like(),
+        }
+    }
+
+    /// Word, set-word, path, url, email or number.
+    fn word_like(&mut self) -> Result<Value> {
+        let start = self.at;
+        let token = self.token(&['/']);
+        if token.ends_with(':') && self.source[self.at..].starts_with("//") {
+            // e.g. `http://red-lang.org`
+            self.token(&[]);
+            return Ok(Value::Url(self.source[start..self.at].to_string()));
+        }
+        if token.contains('@') {
+            self.token(&[]);
+            return Ok(Value::Email(self.source[start..self.at].to_string()));
+        }
+        let first = token.chars().next().unwrap_or(' ');
+        let signed_digit = matches!(first, '+' | '-' | '.') && token[1..].starts_with(|c: char| c.is_ascii_digit());
+        if first.is_ascii_digit() || signed_digit {
+            return self.number(token);
+        }
+        if let Some(name) = token.strip_suffix(':') {
+            if !name.is_empty() && !name.contains(':') {
+                return Ok(Value::SetWord(name.to_string()));
+            }
+        }
+        if token.contains(':') {
+            // e.g. `mailto:someone`
+            self.token(&[]);
+            return Ok(Value::Url(self.source[start..self.at].to_string()));
+        }
+        if token.is_empty() {
+            return self.error("expected a value");
+        }
+        let mut path = self.path(token.to_string())?;
+        if path.len() == 1 {
+            Ok(Value::Word(first_word(&mut path)))
+        } else if self.peek() == Some(':') {
+            self.next();
+            Ok(Value::SetPath(path))
+        } else {
+            Ok(Value::Path(path))
+        }
+    }
+
+    fn word(&mut self) -> Result<String> {
+        match self.token(&['/', ':']) {
+            "" => self.error("expected a word"),
+            name => Ok(name.to_string()),
+        }
+    }
+
+    /// Elements of a path starting with the `first` word, just that word if
+    /// no `/` follows it.
+    fn path(&mut self, first: String) -> Result<Vec<Value>> {
+        let mut elements = vec![Value::Word(first)];
+        while self.peek() == Some('/') {
+            self.next();
+            let element = match self.peek() {
+                Some('(') => self.value()?,
+                Some(':') => {
+                    self.next();
+                    Value::GetWord(self.word()?)
+                }
+                _ => {
+                    let token = self.token(&['/', ':']);
+                    match token.parse::<i32>() {
+                        Ok(i) => Value::Integer(i),
+                        Err(_) if token.is_empty() => return self.error("expected a path element"),
+                        Err(_) => Value::Word(token.to_string()),
+                    }
+                }
+            };
+            elements.push(element);
+        }
+        Ok(elements)
+    }
+
+    fn number(&mut self, token: &str) -> Result<Value> {
+        let digits = token.replace('\'', "");
+        if digits.contains(['.', 'e', 'E']) {
+            if let Ok(f) = digits.parse::<f64>() {
+                return Ok(Value::Float(f));
+            }
+        } else if let Ok(i) = digits.parse::<i32>() {
+            return Ok(Value::Integer(i));
+        } else if let Ok(f) = digits.parse::<f64>() {
+            // too big for an `integer!`, as Red loads it
+            return Ok(Value::Float(f));
+        }
+        self.error(format!("unsupported number {:?}", token))
+    }
+
+    /// Character after a `^` in strings and chars.
+    fn escape(&mut self) -> Result<char> {
+        let c = match self.next() {
+            Some('/') => '\n',
+            Some('-') => '\t',
+            Some('@') => '\0',
+            Some('(') => {
+                let start = self.at;
+                while !matches!(self.next(), Some(')') | None) {}
+                let name = &self.source[start..self.at - 1];
+                let c = match name.to_ascii_lowercase().as_str() {
+                    "null" => Some('\0'),
+                    "line" => Some('\n'),
+                    "tab" => Some('\t'),
+                    "page" => Some('\x0C'),
+                    "esc" => Some('\x1B'),
+                    "back" => Some('\x08'),
+                    "del" => Some('\x7F'),
+                    _ => u32::from_str_radix(name, 16).ok().and_then(char::from_u32),
+                };
+                match c {
+                    Some(c) => c,
+                    None => return self.error(format!("invalid escape ^({})", name)),
+                }
+            }
+            Some(c @ 'A'..='Z') => char::from(c as u8 - b'@'), // control characters, e.g. `^M`
+            Some(c) => c,
+            None => return self.error("unfinished escape"),
+        };
+        Ok(c)
+    }
+
+    fn quoted_string(&mut self) -> Result<String> {
+        let start = self.at;
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('^') => string.push(self.escape()?),
+                Some('\n') | None => {
+                    self.at = start;
+                    return self.error("missing '\"' closing a string");
+                }
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn braced_string(&mut self) -> Result<String> {
+        let start = self.at;
+        self.expect('{')?;
+        let mut string = String::new();
+        let mut depth = 0;
+        loop {
+            match self.next() {
+                Some('}') if depth == 0 => return Ok(string),
+                Some('^') => string.push(self.escape()?),
+                Some(c) => {
+                    match c {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                    string.push(c);
+                }
+                None => {
+                    self.at = start;
+                    return self.error("missing '}' closing a string");
+                }
+            }
+        }
+    }
+
+    /// Token with `%XX` escapes decoded, as in files.
+    fn percent_decoded(&mut self) -> Result<String> {
+        let token = self.token(&[]);
+        let mut bytes = Vec::with_capacity(token.len());
+        let mut rest = token.as_bytes();
+        while let Some((&b, tail)) = rest.split_first() {
+            let hex = tail.get(..2).and_then(|h| std::str::from_utf8(h).ok());
+            match (b, hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+                (b'%', Some(decoded)) => {
+                    bytes.push(decoded);
+                    rest = &tail[2..];
+                }
+                _ => {
+                    bytes.push(b);
+                    rest = tail;
+                }
+            }
+        }
+        match String::from_utf8(bytes) {
+            Ok(name) => Ok(name),
+            Err(_) => self.error("invalid UTF-8 in file name"),
+        }
+    }
+
+    /// Char, binary, construction syntax or issue.
+    fn hash(&mut self) -> Result<Value> {
+        self.expect('#')?;
+        match self.peek() {
+            Some('"') => {
+                self.next();
+                let c = match self.next() {
+                    Some('^') => self.escape()?,
+                    Some(c) => c,
+                    None => return self.error("unfinished char"),
+                };
+                self.expect('"')?;
+                Ok(Value::Char(c))
+            }
+            Some('{') => {
+                self.next();
+                let start = self.at;
+                while !matches!(self.next(), Some('}') | None) {}
+                let hex: String = self.source[start..self.at - 1].chars().filter(|c| !c.is_whitespace()).collect();
+                if !self.source[..self.at].ends_with('}') || !hex.len().is_multiple_of(2) {
+                    return self.error("malformed binary");
+                }
+                let bytes = (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16)).collect();
+                match bytes {
+                    Ok(bytes) => Ok(Value::Binary(bytes)),
+                    Err(_) => self.error("malformed binary"),
+                }
+            }
+            Some('(') => {
+                self.next();
+                let values = self.values(Some(')'))?;
+                if values.len() % 2 != 0 {
+                    return self.error("map without a value for its last key");
+                }
+                let mut values = values.into_iter();
+                Ok(Value::Map(std::iter::from_fn(|| Some((values.next()?, values.next()?))).collect()))
+            }
+            Some('[') => {
+                self.next();
+                self.skip_space();
+                let value = match self.token(&[]) {
+                    "none" => Value::None,
+                    "true" => Value::Logic(true),
+                    "false" => Value::Logic(false),
+                    other => return self.error(format!("unsupported construction #[{}]", other)),
+                };
+                self.skip_space();
+                self.expect(']')?;
+                Ok(value)
+            }
+            _ => match self.token(&[]) {
+                "" => self.error("expected an issue"),
+                name => Ok(Value::Issue(name.to_string())),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_red;
+    use crate::error::Error;
+    use crate::value::Value::{
+        self, Binary, Block, Char, Email, File, Float, GetPath, Integer, Issue, LitPath, Logic, Paren, Path,
+        Refinement, SetPath, SetWord, Tag, Url, Word,
+    };
+
+    fn s(text: &str) -> String {
+        text.to_string()
+    }
+
+    fn w(name: &str) -> Value {
+        Word(s(name))
+    }
+
+    #[test]
+    fn test_parse_red() {
+        assert_eq!(Block(vec![Integer(1), Float(2.5), Value::String(s("x"))]), parse_red(r#"[1 2.5 "x"]"#).unwrap());
+        assert_eq!(Block(vec![Integer(1), Integer(2)]), parse_red("1 2").unwrap());
+        assert_eq!(Block(vec![]), parse_red(" ; nothing\n").unwrap());
+
+        let source = r#"
+            config: [
+                name: "caf^(E9)^/" ; comment
+                size: -1'000 ratio: 1.5e3 big: 3000000000
+                flags: [#[true] #[none] true] char: #"^-"
+                data: #{CA FE} id: #abc-1 doc: %"my file.txt" log: %logs/today%20.txt
+                home: https://red-lang.org mail: someone@example.com tag: <b class="x">
+                text: {multi {nested}
+line}
+                code: (print obj/field/1 :obj/(n + 1) 'obj/x obj/y: /only x < y)
+            ]
+        "#;
+        let expected = Block(vec![
+            SetWord(s("config")),
+            Block(vec![
+                SetWord(s("name")), Value::String(s("café\n")),
+                SetWord(s("size")), Integer(-1000), SetWord(s("ratio")), Float(1500.0),
+                SetWord(s("big")), Float(3e9),
+                SetWord(s("flags")), Block(vec![Logic(true), Value::None, w("true")]),
+                SetWord(s("char")), Char('\t'),
+                SetWord(s("data")), Binary(vec![0xCA, 0xFE]), SetWord(s("id")), Issue(s("abc-1")),
+                SetWord(s("doc")), File(s("my file.txt")), SetWord(s("log")), File(s("logs/today .txt")),
+                SetWord(s("home")), Url(s("https://red-lang.org")),
+                SetWord(s("mail")), Email(s("someone@example.com")),
+                SetWord(s("tag")), Tag(s(r#"b class="x""#)),
+                SetWord(s("text")), Value::String(s("multi {nested}\nline")),
+                SetWord(s("code")), Paren(vec![
+                    w("print"),
+                    Path(vec![w("obj"), w("field"), Integer(1)]),
+                    GetPath(vec![w("obj"), Paren(vec![w("n"), w("+"), Integer(1)])]),
+                    LitPath(vec![w("obj"), w("x")]),
+                    SetPath(vec![w("obj"), w("y")]),
+                    Refinement(s("only")),
+                    w("x"), w("<"), w("y"),
+                ]),
+            ]),
+        ]);
+        assert_eq!(expected, parse_red(source).unwrap());
+
+        match parse_red("[1\n  \"x]") {
+            Err(Error::Parse { line: 2, column: 3, .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(parse_red("[1 2").is_err());
+        assert!(parse_red("1x2").is_err());
+    }
+
+    #[test]
+    fn test_mold() {
+        let value = parse_red(r#"[a: 1 -2.5 1e300 "x^/^"y^"" %"my file" %f.txt http://red-lang.org a@b.c <b>
+            :b 'c /d #e #"^^" #"^"" #{CAFE} (p/1/(q) x/y: :x/y 'x/y) #[none] #[true] [] "^(01)"]"#).unwrap();
+        assert_eq!(value, parse_red(&value.to_string()).unwrap());
+        assert_eq!("[1.0 \"a^-b\" #[false] x/:y]", parse_red("[1.0 {a	b} #[false] x/:y]").unwrap().to_string());
+    }
+}
@@ -0,0 +1,372 @@
+//! Reading Redbin documents written back to back, like entries of a log,
+//! and documents of many root records, as Red saves a block of values.
+
+use crate::de::{from_bytes_with, nested_values, record_len, Deserializer, DeserializerOptions};
+use crate::error::{text_of, DeError};
+use crate::types::HEADER_LEN;
+use crate::value::Value;
+use serde::de::{Deserialize, DeserializeOwned};
+use std::io::{ErrorKind, Read};
+use std::marker::PhantomData;
+
+type Result<T> = std::result::Result<T, DeError>;
+
+const SYMBOL_TABLE_FLAG: u8 = 0x04;
+
+/// The fixed part at the start of every Redbin document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub version: u8,
+    pub flags: u8,
+    /// number of top-level records
+    pub length: u32,
+    /// size of the records in bytes, without the header and symbol table
+    pub size: u32,
+}
+
+impl Header {
+    /// Reads the header at the start of `bytes`.
+    pub fn parse(bytes: &[u8]) -> Result<Header> {
+        if bytes.len() < HEADER_LEN {
+            return Err(DeError::Eof);
+        }
+        if &bytes[..6] != b"REDBIN" {
+            return Err(DeError::Syntax);
+        }
+        let word = |at: usize| u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+        Ok(Header { version: bytes[6], flags: bytes[7], length: word(8), size: word(12) })
+    }
+
+    pub fn has_symbol_table(&self) -> bool {
+        self.flags & SYMBOL_TABLE_FLAG != 0
+    }
+}
+
+/// Iterates over documents read one after the other from `R`, finding where
+/// each ends from the sizes in its header:
+///
+/// ```no_run
+/// # use redbin::DocumentReader;
+/// # use serde_derive::Deserialize;
+/// # use std::fs::File;
+/// # use std::io::BufReader;
+/// # #[derive(Deserialize)]
+/// # struct Entry { message: String }
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// for entry in DocumentReader::<_, Entry>::new(BufReader::new(File::open("app.log")?)) {
+///     let (header, entry) = entry?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// A document that doesn't decode as `T` is an error of its own, and reading
+/// goes on with the next one. Failing to read the stream, or a stream ending
+/// inside a document, ends the iteration after its error.
+pub struct DocumentReader<R, T = Value> {
+    reader: R,
+    options: DeserializerOptions,
+    done: bool,
+    document: Vec<u8>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<R: Read, T: DeserializeOwned> DocumentReader<R, T> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, DeserializerOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: DeserializerOptions) -> Self {
+        DocumentReader { reader, options, done: false, document: Vec::new(), marker: PhantomData }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the bytes of the next document into `self.document`, or nothing
+    /// at the end of the stream.
+    fn read_document(&mut self) -> Result<Option<Header>> {
+        self.document.clear();
+        self.document.resize(HEADER_LEN, 0x00);
+        let mut filled = 0;
+        while filled < HEADER_LEN {
+            match self.reader.read(&mut self.document[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(DeError::Eof),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(DeError::Io(text_of(e, "reading failed"))),
+            }
+        }
+        let header = Header::parse(&self.document)?;
+        if header.has_symbol_table() {
+            self.read_more(8)?;
+            let counts = &self.document[HEADER_LEN..];
+            let count = u32::from_le_bytes([counts[0], counts[1], counts[2], counts[3]]) as usize;
+            let names = u32::from_le_bytes([counts[4], counts[5], counts[6], counts[7]]) as usize;
+            self.read_more(count.checked_mul(4).and_then(|n| n.checked_add(names)).ok_or(DeError::Syntax)?)?;
+        }
+        self.read_more(header.size as usize)?;
+        Ok(Some(header))
+    }
+
+    fn read_more(&mut self, len: usize) -> Result<()> {
+        let start = self.document.len();
+        let read = (&mut self.reader).take(len as u64).read_to_end(&mut self.document)
+            .map_err(|e| DeError::Io(text_of(e, "reading failed")))?;
+        if read < len {
+            self.document.truncate(start);
+            return Err(DeError::Eof);
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read, T: DeserializeOwned> Iterator for DocumentReader<R, T> {
+    type Item = Result<(Header, T)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_document() {
+            Ok(Some(header)) => Some(from_bytes_with(&self.document, self.options.clone()).map(|value| (header, value))),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(error) => {
+                self.done = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// Reads the next document from `reader`, and nothing past it, into a
+/// value owning all its data. The document's bytes are dropped before this
+/// returns.
+pub fn from_reader<R: Read, T: DeserializeOwned>(reader: R) -> Result<T> {
+    from_reader_with(reader, DeserializerOptions::default())
+}
+
+pub fn from_reader_with<R: Read, T: DeserializeOwned>(reader: R, options: DeserializerOptions) -> Result<T> {
+    match DocumentReader::with_options(reader, options).next() {
+        Some(document) => document.map(|(_, value)| value),
+        None => Err(DeError::Eof),
+    }
+}
+
+/// Iterates over the root records of one document, as many as its header
+/// declares, decoding each on its own.
+///
+/// Normally the first record failing to decode ends the iteration. In
+/// recovery mode, its error is reported and reading goes on with the next
+/// record, found from the sizes in record headers. Only a record too broken
+/// to tell its size ends the iteration then.
+pub struct Records<'de, T = Value> {
+    deserializer: Deserializer<'de>,
+    left: u32,
+    recover: bool,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<'de, T: Deserialize<'de>> Records<'de, T> {
+    pub fn new(bytes: &'de [u8]) -> Result<Self> {
+        Self::with_options(bytes, DeserializerOptions::default())
+    }
+
+    pub fn with_options(bytes: &'de [u8], options: DeserializerOptions) -> Result<Self> {
+        let header = Header::parse(bytes)?;
+        let mut deserializer = Deserializer::with_options(bytes, options);
+        deserializer.read_header()?;
+        Ok(Records { deserializer, left: header.length, recover: false, marker: PhantomData })
+    }
+
+    /// Goes on past records that fail to decode.
+    pub fn recover(mut self, recover: bool) -> Self {
+        self.recover = recover;
+        self
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Iterator for Records<'de, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.left == 0 {
+            return None;
+        }
+        self.left -= 1;
+        let rest = self.deserializer.remaining();
+        let result = T::deserialize(&mut self.deserializer);
+        if result.is_err() {
+            match record_len(rest) {
+                Ok(len) if self.recover => self.deserializer.resume_at(&rest[len..]),
+                _ => self.left = 0,
+            }
+        }
+        Some(result)
+    }
+}
+
+/// Where a record of a document is stored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    /// zero-based indexes leading to the record: of a root record first,
+    /// then of a value in each nested block or map, keys counting as values
+    pub path: Vec<usize>,
+    /// offset of the record's first byte, after any padding
+    pub start: usize,
+    /// offset just past the record, nested records included
+    pub end: usize,
+}
+
+/// Like `from_bytes_with`, also giving the span of every record in the
+/// document, root records and nested ones, in the order they're stored:
+///
+/// ```
+/// # use redbin::{from_bytes_with_spans, DeserializerOptions};
+/// # use serde_derive::{Deserialize, Serialize};
+/// # #[derive(Serialize, Deserialize)]
+/// # struct Config { name: String, ports: Vec<u16> }
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let bytes = redbin::to_bytes(&Config { name: "x".to_string(), ports: vec![80, 443] })?;
+/// let (config, spans) = from_bytes_with_spans::<Config>(&bytes, DeserializerOptions::new())?;
+/// for span in spans.iter().filter(|span| span.path.len() == 2) {
+///     println!("{:?} at {}..{}", span.path, span.start, span.end);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_bytes_with_spans<'de, T>(bytes: &'de [u8], options: DeserializerOptions) -> Result<(T, Vec<Span>)>
+where
+    T: Deserialize<'de>,
+{
+    let value = from_bytes_with(bytes, options)?;
+    Ok((value, spans(bytes)?))
+}
+
+/// Spans of the records of a whole document, see `from_bytes_with_spans`.
+pub fn spans(bytes: &[u8]) -> Result<Vec<Span>> {
+    let header = Header::parse(bytes)?;
+    let mut deserializer = Deserializer::from_bytes(bytes);
+    deserializer.read_header()?;
+    let mut at = deserializer.position();
+    let mut spans = Vec::new();
+    for i in 0..header.length as usize {
+        at = add_spans(bytes, at, &mut vec![i], &mut spans)?;
+    }
+    Ok(spans)
+}
+
+/// Adds the spans of the record at `at` and the ones nested in it,
+/// returning where the record ends.
+fn add_spans(bytes: &[u8], at: usize, path: &mut Vec<usize>, spans: &mut Vec<Span>) -> Result<usize> {
+    let start = at + bytes[at..].iter().take_while(|&&b| b == 0x00).count();
+    let end = start + record_len(&bytes[start..])?;
+    spans.push(Span { path: path.clone(), start, end });
+    if let Some((header_len, count)) = nested_values(&bytes[start..]) {
+        let mut at = start + header_len;
+        for i in 0..count {
+            path.push(i);
+            at = add_spans(bytes, at, path, spans)?;
+            path.pop();
+        }
+    }
+    Ok(end)
+}
+
+#[cfg(all(test, feature = "ser"))]
+mod tests {
+    use super::{from_bytes_with_spans, from_reader, DocumentReader, Header, Records, Span};
+    use crate::error::DeError;
+    use crate::{to_bytes, to_bytes_with, DeserializerOptions, SerializerOptions, Value};
+
+    #[test]
+    fn test_document_reader() {
+        let mut log = to_bytes(&(1, "start")).unwrap();
+        log.extend(to_bytes_with(&crate::parse_red("[a: 'b]").unwrap(), SerializerOptions::new()).unwrap());
+        log.extend(to_bytes(&(2, "stop")).unwrap());
+
+        let values: Vec<(Header, Value)> = DocumentReader::new(&log[..]).collect::<Result<_, _>>().unwrap();
+        assert_eq!(3, values.len());
+        assert!(values[1].0.has_symbol_table() && !values[2].0.has_symbol_table());
+        assert_eq!(crate::parse_red("[2 \"stop\"]").unwrap(), values[2].1);
+
+        // the middle one isn't an (i32, String)
+        let entries: Vec<_> = DocumentReader::<_, (i32, String)>::new(&log[..]).collect();
+        assert_eq!(3, entries.len());
+        assert!(entries[1].is_err());
+        assert_eq!((2, String::from("stop")), entries[2].as_ref().unwrap().1);
+
+        let mut entries = DocumentReader::<_, Value>::new(&log[..log.len() - 1]);
+        assert!(entries.nth(1).unwrap().is_ok());
+        assert_eq!(Some(Err(DeError::Eof)), entries.next().map(|e| e.map(|_| ())));
+        assert!(entries.next().is_none());
+        assert_eq!(Err(DeError::Syntax), Header::parse(&[0x00; 16]));
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let mut buffer = to_bytes(&(1, "one")).unwrap();
+        let first: (i32, String) = crate::from_slice_owned(&buffer).unwrap();
+        buffer.clear();
+        buffer.extend(to_bytes(&crate::parse_red("[a: 2]").unwrap()).unwrap());
+        buffer.extend(to_bytes(&(3, "three")).unwrap());
+        assert_eq!((1, String::from("one")), first);
+
+        let mut stream = &buffer[..];
+        assert_eq!(crate::parse_red("[a: 2]").unwrap(), from_reader::<_, Value>(&mut stream).unwrap());
+        assert_eq!((3, String::from("three")), from_reader(&mut stream).unwrap());
+        assert_eq!(Err(DeError::Eof), from_reader::<_, Value>(&mut stream));
+    }
+
+    #[test]
+    fn test_spans() {
+        // [a: [1 "x"] #(k: 2.5)]
+        let value = crate::parse_red("[a: [1 \"x\"] #(k: 2.5)]").unwrap();
+        let bytes = to_bytes(&value).unwrap();
+        let (decoded, spans) = from_bytes_with_spans::<Value>(&bytes, DeserializerOptions::new()).unwrap();
+        assert_eq!(value, decoded);
+        let paths: Vec<&[usize]> = spans.iter().map(|span| &span.path[..]).collect();
+        assert_eq!(vec![&[0][..], &[0, 0], &[0, 1], &[0, 1, 0], &[0, 1, 1], &[0, 2], &[0, 2, 0], &[0, 2, 1]], paths);
+        assert_eq!((0x05, bytes.len()), (bytes[spans[0].start], spans[0].end));
+        assert_eq!(Span { path: vec![0, 1, 0], start: spans[2].start + 12, end: spans[2].start + 20 }, spans[3]);
+        // the float of the map is aligned to 8 bytes
+        let float = &spans[7];
+        assert_eq!(0x0C, bytes[float.start]);
+        assert_eq!((float.start % 8, float.end - float.start), (0, 12));
+        assert_eq!(spans[5].end, float.end);
+        assert_eq!(Ok(decoded), crate::from_bytes(&bytes[..spans[0].end]));
+
+        assert_eq!(Err(DeError::Eof), super::spans(&bytes[..bytes.len() - 4]));
+    }
+
+    #[test]
+    fn test_records() {
+        // 1 "x" 3, as Red saves `[1 "x" 3]`
+        let payloads: Vec<Vec<u8>> = vec![to_bytes(&1).unwrap(), to_bytes(&"x").unwrap(), to_bytes(&3).unwrap()];
+        let mut document = payloads[0][..8].to_vec();
+        document.extend_from_slice(&3_u32.to_le_bytes());
+        document.extend_from_slice(&(payloads.iter().map(|p| p.len() as u32 - 16).sum::<u32>()).to_le_bytes());
+        for payload in &payloads {
+            document.extend_from_slice(&payload[16..]);
+        }
+
+        let values: Vec<Value> = Records::new(&document).unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(vec![Value::Integer(1), Value::String("x".to_string()), Value::Integer(3)], values);
+
+        let numbers: Vec<_> = Records::<i32>::new(&document).unwrap().collect();
+        assert_eq!(2, numbers.len());
+        assert!(numbers[1].is_err());
+        let numbers: Vec<_> = Records::<i32>::new(&document).unwrap().recover(true).collect();
+        assert_eq!((3, Some(&3)), (numbers.len(), numbers[2].as_ref().ok()));
+
+        // a record of unknown type can't be stepped over
+        document[16 + 8] = 0x7F;
+        let values: Vec<_> = Records::<Value>::new(&document).unwrap().recover(true).collect();
+        assert_eq!(vec![Ok(Value::Integer(1)), Err(DeError::Message(message!("Unsupported record type {}.", 127)))], values);
+    }
+}
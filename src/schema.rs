@@ -0,0 +1,371 @@
+//! Checking that documents have an expected shape before trusting them.
+//!
+//! ```
+//! # use redbin::schema::Schema;
+//! # let upload = redbin::to_bytes(&redbin::parse_red(r#"[name: "x" port: 8080]"#).unwrap()).unwrap();
+//! let schema = Schema::object()
+//!     .field("name", Schema::string().max_len(64))
+//!     .field("port", Schema::integer().range(1.0, 65535.0))
+//!     .optional("tags", Schema::block_of(Schema::string()));
+//! for violation in schema.validate(&upload) {
+//!     eprintln!("{}", violation);
+//! }
+//! # assert!(schema.validate(&upload).is_empty());
+//! ```
+
+use crate::value::Value;
+use std::fmt;
+
+/// Expected shape of a value: its type, and optionally its number range,
+/// length, elements or keys.
+#[derive(Clone, Debug)]
+pub struct Schema {
+    shape: Shape,
+    min: Option<f64>,
+    max: Option<f64>,
+    min_len: Option<usize>,
+    max_len: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+enum Shape {
+    Any,
+    None,
+    Logic,
+    Integer,
+    Float,
+    Number,
+    Char,
+    String,
+    Binary,
+    /// a file, url, tag, email, word, path or paren, by its kind name
+    Kind(&'static str),
+    /// a block, with the shape of every element
+    Block(Option<Box<Schema>>),
+    /// a block with the given elements in order
+    Tuple(Vec<Schema>),
+    /// a block of keys followed by their values
+    Object(Vec<Field>),
+    OneOf(Vec<Schema>),
+}
+
+#[derive(Clone, Debug)]
+struct Field {
+    key: String,
+    schema: Schema,
+    required: bool,
+}
+
+/// A place where a document doesn't match a schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    /// where the value is, like a Red path with 1-based indexes, e.g.
+    /// `servers/2/port`; empty for the whole document
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl Schema {
+    fn new(shape: Shape) -> Self {
+        Schema { shape, min: None, max: None, min_len: None, max_len: None }
+    }
+
+    pub fn any() -> Self {
+        Self::new(Shape::Any)
+    }
+
+    pub fn none() -> Self {
+        Self::new(Shape::None)
+    }
+
+    pub fn logic() -> Self {
+        Self::new(Shape::Logic)
+    }
+
+    pub fn integer() -> Self {
+        Self::new(Shape::Integer)
+    }
+
+    pub fn float() -> Self {
+        Self::new(Shape::Float)
+    }
+
+    /// An `integer!` or a `float!`.
+    pub fn number() -> Self {
+        Self::new(Shape::Number)
+    }
+
+    pub fn char() -> Self {
+        Self::new(Shape::Char)
+    }
+
+    pub fn string() -> Self {
+        Self::new(Shape::String)
+    }
+
+    pub fn binary() -> Self {
+        Self::new(Shape::Binary)
+    }
+
+    /// A value tagged with `kind` in `Value`'s other formats, e.g. `"word"`,
+    /// `"set-word"`, `"file"` or `"path"`.
+    ///
+    /// # Panics
+    ///
+    /// If `kind` isn't one of these kinds.
+    pub fn kind(kind: &'static str) -> Self {
+        assert!(crate::value::kind_type(kind).is_some(), "unknown kind {:?}", kind);
+        Self::new(Shape::Kind(kind))
+    }
+
+    /// A block of any values.
+    pub fn block() -> Self {
+        Self::new(Shape::Block(None))
+    }
+
+    /// A block of values all matching `items`.
+    pub fn block_of(items: impl Into<Schema>) -> Self {
+        Self::new(Shape::Block(Some(Box::new(items.into()))))
+    }
+
+    /// A block of exactly these values, in this order.
+    pub fn tuple(items: Vec<Schema>) -> Self {
+        Self::new(Shape::Tuple(items))
+    }
+
+    /// A block of keys followed by their values, e.g. `[name: "x" port: 80]`,
+    /// with the keys given by `field` and `optional`. Other keys are allowed.
+    pub fn object() -> ObjectSchema {
+        ObjectSchema(Self::new(Shape::Object(Vec::new())))
+    }
+
+    /// A value matching any of `schemas`.
+    pub fn one_of(schemas: Vec<Schema>) -> Self {
+        Self::new(Shape::OneOf(schemas))
+    }
+
+    /// Limits numbers to `min..=max`.
+    pub fn range(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    /// Requires strings, binaries and blocks of at least `len` elements.
+    pub fn min_len(mut self, len: usize) -> Self {
+        self.min_len = Some(len);
+        self
+    }
+
+    /// Requires strings, binaries and blocks of at most `len` elements.
+    pub fn max_len(mut self, len: usize) -> Self {
+        self.max_len = Some(len);
+        self
+    }
+
+    /// Checks a Redbin document, returning all violations, none if it matches.
+    #[cfg(feature = "de")]
+    pub fn validate(&self, bytes: &[u8]) -> Vec<Violation> {
+        match crate::from_bytes::<Value>(bytes) {
+            Ok(value) => self.validate_value(&value),
+            Err(e) => vec![Violation { path: String::new(), message: e.to_string() }],
+        }
+    }
+
+    /// Checks a value, returning all violations, none if it matches.
+    pub fn validate_value(&self, value: &Value) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        self.check(value, &mut String::new(), &mut violations);
+        violations
+    }
+
+    fn check(&self, value: &Value, path: &mut String, violations: &mut Vec<Violation>) {
+        let mut violation = |message: String| violations.push(Violation { path: path.clone(), message });
+        let matches = match (&self.shape, value) {
+            (Shape::Any, _)
+                | (Shape::None, Value::None)
+                | (Shape::Logic, Value::Logic(_))
+                | (Shape::Integer, Value::Integer(_))
+                | (Shape::Float, Value::Float(_))
+                | (Shape::Number, Value::Integer(_) | Value::Float(_))
+                | (Shape::Char, Value::Char(_))
+                | (Shape::String, Value::String(_))
+                | (Shape::Binary, Value::Binary(_))
+                | (Shape::Block(_) | Shape::Tuple(_) | Shape::Object(_), Value::Block(_)) => true,
+            (Shape::Kind(kind), value) => kind_name(value) == *kind,
+            (Shape::OneOf(schemas), value) => schemas.iter().any(|schema| schema.validate_value(value).is_empty()),
+            _ => false,
+        };
+        if !matches {
+            violation(format!("expected {}, found {}", self.expected(), value.type_name()));
+            return;
+        }
+
+        if let Some(number) = value.as_f64() {
+            if self.min.is_some_and(|min| number < min) || self.max.is_some_and(|max| number > max) {
+                violation(format!("{} is out of range {}..{}", value,
+                    self.min.unwrap_or(f64::NEG_INFINITY), self.max.unwrap_or(f64::INFINITY)));
+            }
+        }
+        let len = match value {
+            Value::String(s) => Some(s.chars().count()),
+            Value::Binary(bytes) => Some(bytes.len()),
+            _ => value.as_block().map(<[Value]>::len),
+        };
+        if let Some(len) = len {
+            if self.min_len.is_some_and(|min| len < min) {
+                violation(format!("length {} is below {}", len, self.min_len.unwrap_or(0)));
+            }
+            if self.max_len.is_some_and(|max| len > max) {
+                violation(format!("length {} is above {}", len, self.max_len.unwrap_or(0)));
+            }
+        }
+
+        let values = value.as_block().unwrap_or(&[]);
+        match &self.shape {
+            Shape::Block(Some(items)) => {
+                for (i, item) in values.iter().enumerate() {
+                    items.check_at(item, &(i + 1).to_string(), path, violations);
+                }
+            }
+            Shape::Tuple(items) => {
+                if items.len() != values.len() {
+                    violations.push(Violation { path: path.clone(),
+                        message: format!("expected {} values, found {}", items.len(), values.len()) });
+                }
+                for (i, (schema, item)) in items.iter().zip(values).enumerate() {
+                    schema.check_at(item, &(i + 1).to_string(), path, violations);
+                }
+            }
+            Shape::Object(fields) => {
+                for field in fields {
+                    match value.get(&field.key) {
+                        Some(item) => field.schema.check_at(item, &field.key, path, violations),
+                        None if field.required => violations.push(Violation { path: path.clone(),
+                            message: format!("missing key {}", field.key) }),
+                        None => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Checks `value` found at `element` of `path`.
+    fn check_at(&self, value: &Value, element: &str, path: &mut String, violations: &mut Vec<Violation>) {
+        let len = path.len();
+        if !path.is_empty() {
+            path.push('/');
+        }
+        path.push_str(element);
+        self.check(value, path, violations);
+        path.truncate(len);
+    }
+
+    /// Red type names this schema accepts, for messages.
+    fn expected(&self) -> String {
+        match &self.shape {
+            Shape::Any => "any-type!".to_string(),
+            Shape::None => "none!".to_string(),
+            Shape::Logic => "logic!".to_string(),
+            Shape::Integer => "integer!".to_string(),
+            Shape::Float => "float!".to_string(),
+            Shape::Number => "number!".to_string(),
+            Shape::Char => "char!".to_string(),
+            Shape::String => "string!".to_string(),
+            Shape::Binary => "binary!".to_string(),
+            Shape::Kind(kind) => format!("{}!", kind),
+            Shape::Block(_) | Shape::Tuple(_) | Shape::Object(_) => "block!".to_string(),
+            Shape::OneOf(schemas) => schemas.iter().map(Schema::expected).collect::<Vec<_>>().join(" or "),
+        }
+    }
+}
+
+/// Schema of an object, see `Schema::object`, taking its keys.
+#[derive(Clone, Debug)]
+pub struct ObjectSchema(Schema);
+
+impl ObjectSchema {
+    /// Requires `key`, with a value matching `schema`.
+    pub fn field(self, key: &str, schema: impl Into<Schema>) -> Self {
+        self.with_field(key, schema.into(), true)
+    }
+
+    /// Allows `key`, with a value matching `schema`.
+    pub fn optional(self, key: &str, schema: impl Into<Schema>) -> Self {
+        self.with_field(key, schema.into(), false)
+    }
+
+    fn with_field(mut self, key: &str, schema: Schema, required: bool) -> Self {
+        if let Shape::Object(fields) = &mut self.0.shape {
+            fields.push(Field { key: key.to_string(), schema, required });
+        }
+        self
+    }
+
+    /// Checks a Redbin document, like `Schema::validate`.
+    #[cfg(feature = "de")]
+    pub fn validate(&self, bytes: &[u8]) -> Vec<Violation> {
+        self.0.validate(bytes)
+    }
+
+    /// Checks a value, like `Schema::validate_value`.
+    pub fn validate_value(&self, value: &Value) -> Vec<Violation> {
+        self.0.validate_value(value)
+    }
+}
+
+impl From<ObjectSchema> for Schema {
+    fn from(object: ObjectSchema) -> Self {
+        object.0
+    }
+}
+
+/// Kind name of a value, as tagged in `Value`'s other formats.
+fn kind_name(value: &Value) -> &'static str {
+    value.type_name().trim_end_matches('!')
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use super::{Schema, Violation};
+    use crate::{parse_red, to_bytes};
+
+    #[test]
+    fn test_schema() {
+        let schema = Schema::object()
+            .field("name", Schema::string().min_len(1))
+            .field("port", Schema::integer().range(1.0, 65535.0))
+            .optional("tags", Schema::block_of(Schema::one_of(vec![Schema::string(), Schema::kind("word")])))
+            .optional("at", Schema::tuple(vec![Schema::number(), Schema::number()]))
+            .optional("owner", Schema::object().field("id", Schema::integer()));
+
+        let good = parse_red(r#"[name: "db" port: 5432 tags: ["a" b] at: [1 2.5] owner: [id: 7]]"#).unwrap();
+        assert_eq!(Vec::<Violation>::new(), schema.validate(&to_bytes(&good).unwrap()));
+
+        let bad = parse_red(r#"[name: "" port: 0 tags: ["a" 3] at: [1] owner: [id: "7"]]"#).unwrap();
+        let messages: Vec<String> = schema.validate(&to_bytes(&bad).unwrap()).iter().map(|v| v.to_string()).collect();
+        assert_eq!(messages, vec![
+            "name: length 0 is below 1",
+            "port: 0 is out of range 1..65535",
+            "tags/2: expected string! or word!, found integer!",
+            "at: expected 2 values, found 1",
+            "owner/id: expected integer!, found string!",
+        ]);
+
+        let missing = schema.validate_value(&parse_red("[name: \"db\"]").unwrap());
+        assert_eq!(missing, vec![Violation { path: String::new(), message: "missing key port".to_string() }]);
+        assert_eq!("expected block!, found integer!", schema.validate_value(&parse_red("1").unwrap())[0].message);
+        assert_eq!(1, schema.validate(b"REDBIN").len());
+    }
+}
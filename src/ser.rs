@@ -1,82 +1,751 @@
-use crate::error::{Error, Result};
+use crate::error::SerError;
 use serde::ser::{self, Serialize};
-use iconv::{Iconv, IconvError};
-use crate::iconv_tools::iconv;
-
-mod types {
-    pub const NONE: i32 = 0x03_i32;
-    pub const LOGIC: i32 = 0x04_i32;
-    pub const BLOCK: i32 = 0x05_i32;
-    pub const PAREN: i32 = 0x06_i32;
-    pub const STRING: i32 = 0x07_i32;
-    pub const CHAR: i32 = 0x0A_i32;
-    pub const INTEGER: i32 = 0x0B_i32;
-    pub const FLOAT: i32 = 0x0C_i32;
-    pub const BYTES: i32 = 0x29_i32;
-}
-
-pub struct Serializer {
-    output: Vec<u8>,
-    length: i32,
-	ucs4_encoder: Iconv,
+use crate::adapters;
+use crate::types::{self, float_record, VariantCase, HEADER_LEN};
+use crate::value;
+use std::convert::TryFrom;
+
+type Result<T> = std::result::Result<T, SerError>;
+
+
+const SYMBOL_TABLE_FLAG: u8 = 0x04;
+
+/// Record emitted by the next `serialize_str` call.
+#[derive(Clone, Copy, PartialEq)]
+enum StrRecord {
+    /// `string!`
+    String,
+    /// Any other string-like series, e.g. `file!`.
+    Series(i32),
+    /// Word-like record referring to the symbol table, e.g. `issue!`.
+    Word(i32),
+}
+
+/// Characters `string!` records are written with, see
+/// `SerializerOptions::string_encoding`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum StringEncoding {
+    /// ASCII if possible, UCS-4 otherwise.
+    #[default]
+    Auto,
+    /// 1-byte characters up to U+007F. Red reads 1-byte strings as
+    /// Latin-1 but this crate as UTF-8, which agree only on ASCII, so there
+    /// is no Latin-1 option.
+    Ascii,
+    /// 2-byte characters up to U+FFFF.
+    Ucs2,
+    /// 4-byte characters, fitting any text.
+    Ucs4,
+}
+
+/// What to do with text that doesn't fit the encoding chosen for it, see
+/// `SerializerOptions::unencodable`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Unencodable {
+    /// Fail with an error.
+    #[default]
+    Error,
+    /// Write characters that don't fit as Red escapes, e.g. `^(1F600)`.
+    /// This is lossy: Redbin strings aren't parsed again, so a Red peer, and
+    /// this crate, read the escape itself as text, `"caf^(E9)"` for
+    /// `"café"`. Text already holding a `^` fails, as its escapes couldn't
+    /// be told apart from it.
+    Escape,
+    /// Write the whole string with 2-byte, or if needed 4-byte characters.
+    Widen,
+}
+
+/// Serialization settings, see `to_bytes_with`.
+#[derive(Clone, Debug, Default)]
+pub struct SerializerOptions {
+    skip_none_fields: bool,
+    wrap_some: bool,
+    positional_structs: bool,
+    empty_as_none: bool,
+    tuples_as_paren: bool,
+    variants_by_index: bool,
+    variant_case: VariantCase,
+    large_integers_as_string: bool,
+    string_encoding: StringEncoding,
+    unencodable: Unencodable,
+    wrap_root: bool,
+}
+
+impl SerializerOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leave out struct fields and map entries whose value is `none`
+    /// (`Option::None`, but also `()` and unit structs), instead of writing
+    /// the key followed by `none`.
+    pub fn skip_none_fields(mut self, skip: bool) -> Self {
+        self.skip_none_fields = skip;
+        self
+    }
+
+    /// Write `Some(x)` as a one-element paren `(x)`, so that nested options
+    /// like `Some(None)` stay distinguishable from `None`. Read such data
+    /// back with `DeserializerOptions::wrap_some`.
+    pub fn wrap_some(mut self, wrap: bool) -> Self {
+        self.wrap_some = wrap;
+        self
+    }
+
+    /// Write all structs as blocks of bare values in field order, e.g.
+    /// `[12.5 "sdf"]`, like `adapters::Positional` does for single fields.
+    pub fn positional_structs(mut self, positional: bool) -> Self {
+        self.positional_structs = positional;
+        self
+    }
+
+    /// Write empty sequences and maps as `none`, as Red code often expects
+    /// for missing collections. With `skip_none_fields`, fields holding them
+    /// are left out. Read them back with `DeserializerOptions::none_as_empty`.
+    pub fn empty_as_none(mut self, empty_as_none: bool) -> Self {
+        self.empty_as_none = empty_as_none;
+        self
+    }
+
+    /// Write tuples and tuple structs as parens, e.g. `(1 "a")`, for dialects
+    /// telling them apart from blocks. They read back either way.
+    pub fn tuples_as_paren(mut self, paren: bool) -> Self {
+        self.tuples_as_paren = paren;
+        self
+    }
+
+    /// Write enum variants as their index, e.g. `(2 [x: 1])`, instead of
+    /// their name. More compact, but the enum can't be reordered afterwards.
+    /// Both read back the same.
+    pub fn variants_by_index(mut self, by_index: bool) -> Self {
+        self.variants_by_index = by_index;
+        self
+    }
+
+    /// Write enum variant names in another case, e.g. `'unit-struct` for
+    /// `UnitStruct` in Red style. Read them back with
+    /// `DeserializerOptions::variant_case`.
+    pub fn variant_case(mut self, case: VariantCase) -> Self {
+        self.variant_case = case;
+        self
+    }
+
+    /// Write integers beyond the 32-bit `integer!` range as decimal strings,
+    /// e.g. `"8589934592"`, instead of failing (`i64`, `u32`) or writing an
+    /// 8-byte `binary!` (`u64`). Readable by older Red with a plain `load`.
+    /// They read back either way.
+    pub fn large_integers_as_string(mut self, as_string: bool) -> Self {
+        self.large_integers_as_string = as_string;
+        self
+    }
+
+    /// Write strings with the given characters, unless an adapter like
+    /// `adapters::RedString` chooses them.
+    pub fn string_encoding(mut self, encoding: StringEncoding) -> Self {
+        self.string_encoding = encoding;
+        self
+    }
+
+    /// What to do with strings having characters beyond their encoding.
+    pub fn unencodable(mut self, policy: Unencodable) -> Self {
+        self.unencodable = policy;
+        self
+    }
+
+    /// Write the value of a document inside a one-element block, e.g. `[1]`,
+    /// for Red code expecting a block whatever the value. Read it back with
+    /// `DeserializerOptions::unwrap_root`.
+    pub fn wrap_root(mut self, wrap: bool) -> Self {
+        self.wrap_root = wrap;
+        self
+    }
+}
+
+/// Growable byte buffer a `Serializer` writes to, e.g. `Vec<u8>` or, with
+/// the `bumpalo` feature, an arena-allocated `bumpalo::collections::Vec`.
+pub trait Output {
+    /// New empty buffer, allocating the way `self` does.
+    fn empty_like(&self) -> Self;
+    fn extend_from_slice(&mut self, bytes: &[u8]);
+    fn truncate(&mut self, len: usize);
+    fn as_slice(&self) -> &[u8];
+    fn as_mut_slice(&mut self) -> &mut [u8];
+
+    /// Bytes written, which may be more than those kept, see `Overflow::Count`.
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Output for Vec<u8> {
+    fn empty_like(&self) -> Self {
+        Vec::new()
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        Vec::extend_from_slice(self, bytes)
+    }
+
+    fn truncate(&mut self, len: usize) {
+        Vec::truncate(self, len)
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+#[cfg(feature = "bumpalo")]
+impl<'bump> Output for bumpalo::collections::Vec<'bump, u8> {
+    fn empty_like(&self) -> Self {
+        bumpalo::collections::Vec::new_in(self.bump())
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        self.extend_from_slice_copy(bytes)
+    }
+
+    fn truncate(&mut self, len: usize) {
+        bumpalo::collections::Vec::truncate(self, len)
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl Output for bytes::BytesMut {
+    fn empty_like(&self) -> Self {
+        bytes::BytesMut::new()
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        bytes::BytesMut::extend_from_slice(self, bytes)
+    }
+
+    fn truncate(&mut self, len: usize) {
+        bytes::BytesMut::truncate(self, len)
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+/// What a `SliceOutput` does with bytes that don't fit its region.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// Move what was written to the heap and go on there.
+    Spill,
+    /// Only count them, for targets without a heap. Once something didn't
+    /// fit, nothing after it is kept.
+    Count,
+}
+
+/// Output writing into a fixed region of memory, e.g. shared with a Red
+/// process. Bytes that don't fit are handled by its `Overflow` policy, so
+/// serializing can finish and tell how much room the document needs.
+pub struct SliceOutput<'a> {
+    region: &'a mut [u8],
+    overflow: Overflow,
+    /// bytes kept in the region
+    kept: usize,
+    /// bytes written, including those that didn't fit the region
+    len: usize,
+    spill: Vec<u8>,
+}
+
+impl<'a> SliceOutput<'a> {
+    /// Output spilling over to the heap.
+    pub fn new(region: &'a mut [u8]) -> Self {
+        Self::with_overflow(region, Overflow::Spill)
+    }
+
+    pub fn with_overflow(region: &'a mut [u8], overflow: Overflow) -> Self {
+        SliceOutput { region, overflow, kept: 0, len: 0, spill: Vec::new() }
+    }
+
+    /// Whether everything written fits the region.
+    pub fn fits(&self) -> bool {
+        self.kept == self.len
+    }
+
+    fn into_region(self) -> &'a mut [u8] {
+        self.region
+    }
+}
+
+impl Output for SliceOutput<'_> {
+    fn empty_like(&self) -> Self {
+        SliceOutput::with_overflow(&mut [], self.overflow)
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        if self.fits() && self.len + bytes.len() <= self.region.len() {
+            self.region[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+            self.kept += bytes.len();
+        } else if self.overflow == Overflow::Spill {
+            if self.spill.is_empty() {
+                self.spill.extend_from_slice(&self.region[..self.kept]);
+            }
+            self.spill.extend_from_slice(bytes);
+        }
+        self.len += bytes.len();
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.kept = self.kept.min(len);
+        self.len = self.len.min(len);
+        if !self.spill.is_empty() {
+            self.spill.truncate(self.len);
+            if self.len <= self.region.len() {
+                self.region[..self.len].copy_from_slice(&self.spill);
+                self.kept = self.len;
+                self.spill.clear();
+            }
+        }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        if self.spill.is_empty() {&self.region[..self.kept]} else {&self.spill}
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        if self.spill.is_empty() {&mut self.region[..self.kept]} else {&mut self.spill}
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// Figures about the records written by a `Serializer` so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SerializerMetrics {
+    /// size of the payload, without the header and symbol table
+    pub bytes_written: usize,
+    pub records: usize,
+    /// deepest nesting of blocks and parens
+    pub max_depth: usize,
+    /// strings converted from UTF-8 to UCS-2 or UCS-4
+    pub strings_transcoded: usize,
+}
+
+/// Serde serializer producing Redbin records.
+///
+/// Structs are written as blocks of `set-word!` keys and values, e.g.
+/// `[a: 1 b: "x"]`, and maps as `map!`s, so neither is taken for a sequence
+/// when read back without a type, e.g. in an internally tagged enum.
+///
+/// Map keys can be of any type and are written like any other value:
+/// strings as `string!` (`set-word!` within `adapters::SetWordKeys`),
+/// `adapters::Word` as `word!`, integers as `integer!` and so on.
+pub struct Serializer<B = Vec<u8>> {
+    output: B,
+    /// NUL-terminated names of the symbol table
+    symbols: B,
+    options: SerializerOptions,
+    str_record: StrRecord,
+    /// character size of the next string, see `adapters::RedString`
+    str_unit: Option<u8>,
+    word_keys: bool,
+    positional: bool,
+    raw_bytes: bool,
+    /// next sequence is pre-encoded byte chunks, see `stream::ReadBinary`
+    raw_chunks: bool,
+    /// record type of the next sequence, e.g. `paren!`, if not a `block!`
+    block_record: Option<i32>,
+    /// next sequence is a `(head, series)` pair, see `adapters::RedSeries`
+    series_head: bool,
+    depth: usize,
+    metrics: SerializerMetrics,
 }
 
 impl Serializer {
     pub fn new() -> Self {
+        Self::with_options(SerializerOptions::default())
+    }
+
+    pub fn with_options(options: SerializerOptions) -> Self {
+        Self::with_output(Vec::new(), options)
+    }
+}
+
+impl<B: Output> Serializer<B> {
+    /// Serializer appending records to `output`.
+    pub fn with_output(output: B, options: SerializerOptions) -> Self {
         Serializer {
-            output: Vec::new(),
-            length: 0,
-			ucs4_encoder: encoder("UCS-4LE").unwrap(),
+            symbols: output.empty_like(),
+            output,
+            options,
+            str_record: StrRecord::String,
+            str_unit: None,
+            word_keys: false,
+            positional: false,
+            raw_bytes: false,
+            raw_chunks: false,
+            block_record: None,
+            series_head: false,
+            depth: 0,
+            metrics: SerializerMetrics::default(),
+        }
+    }
+
+    pub fn metrics(&self) -> SerializerMetrics {
+        SerializerMetrics { bytes_written: self.output.len(), ..self.metrics }
+    }
+
+    /// The records written, without a document header.
+    pub fn into_output(self) -> B {
+        self.output
+    }
+
+    /// Forgets the record being written when it failed: its depth and the
+    /// adapter flags it set for records it didn't get to.
+    fn reset(&mut self) {
+        self.str_record = StrRecord::String;
+        self.str_unit = None;
+        self.word_keys = false;
+        self.positional = false;
+        self.raw_bytes = false;
+        self.raw_chunks = false;
+        self.block_record = None;
+        self.series_head = false;
+        self.depth = 0;
+    }
+
+    fn enter(&mut self) {
+        self.depth += 1;
+        self.metrics.max_depth = self.metrics.max_depth.max(self.depth);
+    }
+
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Called as each record starts being written.
+    fn record(&mut self, record_type: i32) {
+        self.metrics.records += 1;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(offset = self.output.len(), record_type, depth = self.depth, "encode record");
+        #[cfg(not(feature = "tracing"))]
+        let _ = record_type;
+    }
+
+    fn any_block_header(&mut self, length: i32, record_type: i32) {
+        self.record(record_type);
+        self.output.extend_from_slice(&record_type.to_le_bytes());
+        if record_type != types::MAP {
+            self.output.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // position block on start
         }
+        self.output.extend_from_slice(&length.to_le_bytes());
     }
 
-    fn append_any_block_header(vec: &mut Vec<u8>, length: i32, paren: bool) -> () {
-        let t = if paren {types::PAREN} else {types::BLOCK};
-        vec.append(&mut Vec::from(t.to_le_bytes()));
-        vec.append(&mut Vec::from([0x00, 0x00, 0x00, 0x00])); // position block on start
-        vec.append(&mut Vec::from(length.to_le_bytes()));
+    /// Starts a block whose length is counted as elements are written.
+    fn begin_block(&mut self) -> Compound<'_, B> {
+        let raw = std::mem::take(&mut self.raw_chunks);
+        let series_head = std::mem::take(&mut self.series_head);
+        if !raw && !series_head {
+            let record_type = self.block_record.take().unwrap_or(types::BLOCK);
+            self.any_block_header(0, record_type);
+            self.enter();
+        }
+        let block = OpenBlock {
+            raw,
+            in_paren: false,
+            series_head,
+            // where the head is written instead, if `series_head`
+            length_at: if series_head {self.output.len()} else {self.output.len().saturating_sub(4)}, // unused if raw
+            length: 0,
+            key_at: 0,
+            word_keys: std::mem::take(&mut self.word_keys),
+            positional: std::mem::take(&mut self.positional),
+            empty_as_none: false,
+        };
+        Compound { ser: self, block }
     }
 
-    fn block_header_with(&mut self, len: usize) -> () {
-        Self::append_any_block_header(&mut self.output, len as i32, false);
-        self.length = len as i32;
+    /// Starts the block of a struct's fields.
+    fn begin_struct(&mut self) -> Compound<'_, B> {
+        let positional = self.options.positional_structs;
+        let mut compound = self.begin_block();
+        compound.block.positional |= positional;
+        // `set-word!` keys tell fields from a sequence of strings
+        compound.block.word_keys = true;
+        compound
     }
 
-    fn variant_header(&mut self, with_value: bool) -> () {
+    /// Writes the paren holding an enum variant, which must be `leave`d
+    /// after writing its content.
+    fn variant_header(&mut self, with_value: bool) {
         let length = if with_value {2} else {1};
-        Self::append_any_block_header(&mut self.output, length, true);
-        self.length = length;
+        self.any_block_header(length, types::PAREN);
+        self.enter();
+    }
+
+    /// Writes which variant of an enum follows, by name or by index.
+    fn variant(&mut self, variant_index: u32, variant: &str) -> Result<()> {
+        if self.options.variants_by_index {
+            ser::Serializer::serialize_u32(&mut *self, variant_index)
+        } else {
+            let variant = self.options.variant_case.apply(variant);
+            ser::Serializer::serialize_str(&mut *self, &variant)
+        }
+    }
+
+    /// Starts the block of a tuple or struct variant's values.
+    fn begin_variant_block(&mut self, variant_index: u32, variant: &'static str, fields: bool) -> Result<Compound<'_, B>> {
+        self.variant_header(true);
+        self.variant(variant_index, variant)?;
+        let mut compound = if fields {self.begin_struct()} else {self.begin_block()};
+        compound.block.in_paren = true;
+        Ok(compound)
+    }
+
+    /// index of `name` in the symbol table, adding it if needed
+    fn symbol(&mut self, name: &str) -> i32 {
+        let names = self.symbols.as_slice();
+        let count = names.iter().filter(|&&b| b == 0x00).count();
+        if let Some(i) = names.split(|&b| b == 0x00).take(count).position(|s| s == name.as_bytes()) {
+            return i as i32;
+        }
+        self.symbols.extend_from_slice(name.as_bytes());
+        self.symbols.extend_from_slice(&[0x00]);
+        count as i32
+    }
+
+    fn word(&mut self, record_type: i32, name: &str) {
+        self.record(record_type);
+        let symbol = self.symbol(name);
+        self.output.extend_from_slice(&record_type.to_le_bytes());
+        self.output.extend_from_slice(&symbol.to_le_bytes());
+        self.output.extend_from_slice(&(-1_i32).to_le_bytes()); // global context
+        self.output.extend_from_slice(&(-1_i32).to_le_bytes()); // index in context
+    }
+
+    /// Series header followed by `data` and padding to 32 bits.
+    fn series(&mut self, record_type: i32, unit: u8, length: usize, data: &[u8]) -> Result<()> {
+        let length = i32::try_from(length).map_err(|_| SerError::TooLarge)?;
+        self.record(record_type);
+        let mut header = record_type.to_le_bytes();
+        header[1] = unit;
+        self.output.extend_from_slice(&header);
+        self.output.extend_from_slice(&[0x00; 4]); // head position
+        self.output.extend_from_slice(&length.to_le_bytes());
+        self.output.extend_from_slice(data);
+        self.pad();
+        Ok(())
+    }
+
+    /// Moves the `integer!` written at `at` into the head position of the
+    /// series record following it.
+    fn move_head(&mut self, at: usize) -> Result<()> {
+        let len = self.output.len();
+        let output = self.output.as_mut_slice();
+        // `block!`, `paren!`, `string!`, `file!`, `url!`, any path, `vector!`, `binary!`, `tag!`, `email!`
+        if output.len() < at + 20 || !matches!(output[at + 8], 0x05..=0x09 | 0x19..=0x1C | 0x23 | 0x29 | 0x2C | 0x2D) {
+            return Err(SerError::Message(message!("Head position given for a value that isn't a series.")));
+        }
+        let mut head = [0x00; 4];
+        head.copy_from_slice(&output[at + 4..at + 8]);
+        output.copy_within(at + 8.., at);
+        output[at + 4..at + 8].copy_from_slice(&head);
+        self.output.truncate(len - 8);
+        self.metrics.records -= 1;
+        Ok(())
+    }
+
+    fn pad(&mut self) {
+        let padding = self.output.len().next_multiple_of(4) - self.output.len();
+        self.output.extend_from_slice(&[0x00; 4][..padding]);
+    }
+}
+
+impl Default for Serializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// State of a block being written, its length is patched in when it ends.
+struct OpenBlock {
+    /// elements are written verbatim, without a block around them
+    raw: bool,
+    /// block is the value of an enum variant paren, ended along with it
+    in_paren: bool,
+    /// block is a head position and the series it's moved into
+    series_head: bool,
+    length_at: usize,
+    length: i32,
+    /// start of the last key written, for dropping skipped entries
+    key_at: usize,
+    word_keys: bool,
+    positional: bool,
+    /// a sequence or map written as `none` if it's empty
+    empty_as_none: bool,
+}
+
+/// Serializes the elements of a block, see `Serializer`.
+pub struct Compound<'a, B> {
+    ser: &'a mut Serializer<B>,
+    block: OpenBlock,
+}
+
+impl<B: Output> Compound<'_, B> {
+    fn element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.ser.raw_bytes = self.block.raw;
+        value.serialize(&mut *self.ser)?;
+        self.block.length = self.block.length.checked_add(1).ok_or(SerError::TooLarge)?;
+        Ok(())
+    }
+
+    fn key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.block.key_at = self.ser.output.len();
+        if self.block.word_keys {
+            self.ser.str_record = StrRecord::Word(types::SET_WORD);
+        }
+        let result = self.element(key);
+        self.ser.str_record = StrRecord::String;
+        result
     }
 
-    fn elements<T>(&mut self, value: &T) -> Result<()>
+    /// Writes a struct field, as a key/value pair or just the value.
+    fn field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        let mut serializer = Serializer::new();
-        value.serialize(&mut serializer)?;
-        self.output.append(&mut serializer.output);
+        if self.block.positional {
+            self.element(value)
+        } else {
+            self.key(key)?;
+            self.value(value)
+        }
+    }
+
+    /// Writes a value after its key, or drops both again if the value turned
+    /// out to be `none` and such fields are skipped.
+    fn value<V>(&mut self, value: &V) -> Result<()>
+    where
+        V: ?Sized + Serialize,
+    {
+        let value_start = self.ser.output.len();
+        self.element(value)?;
+        if self.ser.options.skip_none_fields
+                && self.ser.output.as_slice().get(value_start..) == Some(&types::NONE.to_le_bytes()[..]) {
+            self.ser.output.truncate(self.block.key_at);
+            self.block.length -= 2;
+        }
+        Ok(())
+    }
+
+    fn end_block(self) -> Result<()> {
+        if self.block.raw {
+            return Ok(());
+        }
+        if self.block.series_head {
+            return self.ser.move_head(self.block.length_at);
+        }
+        self.ser.leave();
+        if self.block.in_paren {
+            self.ser.leave();
+        }
+        let at = self.block.length_at;
+        // where the `block!` or `map!` header starts
+        let start = match self.ser.output.as_slice() {
+            output if output.get(at - 4..at) == Some(&types::MAP.to_le_bytes()[..]) => Some(at - 4),
+            output if output.get(at - 8..at - 4) == Some(&types::BLOCK.to_le_bytes()[..]) => Some(at - 8),
+            _ => None,
+        };
+        if let (true, 0, Some(start)) = (self.block.empty_as_none, self.block.length, start) {
+            self.ser.output.truncate(start);
+            self.ser.output.extend_from_slice(&types::NONE.to_le_bytes());
+            return Ok(());
+        }
+        // not kept by a `SliceOutput` out of room
+        if let Some(length) = self.ser.output.as_mut_slice().get_mut(at..at + 4) {
+            length.copy_from_slice(&self.block.length.to_le_bytes());
+        }
         Ok(())
     }
+}
 
-    fn prepend_block_header(&mut self) -> () {
-        let mut header = Vec::new();
-        Self::append_any_block_header(&mut header, self.length, false);
-        self.output.splice(0..0, header);
+/// Document header declaring `records` root records in `size` bytes.
+fn header(records: usize, size: usize, symbols: bool) -> Result<[u8; HEADER_LEN]> {
+    let size = i32::try_from(size).map_err(|_| SerError::TooLarge)?;
+    let records = i32::try_from(records).map_err(|_| SerError::TooLarge)?;
+    let mut header = [0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, // "REDBIN"
+        0x02, // version
+        0x00, // flags
+        0x00, 0x00, 0x00, 0x00, // length (number of records)
+        0x00, 0x00, 0x00, 0x00]; // size of payload
+    if symbols {
+        header[7] |= SYMBOL_TABLE_FLAG;
     }
+    header[8..12].copy_from_slice(&records.to_le_bytes());
+    header[12..].copy_from_slice(&size.to_le_bytes());
+    Ok(header)
+}
 
-	fn ucs4_encode(&mut self, input: &str) -> std::result::Result<Vec<u8>, IconvError> {
-		encode(&mut self.ucs4_encoder, input)
-	}
-	
+/// `text` with characters beyond `max` written as Red escapes, e.g.
+/// `^(1F600)`.
+fn escape_beyond(text: &str, max: char) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c > max {
+            escaped.push_str(&format!("^({:X})", c as u32));
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
 }
 
-fn encoder(to_encoding: &str) -> std::result::Result<Iconv, IconvError> {
-	Iconv::new("UTF-8", to_encoding)
+/// Size of the symbol table of `names`, none if there are no names.
+fn symbol_table_len(names: &[u8]) -> usize {
+    if names.is_empty() {
+        return 0;
+    }
+    let count = names.iter().filter(|&&b| b == 0x00).count();
+    8 + 4 * count + names.len().next_multiple_of(8)
 }
-	
-/// convert `input` from UTF-8 to `encoding`
-fn encode(c: &mut Iconv, input: &str) -> std::result::Result<Vec<u8>, IconvError> {
-	iconv(c, input.as_bytes())
+
+/// Writes the symbol table: count, names buffer size, offsets and the
+/// NUL-terminated UTF-8 names.
+fn symbol_table<B: Output>(output: &mut B, names: &[u8]) {
+    let count = names.iter().filter(|&&b| b == 0x00).count();
+    let size = names.len().next_multiple_of(8); // 64-bit alignment
+    output.extend_from_slice(&(count as i32).to_le_bytes());
+    output.extend_from_slice(&(size as i32).to_le_bytes());
+    let mut offset = 0;
+    for name in names.split(|&b| b == 0x00).take(count) {
+        output.extend_from_slice(&(offset as i32).to_le_bytes());
+        offset += name.len() + 1;
+    }
+    output.extend_from_slice(names);
+    output.extend_from_slice(&[0x00; 8][..size - names.len()]);
 }
 
 
@@ -84,33 +753,285 @@ pub fn to_bytes<T>(value: &T) -> Result<Vec<u8>>
 where
     T: Serialize,
 {
-    let mut header = Vec::from([0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, // "REDBIN"
-            0x02, // version
-            0x00, // flags
-            0x01, 0x00, 0x00, 0x00]); // length (number of records))
+    to_bytes_with(value, SerializerOptions::default())
+}
+
+pub fn to_bytes_with<T>(value: &T, options: SerializerOptions) -> Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    to_bytes_with_metrics(value, options).map(|(document, _)| document)
+}
 
-    let mut serializer = Serializer::new();
-    value.serialize(&mut serializer)?;
-    header.append(&mut Vec::from((serializer.output.len() as i32).to_le_bytes())); // size of payload
-    Ok([&header[..], &serializer.output[..]].concat())
+/// Like `to_bytes_with`, also telling what was written.
+pub fn to_bytes_with_metrics<T>(value: &T, options: SerializerOptions) -> Result<(Vec<u8>, SerializerMetrics)>
+where
+    T: Serialize,
+{
+    let mut document = Vec::new();
+    let metrics = write_document(value, &mut document, options)?;
+    Ok((document, metrics))
+}
+
+/// Like `to_bytes_with`, but builds the document, and everything needed
+/// along the way, in `bump` instead of the global allocator. Adapters with
+/// pre-encoded payloads, like `adapters::Vector` or `adapters::Date`, still
+/// use the global allocator for these.
+#[cfg(feature = "bumpalo")]
+pub fn to_bytes_in<'bump, T>(value: &T, bump: &'bump bumpalo::Bump, options: SerializerOptions)
+    -> Result<bumpalo::collections::Vec<'bump, u8>>
+where
+    T: Serialize,
+{
+    let mut document = bumpalo::collections::Vec::new_in(bump);
+    write_document(value, &mut document, options)?;
+    Ok(document)
+}
+
+/// Like `to_bytes_with`, but appends the document to `output`, ready to be
+/// split off and frozen into a `bytes::Bytes`.
+#[cfg(feature = "bytes")]
+pub fn to_bytes_mut<T>(value: &T, output: &mut bytes::BytesMut, options: SerializerOptions) -> Result<()>
+where
+    T: Serialize,
+{
+    write_document(value, output, options).map(drop)
+}
+
+/// Writes a document holding `value` at the start of `region`, e.g. a
+/// shared-memory segment a Red process reads from, returning its size.
+/// Records are written in place; only the symbol table is built on the
+/// heap, with the records moved along the region to make room for it.
+///
+/// If the document doesn't fit, `SerError::OutputTooSmall` tells the size it
+/// needs, and the content of `region` is unspecified.
+pub fn to_slice<T>(value: &T, region: &mut [u8], options: SerializerOptions) -> Result<usize>
+where
+    T: Serialize,
+{
+    let (head, body) = region.split_at_mut(HEADER_LEN.min(region.len()));
+    let mut serializer = Serializer::with_output(SliceOutput::new(body), options);
+    serialize_root(value, &mut serializer)?;
+    let size = serializer.output.len();
+    let table = symbol_table_len(serializer.symbols.as_slice());
+    let needed = HEADER_LEN + table + size;
+    if !serializer.output.fits() || needed > HEADER_LEN + serializer.output.region.len() || head.len() < HEADER_LEN {
+        return Err(SerError::OutputTooSmall { needed });
+    }
+    let symbols = std::mem::replace(&mut serializer.symbols, SliceOutput::new(&mut []));
+    head.copy_from_slice(&header(1, size, !symbols.is_empty())?);
+    let body = serializer.into_output().into_region();
+    if table > 0 {
+        body.copy_within(..size, table);
+        symbol_table(&mut SliceOutput::new(&mut body[..table]), symbols.as_slice());
+    }
+    Ok(needed)
+}
+
+/// Like `to_slice`, but without touching the heap at all, for devices with
+/// no allocator, e.g. sending telemetry to a Red app. Records are written
+/// to `region`, and names of words, like struct field names, to `symbols`
+/// first, then moved into the symbol table.
+///
+/// Nothing is allocated unless strings are written with escapes
+/// (`Unencodable::Escape`), variant names recased (`variant_case`) or
+/// integers as strings (`large_integers_as_string`), nor while failing
+/// with `SerError::OutputTooSmall`. It tells the size the document needs,
+/// which is only a guess with `skip_none_fields` or `empty_as_none`, taking
+/// back bytes that may not have been kept. If names don't fit `symbols`,
+/// the error is a `SerError::Message`.
+pub fn to_slice_fixed<T>(value: &T, region: &mut [u8], symbols: &mut [u8], options: SerializerOptions) -> Result<usize>
+where
+    T: Serialize,
+{
+    let room = region.len();
+    let (head, body) = region.split_at_mut(HEADER_LEN.min(room));
+    let mut serializer = Serializer::with_output(SliceOutput::with_overflow(body, Overflow::Count), options);
+    serializer.symbols = SliceOutput::with_overflow(symbols, Overflow::Count);
+    // with bytes dropped, the serializer may fail on what it reads back
+    let result = serialize_root(value, &mut serializer);
+    let Serializer { output, symbols, .. } = serializer;
+    if !symbols.fits() {
+        return Err(SerError::Message(message!("Symbol names need {} bytes.", symbols.len())));
+    }
+    let size = output.len();
+    let names_len = symbols.len();
+    let names = &symbols.into_region()[..names_len];
+    let table = symbol_table_len(names);
+    let needed = HEADER_LEN + table + size;
+    if !output.fits() || needed > room {
+        return Err(SerError::OutputTooSmall { needed });
+    }
+    result?;
+    head.copy_from_slice(&header(1, size, !names.is_empty())?);
+    let body = output.into_region();
+    if table > 0 {
+        body.copy_within(..size, table);
+        symbol_table(&mut SliceOutput::with_overflow(&mut body[..table], Overflow::Count), names);
+    }
+    Ok(needed)
+}
+
+/// Like `to_slice_fixed`, but returns the document in a `heapless::Vec` of
+/// at most `N` bytes.
+#[cfg(feature = "heapless")]
+pub fn to_heapless<T, const N: usize>(value: &T, symbols: &mut [u8], options: SerializerOptions)
+    -> Result<heapless::Vec<u8, N>>
+where
+    T: Serialize,
+{
+    let mut document = heapless::Vec::new();
+    document.resize(N, 0x00).expect("a vector of its capacity");
+    let size = to_slice_fixed(value, &mut document, symbols, options)?;
+    document.truncate(size);
+    Ok(document)
+}
+
+/// Writes the root record of a document, wrapped in a block if the options
+/// say so.
+fn serialize_root<T, B>(value: &T, serializer: &mut Serializer<B>) -> Result<()>
+where
+    T: Serialize,
+    B: Output,
+{
+    if serializer.options.wrap_root {
+        std::slice::from_ref(value).serialize(serializer)
+    } else {
+        value.serialize(serializer)
+    }
+}
+
+/// Appends a whole document holding `value` to `document`.
+fn write_document<T, B>(value: &T, document: &mut B, options: SerializerOptions) -> Result<SerializerMetrics>
+where
+    T: Serialize,
+    B: Output,
+{
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("redbin::encode").entered();
+    let mut serializer = Serializer::with_output(document.empty_like(), options);
+    let result = serialize_root(value, &mut serializer);
+    #[cfg(feature = "tracing")]
+    if let Err(error) = &result {
+        tracing::debug!(offset = serializer.output.len(), %error, "encode failed");
+    }
+    result?;
+    finish_document(&serializer, 1, document)?;
+    Ok(serializer.metrics())
+}
+
+/// Appends the header, symbol table and records of `serializer` to
+/// `document`, declaring `records` root records.
+fn finish_document<B: Output>(serializer: &Serializer<B>, records: usize, document: &mut B) -> Result<()> {
+    #[cfg(feature = "tracing")]
+    let start = document.len();
+    document.extend_from_slice(&header(records, serializer.output.len(), !serializer.symbols.is_empty())?);
+    if !serializer.symbols.is_empty() {
+        symbol_table(document, serializer.symbols.as_slice());
+    }
+    document.extend_from_slice(serializer.output.as_slice());
+    #[cfg(feature = "tracing")]
+    tracing::debug!(size = document.len() - start, records = serializer.metrics.records, "encoded");
+    Ok(())
+}
+
+/// Builds a document of many root records, written one at a time as they
+/// come, like Red saves a block of values:
+///
+/// ```
+/// # use redbin::SerializerSession;
+/// # fn main() -> redbin::Result<()> {
+/// # let events = vec![("start", 1), ("stop", 2)];
+/// let mut session = SerializerSession::new();
+/// for event in events {
+///     session.write(&event)?;
+/// }
+/// let document = session.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Read such documents back with `Records`.
+pub struct SerializerSession<B = Vec<u8>> {
+    serializer: Serializer<B>,
+    records: usize,
+}
+
+impl SerializerSession {
+    pub fn new() -> Self {
+        Self::with_options(SerializerOptions::default())
+    }
+
+    pub fn with_options(options: SerializerOptions) -> Self {
+        SerializerSession { serializer: Serializer::with_options(options), records: 0 }
+    }
+}
+
+impl Default for SerializerSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: Output> SerializerSession<B> {
+    /// Session building its records in `output`.
+    pub fn with_output(output: B, options: SerializerOptions) -> Self {
+        SerializerSession { serializer: Serializer::with_output(output, options), records: 0 }
+    }
+
+    /// Adds `value` as the next root record. If it fails to serialize, the
+    /// document is left as it was.
+    pub fn write<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let (len, metrics) = (self.serializer.output.len(), self.serializer.metrics);
+        let names_len = self.serializer.symbols.len();
+        if let Err(error) = value.serialize(&mut self.serializer) {
+            self.serializer.output.truncate(len);
+            self.serializer.symbols.truncate(names_len);
+            self.serializer.metrics = metrics;
+            self.serializer.reset();
+            return Err(error);
+        }
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Number of records written so far.
+    pub fn len(&self) -> usize {
+        self.records
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records == 0
+    }
+
+    /// The whole document, in a buffer allocated the way the records were.
+    pub fn finish(self) -> Result<B> {
+        let mut document = self.serializer.output.empty_like();
+        finish_document(&self.serializer, self.records, &mut document)?;
+        Ok(document)
+    }
 }
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+impl<'a, B: Output> ser::Serializer for &'a mut Serializer<B> {
     type Ok = ();
 
-    type Error = Error;
+    type Error = SerError;
 
-    type SerializeSeq = Self;
-    type SerializeTuple = Self;
-    type SerializeTupleStruct = Self;
-    type SerializeTupleVariant = Self;
-    type SerializeMap = Self;
-    type SerializeStruct = Self;
-    type SerializeStructVariant = Self;
+    type SerializeSeq = Compound<'a, B>;
+    type SerializeTuple = Compound<'a, B>;
+    type SerializeTupleStruct = Compound<'a, B>;
+    type SerializeTupleVariant = Compound<'a, B>;
+    type SerializeMap = Compound<'a, B>;
+    type SerializeStruct = Compound<'a, B>;
+    type SerializeStructVariant = Compound<'a, B>;
 
     fn serialize_bool(self, v: bool) -> Result<()> {
-        self.output.append(&mut Vec::from(types::LOGIC.to_le_bytes()));
-        self.output.append(&mut Vec::from((v as i32).to_le_bytes()));
+        self.record(types::LOGIC);
+        self.output.extend_from_slice(&types::LOGIC.to_le_bytes());
+        self.output.extend_from_slice(&(v as i32).to_le_bytes());
         Ok(())
     }
 
@@ -123,17 +1044,19 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_i32(self, v: i32) -> Result<()> {
-        self.output.append(&mut Vec::from(types::INTEGER.to_le_bytes()));
-        self.output.append(&mut Vec::from(v.to_le_bytes()));
+        self.record(types::INTEGER);
+        self.output.extend_from_slice(&types::INTEGER.to_le_bytes());
+        self.output.extend_from_slice(&v.to_le_bytes());
         Ok(())
     }
 
     fn serialize_i64(self, v: i64) -> Result<()> {
-        if v > (i32::MAX as i64)
-                || v < (i32::MIN as i64) {
-            Err(Error::Message(String::from("32-bit signed integer! limit exceeded")))
+        if let Ok(v) = i32::try_from(v) {
+            self.serialize_i32(v)
+        } else if self.options.large_integers_as_string {
+            self.serialize_str(&v.to_string())
         } else {
-            self.serialize_i32(v as i32)
+            Err(SerError::Message(message!("32-bit signed integer! limit exceeded")))
         }
     }
 
@@ -146,15 +1069,23 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_u32(self, v: u32) -> Result<()> {
-        if v > (i32::MAX as u32) {
-            Err(Error::Message(String::from("32-bit signed integer! limit exceeded")))
+        if let Ok(v) = i32::try_from(v) {
+            self.serialize_i32(v)
+        } else if self.options.large_integers_as_string {
+            self.serialize_str(&v.to_string())
         } else {
-            self.serialize_i32(v as i32)
+            Err(SerError::Message(message!("32-bit signed integer! limit exceeded")))
         }
     }
 
     fn serialize_u64(self, v: u64) -> Result<()> {
-        self.serialize_bytes(&v.to_le_bytes())
+        if !self.options.large_integers_as_string {
+            self.serialize_bytes(&v.to_le_bytes())
+        } else if let Ok(v) = i32::try_from(v) {
+            self.serialize_i32(v)
+        } else {
+            self.serialize_str(&v.to_string())
+        }
     }
 
     fn serialize_f32(self, v: f32) -> Result<()> {
@@ -162,74 +1093,93 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_f64(self, v: f64) -> Result<()> {
-        self.output.append(&mut Vec::from(types::FLOAT.to_le_bytes()));
-        let mut bytes = Vec::from(v.to_le_bytes());
-
-        // swap words
-        bytes.append(&mut Vec::from(&bytes[0..4]));
-        bytes = Vec::from(&bytes[4..12]);
-
-        // Optional padding at the beginning is not added.
-        // Red's "load/as [...] 'redbin" command accepts data without padding.
-        self.output.append(&mut bytes);
+        self.record(types::FLOAT);
+        self.output.extend_from_slice(&float_record(types::FLOAT, v));
         Ok(())
     }
 
     fn serialize_char(self, v: char) -> Result<()> {
-        self.output.append(&mut Vec::from(types::CHAR.to_le_bytes()));
-        let bytes = self.ucs4_encode(v.encode_utf8(&mut [0x00; 4]))
-            .map_err(|e| Error::Message(e.to_string()))?;
-        self.output.append(&mut Vec::from(bytes));
+        self.record(types::CHAR);
+        self.output.extend_from_slice(&types::CHAR.to_le_bytes());
+        self.output.extend_from_slice(&(v as u32).to_le_bytes()); // UCS-4
         Ok(())
     }
 
     fn serialize_str(self, v: &str) -> Result<()> {
-        let mut header = Vec::from(types::STRING.to_le_bytes());
-        let len = v.chars().count() as i32;
-        let (mut encoded, padding) = if len == (v.len() as i32) { // ASCII
-            header[1] = 0x01; // 1-byte characters, ASCII
-            (Vec::from(v),
-                ((4 - (len % 4)) % 4) as usize)
-        } else { // Unicode
-            header[1] = 0x04; // 4-byte characters, UCS-4
-            (self.ucs4_encode(v)
-                .map_err(|e| Error::Message(e.to_string()))?,
-                0 as usize)
+        let record_type = match std::mem::replace(&mut self.str_record, StrRecord::String) {
+            StrRecord::String => types::STRING,
+            StrRecord::Series(t) => t,
+            StrRecord::Word(t) => {
+                self.word(t, v);
+                return Ok(());
+            }
         };
-        header.append(&mut Vec::from([0x00; 4])); // head position
-        header.append(&mut Vec::from(len.to_le_bytes()));
-
-        self.output.append(&mut header);
-        
-        self.output.append(&mut encoded);
-        
-        let mut p = Vec::from([0x00; 4]);
-        p.resize(padding, 0x00);
-        self.output.append(&mut p);
+        let encoding = match self.str_unit.take() {
+            Some(0x01) => StringEncoding::Ascii,
+            Some(0x02) => StringEncoding::Ucs2,
+            Some(_) => StringEncoding::Ucs4,
+            None => self.options.string_encoding,
+        };
+        let (mut unit, max) = match encoding {
+            StringEncoding::Auto if v.is_ascii() => (0x01, '\u{7F}'),
+            StringEncoding::Auto | StringEncoding::Ucs4 => (0x04, char::MAX),
+            StringEncoding::Ascii => (0x01, '\u{7F}'),
+            StringEncoding::Ucs2 => (0x02, '\u{FFFF}'),
+        };
+        let escaped;
+        let mut v = v;
+        if v.chars().any(|c| c > max) {
+            match self.options.unencodable {
+                Unencodable::Error => {
+                    return Err(SerError::Message(message!("String doesn't fit {}-byte characters.", unit)));
+                }
+                Unencodable::Escape if v.contains('^') => {
+                    return Err(SerError::Message(message!("String with a ^ can't be escaped unambiguously.")));
+                }
+                Unencodable::Escape => {
+                    escaped = escape_beyond(v, max);
+                    v = &escaped;
+                }
+                Unencodable::Widen if v.chars().all(|c| c <= '\u{FFFF}') => unit = 0x02,
+                Unencodable::Widen => unit = 0x04,
+            }
+        }
+        match unit {
+            0x01 => {
+                self.series(record_type, 0x01, v.len(), v.as_bytes())?; // 1-byte characters, ASCII
+                return Ok(());
+            }
+            0x02 => {
+                self.series(record_type, 0x02, v.chars().count(), &[])?; // UCS-2
+                for c in v.chars() {
+                    self.output.extend_from_slice(&(c as u16).to_le_bytes());
+                }
+            }
+            _ => {
+                self.series(record_type, 0x04, v.chars().count(), &[])?; // 4-byte characters, UCS-4
+                for c in v.chars() {
+                    self.output.extend_from_slice(&(c as u32).to_le_bytes());
+                }
+            }
+        }
+        self.metrics.strings_transcoded += 1;
+        self.pad();
         Ok(())
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<()> {
-        let mut header = Vec::from(types::BYTES.to_le_bytes());
-        let len = v.len() as i32;
-        header[1] = 0x01; // unit
-        let mut encoded = Vec::from(v);
-        let padding = ( (4 - (len % 4)) % 4 ) as usize;
-        header.append(&mut Vec::from([0x00; 4])); // head position
-        header.append(&mut Vec::from(len.to_le_bytes()));
-
-        self.output.append(&mut header);
-        
-        self.output.append(&mut encoded);
-        
-        let mut p = Vec::from([0x00; 4]);
-        p.resize(padding, 0x00);
-        self.output.append(&mut p);
-        Ok(())
+        if std::mem::take(&mut self.raw_bytes) {
+            let record_type = v.get(..4).map_or(0, |t| i32::from_le_bytes([t[0], t[1], t[2], t[3]]));
+            self.record(record_type);
+            self.output.extend_from_slice(v);
+            return Ok(());
+        }
+        self.series(types::BYTES, 0x01, v.len(), v)
     }
 
     fn serialize_none(self) -> Result<()> {
-        self.output.append(&mut Vec::from(types::NONE.to_le_bytes()));
+        self.record(types::NONE);
+        self.output.extend_from_slice(&types::NONE.to_le_bytes());
         Ok(())
     }
 
@@ -237,7 +1187,14 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(self)
+        if !self.options.wrap_some {
+            return value.serialize(self);
+        }
+        self.any_block_header(1, types::PAREN);
+        self.enter();
+        value.serialize(&mut *self)?;
+        self.leave();
+        Ok(())
     }
 
     fn serialize_unit(self) -> Result<()> {
@@ -251,48 +1208,101 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     fn serialize_unit_variant(
         self,
         _name: &'static str,
-        _variant_index: u32,
+        variant_index: u32,
         variant: &'static str,
     ) -> Result<()> {
         self.variant_header(false);
-        self.serialize_str(variant)
+        self.variant(variant_index, variant)?;
+        self.leave();
+        Ok(())
     }
 
     fn serialize_newtype_struct<T>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(self)
+        match name {
+            adapters::ISSUE => self.str_record = StrRecord::Word(types::ISSUE),
+            adapters::FILE => self.str_record = StrRecord::Series(types::FILE),
+            adapters::VECTOR | adapters::IP | adapters::GEOMETRY | adapters::TIME | adapters::DATE
+            | adapters::RAW_RECORD =>
+                self.raw_bytes = true,
+            // keeps `set-word!` if used as a key of a `SetWordKeys` map
+            adapters::WORD => if self.str_record == StrRecord::String {
+                self.str_record = StrRecord::Word(types::WORD);
+            },
+            adapters::SET_WORD_KEYS => self.word_keys = true,
+            adapters::POSITIONAL => self.positional = true,
+            adapters::RAW_CHUNKS => {
+                self.record(types::BYTES);
+                self.raw_chunks = true;
+            }
+            adapters::SERIES => self.series_head = true,
+            adapters::UNIT_1 => self.str_unit = Some(0x01),
+            adapters::UNIT_2 => self.str_unit = Some(0x02),
+            adapters::UNIT_4 => self.str_unit = Some(0x04),
+            _ => return value.serialize(self),
+        }
+        let result = value.serialize(&mut *self);
+        self.str_record = StrRecord::String;
+        self.raw_bytes = false;
+        self.word_keys = false;
+        self.positional = false;
+        self.raw_chunks = false;
+        self.series_head = false;
+        self.str_unit = None;
+        result
     }
 
     fn serialize_newtype_variant<T>(
         self,
-        _name: &'static str,
-        _variant_index: u32,
+        name: &'static str,
+        variant_index: u32,
         variant: &'static str,
         value: &T,
     ) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
+        if name == adapters::VALUE {
+            // a `Value` tagged with its record type
+            let record_type = value::kind_type(variant)
+                .map(i32::from)
+                .ok_or_else(|| SerError::Message(message!("Unknown kind of value {}.", variant)))?;
+            match record_type {
+                types::WORD..=types::ISSUE => self.str_record = StrRecord::Word(record_type),
+                types::FILE | types::URL | types::TAG | types::EMAIL =>
+                    self.str_record = StrRecord::Series(record_type),
+                _ => self.block_record = Some(record_type),
+            }
+            let result = value.serialize(&mut *self);
+            self.str_record = StrRecord::String;
+            self.block_record = None;
+            return result;
+        }
         self.variant_header(true);
-        self.serialize_str(variant)?;
-        value.serialize(self)?;
+        self.variant(variant_index, variant)?;
+        value.serialize(&mut *self)?;
+        self.leave();
         Ok(())
     }
 
-    #[allow(unused)]
-    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
-        Ok(self)
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        let empty_as_none = self.options.empty_as_none;
+        let mut compound = self.begin_block();
+        compound.block.empty_as_none = empty_as_none;
+        Ok(compound)
     }
 
-    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
-        self.block_header_with(len);
-        Ok(self)
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        if self.options.tuples_as_paren {
+            self.block_record = Some(types::PAREN);
+        }
+        Ok(self.begin_block())
     }
 
     fn serialize_tuple_struct(
@@ -300,184 +1310,169 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         _name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
-        self.block_header_with(len);
-        Ok(self)
+        self.serialize_tuple(len)
     }
 
     fn serialize_tuple_variant(
         self,
         _name: &'static str,
-        _variant_index: u32,
+        variant_index: u32,
         variant: &'static str,
-        len: usize,
+        _len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        self.variant_header(true);
-        self.serialize_str(variant)?;
-        self.block_header_with(len);
-        Ok(self)
+        self.begin_variant_block(variant_index, variant, false)
     }
 
-    #[allow(unused)]
-    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
-        Ok(self)
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        // a `map!` of a `Value` stays one when empty
+        let empty_as_none = self.options.empty_as_none && self.block_record != Some(types::MAP);
+        // other maps are `map!`s too, so they aren't taken for sequences,
+        // unless `SetWordKeys` asks for a block
+        if !self.word_keys && self.block_record.is_none() {
+            self.block_record = Some(types::MAP);
+        }
+        let mut compound = self.begin_block();
+        compound.block.empty_as_none = empty_as_none;
+        Ok(compound)
     }
 
     fn serialize_struct(
         self,
         _name: &'static str,
-        len: usize,
+        _len: usize,
     ) -> Result<Self::SerializeStruct> {
-        self.block_header_with(len * 2);
-        Ok(self)
+        Ok(self.begin_struct())
     }
 
     fn serialize_struct_variant(
         self,
         _name: &'static str,
-        _variant_index: u32,
+        variant_index: u32,
         variant: &'static str,
-        len: usize,
+        _len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        self.variant_header(true);
-        self.serialize_str(variant)?;
-        self.block_header_with(len * 2);
-        Ok(self)
+        self.begin_variant_block(variant_index, variant, true)
     }
 
 }
 
 
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
+impl<B: Output> ser::SerializeSeq for Compound<'_, B> {
     type Ok = ();
-    type Error = Error;
+    type Error = SerError;
 
     fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        self.elements(value)?;
-        self.length += 1;
-        Ok(())
+        self.element(value)
     }
 
     fn end(self) -> Result<()> {
-        self.prepend_block_header();
-        Ok(())
+        self.end_block()
     }
 }
 
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
+impl<B: Output> ser::SerializeTuple for Compound<'_, B> {
     type Ok = ();
-    type Error = Error;
+    type Error = SerError;
 
     fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        self.elements(value)?;
-        Ok(())
+        self.element(value)
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.end_block()
     }
 }
 
-impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+impl<B: Output> ser::SerializeTupleStruct for Compound<'_, B> {
     type Ok = ();
-    type Error = Error;
+    type Error = SerError;
 
     fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        self.elements(value)?;
-        Ok(())
+        self.element(value)
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.end_block()
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+impl<B: Output> ser::SerializeTupleVariant for Compound<'_, B> {
     type Ok = ();
-    type Error = Error;
+    type Error = SerError;
 
     fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        self.elements(value)?;
-        Ok(())
+        self.element(value)
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.end_block()
     }
 }
 
-impl<'a> ser::SerializeMap for &'a mut Serializer {
+impl<B: Output> ser::SerializeMap for Compound<'_, B> {
     type Ok = ();
-    type Error = Error;
+    type Error = SerError;
 
     fn serialize_key<T>(&mut self, key: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        self.elements(key)?;
-        self.length += 1;
-        Ok(())
+        self.key(key)
     }
 
     fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        self.elements(value)?;
-        self.length += 1;
-        Ok(())
+        self.value(value)
     }
 
     fn end(self) -> Result<()> {
-        self.prepend_block_header();
-        Ok(())
+        self.end_block()
     }
 }
 
-impl<'a> ser::SerializeStruct for &'a mut Serializer {
+impl<B: Output> ser::SerializeStruct for Compound<'_, B> {
     type Ok = ();
-    type Error = Error;
+    type Error = SerError;
 
     fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        self.elements(key)?;
-        self.elements(value)?;
-        Ok(())
+        self.field(key, value)
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.end_block()
     }
 }
 
-impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
+impl<B: Output> ser::SerializeStructVariant for Compound<'_, B> {
     type Ok = ();
-    type Error = Error;
+    type Error = SerError;
 
     fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        self.elements(key)?;
-        self.elements(value)?;
-        Ok(())
+        self.field(key, value)
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.end_block()
     }
 }
 
@@ -492,13 +1487,15 @@ Redbin values can be generated in Red:
 , 0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x0B, 0x00, 0x00, 0x00, 0x37, 0x00, 0x00, 0x00
 */
 
-#[cfg(test)]
+#[cfg(all(test, feature = "ser", feature = "de"))]
 mod tests {
-    use super::to_bytes;
-    use serde_derive::Serialize;
+    use super::{to_bytes, to_bytes_with, to_bytes_with_metrics, SerializerMetrics, SerializerOptions};
+    use crate::from_bytes;
+    use serde_derive::{Deserialize, Serialize};
     use serde_bytes::ByteBuf;
     
     #[test]
+    #[allow(clippy::type_complexity)]
     fn test_seq() {
         let i: (i8, i16, u32, u64, &[u8], bool, f64, f32, &str, &str, &str, char, char, ByteBuf) = (-2i8, 299i16, 66666u32, 18_446_744_073_709_551_614u64, &[5, 6], true, 122234.23425, 12.5, "aa", "ą", "💖", 'a', '💖', ByteBuf::from([0xCA, 0xFE]));
         
@@ -577,10 +1574,10 @@ mod tests {
         test_map.insert(String::from("a"), 12.5);
         test_map.insert(String::from("b"), 100.1);
 
-        // rust-redbin-helper ["a" 12.5 "b" 100.1]
+        // rust-redbin-helper #("a" 12.5 "b" 100.1)
         assert_eq!(to_bytes(&test_map).unwrap(),
-            &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x44, 0x00, 0x00, 0x00,
-            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
+            &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00,
+            0x28, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
                 0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x61, 0x00, 0x00, 0x00, 
                 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x29, 0x40, 0x00, 0x00, 0x00, 0x00,
                 0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x62, 0x00, 0x00, 0x00, 
@@ -591,13 +1588,15 @@ mod tests {
         struct WhatNot { a: f64, b: String }
         let wtf = WhatNot { a: 12.5, b: String::from("sdf") };
 
-        // rust-redbin-helper ["a" 12.5 "b" "sdf"]
+        // rust-redbin-helper [a: 12.5 b: "sdf"]
         assert_eq!(to_bytes(&wtf).unwrap(),
-            &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x48, 0x00, 0x00, 0x00,
+            &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x04, 0x01, 0x00, 0x00, 0x00, 0x48, 0x00, 0x00, 0x00,
+            0x02, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+            0x61, 0x00, 0x62, 0x00, 0x00, 0x00, 0x00, 0x00,
             0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
-                0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x61, 0x00, 0x00, 0x00, 
+                0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
                 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x29, 0x40, 0x00, 0x00, 0x00, 0x00,
-                0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x62, 0x00, 0x00, 0x00, 
+                0x10, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
                 0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x73, 0x64, 0x66, 0x00]);
 
 
@@ -610,9 +1609,11 @@ mod tests {
         }
         let enum_test_tuple = (E::Unit, E::Newtype(1), E::Tuple(1, 2), E::Struct { a: 1 });
 
-        // rust-redbin-helper [ ("Unit") ("Newtype" 1) ("Tuple" [1 2]) ("Struct" ["a" 1]) ]
+        // rust-redbin-helper [ ("Unit") ("Newtype" 1) ("Tuple" [1 2]) ("Struct" [a: 1]) ]
         assert_eq!(to_bytes(&enum_test_tuple).unwrap(),
-            &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0xD0, 0x00, 0x00, 0x00,
+            &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x04, 0x01, 0x00, 0x00, 0x00, 0xD0, 0x00, 0x00, 0x00,
+            0x01, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x61, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
             0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
                 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
                     0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x55, 0x6E, 0x69, 0x74,
@@ -627,8 +1628,268 @@ mod tests {
                 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
                     0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x53, 0x74, 0x72, 0x75, 0x63, 0x74, 0x00, 0x00,
                     0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
-                        0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x61, 0x00, 0x00, 0x00,
+                        0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
                         0x0B, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]);
     }
 
+    #[test]
+    fn test_skip_none_fields() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct S {
+            a: Option<u32>,
+            b: Option<u32>,
+            c: Vec<Option<u32>>,
+        }
+        let s = S { a: None, b: Some(1), c: vec![None] };
+        let options = SerializerOptions::new().skip_none_fields(true);
+
+        // rust-redbin-helper [b: 1 c: [#(none)]], with `a` left in the symbol table
+        let expected = &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x04, 0x01, 0x00, 0x00, 0x00, 0x44, 0x00, 0x00, 0x00,
+            0x03, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+            0x04, 0x00, 0x00, 0x00, 0x61, 0x00, 0x62, 0x00, 0x63, 0x00, 0x00, 0x00,
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
+                0x10, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                0x0B, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                0x10, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                    0x03, 0x00, 0x00, 0x00];
+        let bytes = to_bytes_with(&s, options).unwrap();
+        assert_eq!(bytes, expected);
+        assert_eq!(from_bytes::<S>(&bytes).unwrap(), s);
+
+        // without the option, `none` fields are written as usual
+        assert_eq!(from_bytes::<S>(&to_bytes(&s).unwrap()).unwrap(), s);
+    }
+
+    #[test]
+    fn test_empty_as_none() {
+        use crate::{from_bytes_with, DeserializerOptions};
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct S {
+            a: Vec<u32>,
+            b: BTreeMap<String, u32>,
+            c: Vec<u32>,
+        }
+        let s = S { a: vec![], b: BTreeMap::new(), c: vec![1] };
+        let options = SerializerOptions::new().empty_as_none(true);
+
+        // [a: none b: none c: [1]], after the symbol table of `a b c`
+        let bytes = to_bytes_with(&s, options.clone()).unwrap();
+        assert_eq!(&bytes[44..], &[0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00,
+            0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+            0x03, 0x00, 0x00, 0x00,
+            0x10, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+            0x03, 0x00, 0x00, 0x00,
+            0x10, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                0x0B, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00][..]);
+        assert!(from_bytes::<S>(&bytes).is_err());
+        let lenient = DeserializerOptions::new().none_as_empty(true);
+        assert_eq!(s, from_bytes_with::<S>(&bytes, lenient.clone()).unwrap());
+        assert_eq!(s, from_bytes_with::<S>(&to_bytes(&s).unwrap(), lenient).unwrap());
+
+        // left out entirely along with `none` fields
+        let bytes = to_bytes_with(&s, options.skip_none_fields(true)).unwrap();
+        assert_eq!(2, bytes[52]);
+    }
+
+    #[test]
+    fn test_session() {
+        use crate::adapters::{RedString, Word};
+        use crate::{Records, Value};
+
+        let mut session = super::SerializerSession::new();
+        session.write(&1).unwrap();
+        session.write(&crate::parse_red("[a: 'b]").unwrap()).unwrap();
+        // doesn't fit 1-byte characters, and leaves nothing behind
+        let wide = RedString { unit: 1, head: 0, content: "😀".to_string() };
+        assert!(session.write(&(3, "a", wide)).is_err());
+        // nor the name of a word in it
+        let wide = RedString { unit: 1, head: 0, content: "😀".to_string() };
+        assert!(session.write(&(Word("gone".to_string()), wide)).is_err());
+        session.write(&Word("c".to_string())).unwrap();
+        assert_eq!(3, session.len());
+        let document = session.finish().unwrap();
+        assert_eq!(3, document[8]);
+
+        let values: Vec<Value> = Records::new(&document).unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(vec![Value::Integer(1), crate::parse_red("[a: 'b]").unwrap(), Value::Word("c".to_string())], values);
+        assert!(!document.windows(4).any(|name| name == b"gone"));
+        assert_eq!(16, super::SerializerSession::new().finish().unwrap().len());
+    }
+
+    #[test]
+    fn test_to_slice() {
+        use super::to_slice;
+        use crate::error::SerError;
+
+        let value = crate::parse_red(r#"[a: 1 b: "x" c: [d e]]"#).unwrap();
+        let expected = to_bytes(&value).unwrap();
+        let mut region = [0xFF; 256];
+        assert_eq!(expected.len(), to_slice(&value, &mut region, SerializerOptions::new()).unwrap());
+        assert_eq!(expected, region[..expected.len()]);
+
+        let mut region = vec![0x00; expected.len()];
+        assert_eq!(expected.len(), to_slice(&value, &mut region, SerializerOptions::new()).unwrap());
+        assert_eq!(expected, region);
+        let needed = Err(SerError::OutputTooSmall { needed: expected.len() });
+        assert_eq!(needed, to_slice(&value, &mut region[..expected.len() - 1], SerializerOptions::new()));
+        assert_eq!(needed, to_slice(&value, &mut region[..4], SerializerOptions::new()));
+
+        // a skipped field written past the end of the region
+        #[derive(Serialize)]
+        struct S {
+            a: Option<i32>,
+            b: String,
+            c: Option<i32>,
+        }
+        let s = S { a: Some(1), b: "x".repeat(20), c: None };
+        let options = SerializerOptions::new().skip_none_fields(true);
+        let expected = to_bytes_with(&s, options.clone()).unwrap();
+        let mut region = vec![0x00; expected.len()];
+        assert_eq!(expected.len(), to_slice(&s, &mut region, options).unwrap());
+        assert_eq!(expected, region);
+    }
+
+    #[test]
+    fn test_tuples_as_paren() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Pair(u8, u8);
+        let value = (Pair(1, 2), vec![3]);
+        let bytes = to_bytes_with(&value, SerializerOptions::new().tuples_as_paren(true)).unwrap();
+        // ((1 2) [3])
+        assert_eq!(&bytes[16..], &[0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+            0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+                0x0B, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                0x0B, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                0x0B, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00][..]);
+        assert_eq!(value, from_bytes(&bytes).unwrap());
+        assert_eq!(value, from_bytes(&to_bytes(&value).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_variants_by_index() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        enum E {
+            Unit,
+            Newtype(u8),
+            Tuple(u8, u8),
+            Struct { x: u8 },
+        }
+        let options = SerializerOptions::new().variants_by_index(true);
+        // (0)
+        assert_eq!(&to_bytes_with(&E::Unit, options.clone()).unwrap()[16..], &[
+            0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+                0x0B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00][..]);
+        for value in [E::Unit, E::Newtype(1), E::Tuple(2, 3), E::Struct { x: 4 }] {
+            let bytes = to_bytes_with(&value, options.clone()).unwrap();
+            assert!(bytes.len() < to_bytes(&value).unwrap().len());
+            assert_eq!(value, from_bytes(&bytes).unwrap());
+        }
+        let bytes = to_bytes_with(&E::Struct { x: 4 }, options).unwrap();
+        // after the symbol table of `x` and the paren header
+        assert_eq!(3, bytes[36 + 12 + 4]);
+    }
+
+    #[test]
+    fn test_variant_case() {
+        use crate::{DeserializerOptions, VariantCase};
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        enum E {
+            UnitStruct,
+            HTTPServer(u8),
+            Ipv4Addr { x: u8 },
+            #[serde(rename = "snake_case")]
+            SnakeCase,
+        }
+        assert_eq!(["unit-struct", "http-server", "ipv4-addr", "snake-case"],
+            ["UnitStruct", "HTTPServer", "Ipv4Addr", "snake_case"].map(|name| VariantCase::Kebab.apply(name)));
+        let values = vec![E::UnitStruct, E::HTTPServer(1), E::Ipv4Addr { x: 2 }, E::SnakeCase];
+
+        for case in [VariantCase::Lowercase, VariantCase::Kebab] {
+            let bytes = to_bytes_with(&values, SerializerOptions::new().variant_case(case)).unwrap();
+            let text = crate::from_bytes::<crate::Value>(&bytes).unwrap().to_string();
+            let expected = match case {
+                VariantCase::Kebab => r#"[("unit-struct") ("http-server" 1) ("ipv4-addr" [x: 2]) ("snake-case")]"#,
+                _ => r#"[("unitstruct") ("httpserver" 1) ("ipv4addr" [x: 2]) ("snake_case")]"#,
+            };
+            assert_eq!(expected, text);
+            let options = DeserializerOptions::new().variant_case(case);
+            assert_eq!(values, crate::from_bytes_with::<Vec<E>>(&bytes, options).unwrap());
+        }
+        let bytes = to_bytes_with(&values, SerializerOptions::new().variant_case(VariantCase::Kebab)).unwrap();
+        assert!(from_bytes::<Vec<E>>(&bytes).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_unencodable() {
+        use super::{StringEncoding, Unencodable};
+        let ascii = SerializerOptions::new().string_encoding(StringEncoding::Ascii);
+        assert_eq!(Err(crate::SerError::Message(message!("String doesn't fit {}-byte characters.", 1))),
+            to_bytes_with(&"zł", ascii.clone()));
+        // "z^(142)"
+        let bytes = to_bytes_with(&"zł", ascii.clone().unencodable(Unencodable::Escape)).unwrap();
+        assert_eq!(&bytes[16..], &[0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00,
+            0x7A, 0x5E, 0x28, 0x31, 0x34, 0x32, 0x29, 0x00][..]);
+        let bytes = to_bytes_with(&"zł", ascii.clone().unencodable(Unencodable::Widen)).unwrap();
+        assert_eq!(&bytes[16..], &[0x07, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+            0x7A, 0x00, 0x42, 0x01][..]);
+        assert_eq!("zł", from_bytes::<String>(&bytes).unwrap());
+        let bytes = to_bytes_with(&"z😀", ascii.clone().unencodable(Unencodable::Widen)).unwrap();
+        assert_eq!(0x04, bytes[17]);
+        // Latin-1 characters beyond ASCII round trip, widened
+        assert!(to_bytes_with(&"café", ascii.clone()).is_err());
+        let bytes = to_bytes_with(&"café", ascii.clone().unencodable(Unencodable::Widen)).unwrap();
+        assert_eq!((0x02, "café"), (bytes[17], from_bytes::<String>(&bytes).unwrap().as_str()));
+        assert_eq!(Ok(crate::Value::String("café".to_string())), from_bytes(&bytes));
+        // escapes are read back as they are, as Red reads them too
+        let bytes = to_bytes_with(&"café", ascii.clone().unencodable(Unencodable::Escape)).unwrap();
+        assert_eq!("caf^(E9)", from_bytes::<String>(&bytes).unwrap());
+        assert_eq!(Ok(crate::Value::String("caf^(E9)".to_string())), from_bytes(&bytes));
+        assert_eq!(Err(crate::SerError::Message(message!("String with a ^ can't be escaped unambiguously."))),
+            to_bytes_with(&"caf^(E9) é", ascii.clone().unencodable(Unencodable::Escape)));
+        assert!(to_bytes_with(&"a^b", ascii.clone().unencodable(Unencodable::Escape)).is_ok());
+        // fitting text is left alone
+        assert_eq!(to_bytes(&"abc").unwrap(), to_bytes_with(&"abc", ascii.unencodable(Unencodable::Escape)).unwrap());
+    }
+
+    #[test]
+    fn test_large_integers_as_string() {
+        let value = (-8_589_934_592i64, 3_000_000_000u32, u64::MAX, 5u64);
+        assert!(to_bytes(&value).is_err());
+        let bytes = to_bytes_with(&value, SerializerOptions::new().large_integers_as_string(true)).unwrap();
+        // ["-8589934592" "3000000000" "18446744073709551615" 5]
+        assert_eq!(&bytes[16..40], &[0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
+            0x07, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0B, 0x00, 0x00, 0x00][..]);
+        assert_eq!(&bytes[bytes.len() - 8..], &[0x0B, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00][..]);
+        assert_eq!(value, from_bytes(&bytes).unwrap());
+        // u64 keeps reading back from its default binary! form
+        assert_eq!(u64::MAX, from_bytes::<u64>(&to_bytes(&u64::MAX).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_metrics() {
+        #[derive(Serialize)]
+        enum E {
+            Tuple(u32, u32),
+        }
+        #[derive(Serialize)]
+        struct S {
+            name: String,
+            e: E,
+        }
+        let s = S { name: "żółw".to_string(), e: E::Tuple(1, 2) };
+        let (bytes, metrics) = to_bytes_with_metrics(&s, SerializerOptions::new()).unwrap();
+        // [name: "żółw" e: ("Tuple" [1 2])], after the symbol table of `name e`
+        assert_eq!(metrics, SerializerMetrics {
+            bytes_written: bytes.len() - 16 - 24,
+            records: 9,
+            max_depth: 3,
+            strings_transcoded: 1,
+        });
+    }
 }
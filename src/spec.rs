@@ -0,0 +1,368 @@
+//! Red source describing the shape of a Rust struct, for scaffolding
+//! matching objects on the Red side.
+//!
+//! ```
+//! # use redbin::spec::object_spec;
+//! # use serde_derive::Serialize;
+//! # fn main() -> redbin::Result<()> {
+//! #[derive(Serialize, Default)]
+//! struct Server {
+//!     name: String,
+//!     port: u16,
+//!     tags: Vec<String>,
+//!     owner: Option<String>,
+//! }
+//! assert_eq!(object_spec(&Server::default())?,
+//!     "make object! [\n    name: \"\"\n    port: 0\n    tags: []\n    owner: none\n]");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::SerError;
+use serde::ser::{self, Serialize};
+
+type Result<T> = std::result::Result<T, SerError>;
+
+/// Red source making an object with the fields of `value`, a struct, each
+/// set to an empty value of its type: `none` for options and enums, `""`,
+/// `0`, `[]` and so on. Nested structs become nested objects, unless inside
+/// a collection, which is left empty. Serialize a `Default` instance
+/// rather than one with data, as `None` fields show as `none` whatever the
+/// type inside.
+pub fn object_spec<T: ?Sized + Serialize>(value: &T) -> Result<String> {
+    let mut out = String::new();
+    value.serialize(Spec { out: &mut out, depth: 0, root: true })?;
+    Ok(out)
+}
+
+/// Writes the empty value for what it's given, at `depth` objects deep.
+struct Spec<'a> {
+    out: &'a mut String,
+    depth: usize,
+    /// only a struct can be given first
+    root: bool,
+}
+
+impl<'a> Spec<'a> {
+    fn write(self, text: &str) -> Result<()> {
+        if self.root {
+            return Err(SerError::Message(message!("object spec needs a struct")));
+        }
+        self.out.push_str(text);
+        Ok(())
+    }
+
+    fn empty(self, text: &'static str) -> Result<Empty> {
+        self.write(text)?;
+        Ok(Empty)
+    }
+}
+
+impl<'a> ser::Serializer for Spec<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    type SerializeSeq = Empty;
+    type SerializeTuple = Empty;
+    type SerializeTupleStruct = Empty;
+    type SerializeTupleVariant = Empty;
+    type SerializeMap = Empty;
+    type SerializeStruct = Object<'a>;
+    type SerializeStructVariant = Empty;
+
+    fn serialize_bool(self, _v: bool) -> Result<()> {
+        self.write("false")
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<()> {
+        self.write("0")
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<()> {
+        self.write("0")
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<()> {
+        self.write("0")
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<()> {
+        self.write("0")
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<()> {
+        self.write("0")
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<()> {
+        self.write("0")
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<()> {
+        self.write("0")
+    }
+
+    // written as an 8-byte `binary!`
+    fn serialize_u64(self, _v: u64) -> Result<()> {
+        self.write("#{}")
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<()> {
+        self.write("0.0")
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<()> {
+        self.write("0.0")
+    }
+
+    fn serialize_char(self, _v: char) -> Result<()> {
+        self.write("#\"^@\"")
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<()> {
+        self.write("\"\"")
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
+        self.write("#{}")
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        self.write("none")
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        self.write("none")
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        self.write("none")
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, _variant: &'static str) -> Result<()> {
+        self.write("none")
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        self.write("none")
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Empty> {
+        self.empty("[]")
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Empty> {
+        self.empty("[]")
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Empty> {
+        self.empty("[]")
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Empty> {
+        self.empty("none")
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Empty> {
+        self.empty("[]")
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Object<'a>> {
+        self.out.push_str("make object! [");
+        Ok(Object { out: self.out, depth: self.depth, fields: 0 })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Empty> {
+        self.empty("none")
+    }
+}
+
+/// A collection or enum variant, written whole when started; whatever is
+/// in it is skipped.
+struct Empty;
+
+impl ser::SerializeSeq for Empty {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, _value: &T) -> Result<()> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for Empty {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, _value: &T) -> Result<()> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for Empty {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _value: &T) -> Result<()> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleVariant for Empty {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _value: &T) -> Result<()> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for Empty {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, _key: &T) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, _value: &T) -> Result<()> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for Empty {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, _key: &'static str, _value: &T) -> Result<()> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A struct, written as `make object!` with a line per field.
+struct Object<'a> {
+    out: &'a mut String,
+    depth: usize,
+    fields: usize,
+}
+
+impl<'a> Object<'a> {
+    fn indent(&mut self, depth: usize) {
+        self.out.push('\n');
+        for _ in 0..depth {
+            self.out.push_str("    ");
+        }
+    }
+}
+
+impl<'a> ser::SerializeStruct for Object<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        self.indent(self.depth + 1);
+        self.out.push_str(key);
+        self.out.push_str(": ");
+        self.fields += 1;
+        value.serialize(Spec { out: self.out, depth: self.depth + 1, root: false })
+    }
+
+    fn end(mut self) -> Result<()> {
+        if self.fields > 0 {
+            self.indent(self.depth);
+        }
+        self.out.push(']');
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::object_spec;
+    use serde_derive::Serialize;
+
+    #[test]
+    fn test_object_spec() {
+        #[derive(Serialize, Default)]
+        struct Point {
+            x: f64,
+            y: f64,
+        }
+        #[derive(Serialize, Default)]
+        struct Empty {}
+        #[derive(Serialize, Default)]
+        struct Shape {
+            name: String,
+            sides: u8,
+            closed: bool,
+            origin: Point,
+            points: Vec<Point>,
+            label: Option<String>,
+            extra: Empty,
+        }
+
+        assert_eq!(object_spec(&Shape::default()).unwrap(), "make object! [
+    name: \"\"
+    sides: 0
+    closed: false
+    origin: make object! [
+        x: 0.0
+        y: 0.0
+    ]
+    points: []
+    label: none
+    extra: make object! []
+]");
+        assert!(object_spec(&vec![1, 2]).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+//! Moving big `binary!` payloads, like embedded files, between Redbin and
+//! IO streams without holding them in one more buffer of their own.
+//!
+//! Redbin declares sizes as 32-bit signed integers, so no series nor whole
+//! payload can exceed 2 GiB; going over fails with `SerError::TooLarge`
+//! instead of writing a corrupt document.
+
+use crate::adapters::RAW_CHUNKS;
+use crate::types;
+use serde::de::{self, DeserializeSeed, Deserializer, Visitor};
+use serde::ser::{self, Serialize, SerializeSeq, Serializer};
+use serde_bytes::Bytes;
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{Read, Write};
+
+/// Size of the pieces a payload is written in.
+const CHUNK: usize = 64 * 1024;
+
+/// Seed writing the content of a `binary!` record to `W` in chunks,
+/// producing the number of bytes written:
+///
+/// ```no_run
+/// # use redbin::stream::WriteBinary;
+/// # use std::fs::File;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let document = redbin::to_bytes(&serde_bytes::Bytes::new(b"payload"))?;
+/// let mut file = File::create("attachment.bin")?;
+/// let written = redbin::from_bytes_seed(&document, WriteBinary(&mut file), Default::default())?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Being a `DeserializeSeed`, it can also be used for a `binary!` nested in
+/// a block, from a hand-written `Visitor`.
+pub struct WriteBinary<'w, W>(pub &'w mut W);
+
+impl<'de, W: Write> DeserializeSeed<'de> for WriteBinary<'_, W> {
+    type Value = u64;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<u64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_bytes(self)
+    }
+}
+
+impl<'de, W: Write> Visitor<'de> for WriteBinary<'_, W> {
+    type Value = u64;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a binary! record")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<u64, E>
+    where
+        E: de::Error,
+    {
+        for chunk in v.chunks(CHUNK) {
+            self.0.write_all(chunk).map_err(E::custom)?;
+        }
+        Ok(v.len() as u64)
+    }
+}
+
+/// Serializes `len` bytes read from `R` as a `binary!` record, reading and
+/// writing them a chunk at a time. The reader is used up by serializing, so
+/// this can be serialized only once.
+///
+/// Other formats see the record pre-encoded, as a sequence of byte chunks.
+pub struct ReadBinary<R> {
+    reader: RefCell<R>,
+    len: u64,
+}
+
+impl<R: Read> ReadBinary<R> {
+    pub fn new(reader: R, len: u64) -> Self {
+        ReadBinary { reader: RefCell::new(reader), len }
+    }
+}
+
+impl<R: Read> Serialize for ReadBinary<R> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(RAW_CHUNKS, &Chunks(self))
+    }
+}
+
+struct Chunks<'a, R>(&'a ReadBinary<R>);
+
+impl<R: Read> Serialize for Chunks<'_, R> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let len = self.0.len;
+        let length = i32::try_from(len)
+            .map_err(|_| ser::Error::custom("binary! longer than 32-bit signed integer! limit"))?;
+        let mut reader = self.0.reader.borrow_mut();
+        let mut seq = serializer.serialize_seq(None)?;
+
+        let mut header = [0x00; 12];
+        header[..4].copy_from_slice(&types::BYTES.to_le_bytes());
+        header[1] = 0x01; // unit
+        header[8..].copy_from_slice(&length.to_le_bytes());
+        seq.serialize_element(Bytes::new(&header))?;
+
+        let mut buffer = vec![0x00; CHUNK.min(len as usize)];
+        let mut left = len;
+        while left > 0 {
+            let n = buffer.len().min(left as usize);
+            let n = reader.read(&mut buffer[..n]).map_err(ser::Error::custom)?;
+            if n == 0 {
+                return Err(ser::Error::custom(format!("binary source ended after {} of {} bytes", len - left, len)));
+            }
+            seq.serialize_element(Bytes::new(&buffer[..n]))?;
+            left -= n as u64;
+        }
+
+        let padding = (4 - len % 4) % 4;
+        if padding > 0 {
+            seq.serialize_element(Bytes::new(&[0x00; 3][..padding as usize]))?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use super::{ReadBinary, WriteBinary};
+    use crate::{from_bytes, from_bytes_seed, to_bytes, DeserializerOptions};
+    use serde_bytes::ByteBuf;
+    use serde_derive::{Deserialize, Serialize};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_write_binary() {
+        let payload: Vec<u8> = (0..200_000_u32).map(|i| i as u8).collect();
+        let document = to_bytes(&ByteBuf::from(payload.clone())).unwrap();
+
+        let mut sink = Vec::new();
+        let written = from_bytes_seed(&document, WriteBinary(&mut sink), DeserializerOptions::new()).unwrap();
+        assert_eq!(payload.len() as u64, written);
+        assert_eq!(payload, sink);
+
+        let document = to_bytes(&1).unwrap();
+        assert!(from_bytes_seed(&document, WriteBinary(&mut sink), DeserializerOptions::new()).is_err());
+    }
+
+    #[test]
+    fn test_read_binary() {
+        let payload: Vec<u8> = (0..100_001_u32).map(|i| i as u8).collect();
+        assert_eq!(to_bytes(&ByteBuf::from(payload.clone())).unwrap(),
+            to_bytes(&ReadBinary::new(Cursor::new(&payload), payload.len() as u64)).unwrap());
+
+        #[derive(Serialize)]
+        struct Upload<R: std::io::Read> {
+            name: String,
+            content: ReadBinary<R>,
+        }
+        #[derive(Deserialize)]
+        struct Download {
+            name: String,
+            content: ByteBuf,
+        }
+        let upload = Upload { name: String::from("a.txt"), content: ReadBinary::new(&b"abcde"[..], 5) };
+        let download: Download = from_bytes(&to_bytes(&upload).unwrap()).unwrap();
+        assert_eq!(("a.txt", &b"abcde"[..]), (download.name.as_str(), &download.content[..]));
+
+        let short = ReadBinary::new(&b"abc"[..], 5);
+        assert!(to_bytes(&short).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+//! Helpers for checking in tests that types stay Redbin-compatible.
+//!
+//! ```
+//! # use serde_derive::{Deserialize, Serialize};
+//! # #[derive(Serialize, Deserialize, Default, PartialEq, Debug)]
+//! # struct Config { name: String, port: u16 }
+//! # let saved_by_red = redbin::to_bytes(&Config::default()).unwrap();
+//! redbin::testing::assert_roundtrip(&Config::default());
+//! redbin::testing::assert_golden(&Config::default(), &saved_by_red);
+//! ```
+//!
+//! With the `red-harness` feature, `red` checks types against a real Red.
+
+#[cfg(feature = "red-harness")]
+pub mod red;
+
+use crate::{from_bytes, to_bytes};
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std::fmt::{Debug, Write};
+
+/// Bytes shown on each line of a hex dump.
+const ROW: usize = 16;
+
+/// Rows of the hex dump shown before and after the first difference.
+const CONTEXT: usize = 2;
+
+/// Asserts that `value` reads back equal to itself, and that it is written
+/// the same way again after that.
+#[track_caller]
+pub fn assert_roundtrip<T>(value: &T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let bytes = to_bytes(value).unwrap_or_else(|e| panic!("failed to serialize {:?}: {}", value, e));
+    let decoded: T = from_bytes(&bytes).unwrap_or_else(|e| panic!("failed to deserialize {:?}: {}", value, e));
+    assert_eq!(value, &decoded, "value changed in a round trip");
+    assert_bytes_eq(&to_bytes(&decoded).expect("failed to serialize again"), &bytes);
+}
+
+/// Asserts that `value` is written as `expected`, e.g. a file saved by Red.
+#[track_caller]
+pub fn assert_golden<T>(value: &T, expected: &[u8])
+where
+    T: Serialize + Debug,
+{
+    let bytes = to_bytes(value).unwrap_or_else(|e| panic!("failed to serialize {:?}: {}", value, e));
+    assert_bytes_eq(&bytes, expected);
+}
+
+/// Asserts that two documents are the same, showing where they differ.
+#[track_caller]
+pub fn assert_bytes_eq(actual: &[u8], expected: &[u8]) {
+    if let Some(diff) = diff_bytes(actual, expected) {
+        panic!("{}", diff);
+    }
+}
+
+/// Describes the first difference of two documents, with hex dumps of both
+/// around it, or `None` if they're equal.
+pub fn diff_bytes(actual: &[u8], expected: &[u8]) -> Option<String> {
+    let offset = match actual.iter().zip(expected).position(|(a, e)| a != e) {
+        Some(offset) => offset,
+        None if actual.len() == expected.len() => return None,
+        None => actual.len().min(expected.len()),
+    };
+    let mut diff = format!("bytes differ at offset {:#06X} (length {:#06X}, expected {:#06X})\n",
+        offset, actual.len(), expected.len());
+    let first_row = (offset / ROW).saturating_sub(CONTEXT);
+    let rows = first_row..offset / ROW + CONTEXT + 1;
+    diff.push_str("actual:\n");
+    hex_dump(&mut diff, actual, rows.clone(), offset);
+    diff.push_str("expected:\n");
+    hex_dump(&mut diff, expected, rows, offset);
+    Some(diff)
+}
+
+/// Writes `rows` of `bytes`, marking the byte at `mark` with brackets.
+fn hex_dump(out: &mut String, bytes: &[u8], rows: std::ops::Range<usize>, mark: usize) {
+    for row in rows {
+        let start = row * ROW;
+        if start >= bytes.len() && start > mark {
+            break;
+        }
+        let _ = write!(out, "  {:06X} ", start);
+        for (i, byte) in bytes.iter().enumerate().skip(start).take(ROW) {
+            if i == mark {
+                let _ = write!(out, "[{:02X}]", byte);
+            } else if i == mark + 1 {
+                let _ = write!(out, "{:02X}", byte);
+            } else {
+                let _ = write!(out, " {:02X}", byte);
+            }
+        }
+        out.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assert_golden, assert_roundtrip, diff_bytes};
+    use serde_derive::{Deserialize, Serialize};
+
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_testing() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct S {
+            a: i32,
+            b: Vec<String>,
+        }
+        assert_roundtrip(&S { a: 1, b: vec!["ż".to_string()] });
+        assert_golden(&55, &[0x52, 0x45, 0x44, 0x42, 0x49, 0x4E, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00,
+            0x0B, 0x00, 0x00, 0x00, 0x37, 0x00, 0x00, 0x00]);
+
+        assert_eq!(None, diff_bytes(&[1, 2], &[1, 2]));
+        let actual: Vec<u8> = (0..40).collect();
+        let mut expected = actual.clone();
+        expected[33] = 0xFF;
+        assert_eq!(diff_bytes(&actual, &expected).unwrap(), "\
+bytes differ at offset 0x0021 (length 0x0028, expected 0x0028)
+actual:
+  000000  00 01 02 03 04 05 06 07 08 09 0A 0B 0C 0D 0E 0F
+  000010  10 11 12 13 14 15 16 17 18 19 1A 1B 1C 1D 1E 1F
+  000020  20[21]22 23 24 25 26 27
+expected:
+  000000  00 01 02 03 04 05 06 07 08 09 0A 0B 0C 0D 0E 0F
+  000010  10 11 12 13 14 15 16 17 18 19 1A 1B 1C 1D 1E 1F
+  000020  20[FF]22 23 24 25 26 27
+");
+        // a missing byte is reported past the end of the shorter document
+        assert!(diff_bytes(&[1, 2], &[1, 2, 3]).unwrap().starts_with("bytes differ at offset 0x0002"));
+    }
+}
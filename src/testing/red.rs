@@ -0,0 +1,124 @@
+//! Conformance checks against a real Red, run when a `red` executable is
+//! available: on the `PATH`, or wherever the `RED` environment variable
+//! points to. Without one, checks are skipped with a note on stderr.
+//!
+//! ```no_run
+//! # use serde_derive::{Deserialize, Serialize};
+//! # #[derive(Serialize, Deserialize, Default, PartialEq, Debug)]
+//! # struct Config { name: String, port: u16 }
+//! redbin::testing::red::assert_red_roundtrip(&Config::default());
+//! ```
+
+use crate::error::{Error, Result};
+use crate::{from_bytes_with, to_bytes, DeserializerOptions};
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std::ffi::{OsStr, OsString};
+use std::fmt::Debug;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Loads a document with `load/as` and saves what it holds back with
+/// `save/as`.
+const SCRIPT: &str = "Red []
+data: load/as read/binary to-red-file system/script/args 'redbin
+write/binary to-red-file system/script/args save/as none :data 'redbin
+";
+
+/// Names of scratch directories, unique within the process.
+static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+/// The `red` executable used.
+fn red() -> OsString {
+    std::env::var_os("RED").unwrap_or_else(|| OsString::from("red"))
+}
+
+/// `document` as Red saves it again after loading it, or `None` if there's
+/// no `red` executable.
+pub fn red_roundtrip(document: &[u8]) -> Option<Result<Vec<u8>>> {
+    run_red(&red(), document)
+}
+
+fn run_red(red: &OsStr, document: &[u8]) -> Option<Result<Vec<u8>>> {
+    let dir = std::env::temp_dir()
+        .join(format!("redbin-{}-{}", std::process::id(), RUNS.fetch_add(1, Ordering::Relaxed)));
+    let result = in_dir(red, &dir, document);
+    let _ = fs::remove_dir_all(&dir);
+    match result {
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => Some(Err(Error::Io(e.to_string()))),
+        Ok(saved) => Some(saved),
+    }
+}
+
+fn in_dir(red: &OsStr, dir: &Path, document: &[u8]) -> std::io::Result<Result<Vec<u8>>> {
+    fs::create_dir_all(dir)?;
+    let (script, data) = (dir.join("roundtrip.red"), dir.join("data.redbin"));
+    fs::write(&script, SCRIPT)?;
+    fs::write(&data, document)?;
+    let output = Command::new(red).arg(&script).arg(&data).current_dir(dir).output()?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Ok(Err(Error::Io(format!("red failed ({}): {}", output.status, message))));
+    }
+    // only a missing `red` tells that checks are skipped
+    Ok(fs::read(&data).map_err(|e| Error::Io(e.to_string())))
+}
+
+/// Asserts that `value` reads back equal to itself after Red loads and
+/// saves it. Red may save a single value inside a block, which is read
+/// like `DeserializerOptions::unwrap_root` does.
+#[track_caller]
+pub fn assert_red_roundtrip<T>(value: &T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let bytes = to_bytes(value).unwrap_or_else(|e| panic!("failed to serialize {:?}: {}", value, e));
+    let saved = match red_roundtrip(&bytes) {
+        Some(saved) => saved.unwrap_or_else(|e| panic!("Red failed on {:?}: {}", value, e)),
+        None => {
+            eprintln!("no red executable found, skipped a round trip of {:?}", value);
+            return;
+        }
+    };
+    let options = DeserializerOptions::new().unwrap_root(true);
+    let decoded: T = from_bytes_with(&saved, options)
+        .unwrap_or_else(|e| panic!("failed to deserialize {:?} saved by Red: {}", value, e));
+    assert_eq!(value, &decoded, "value changed in a round trip through Red");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assert_red_roundtrip, run_red};
+    use serde_derive::{Deserialize, Serialize};
+    use std::ffi::OsStr;
+
+    #[test]
+    fn test_red_roundtrip() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct S {
+            a: i32,
+            b: Vec<String>,
+        }
+        assert!(run_red(OsStr::new("/nonexistent/red"), &[]).is_none());
+        // checked only where Red is installed
+        assert_red_roundtrip(&S { a: 1, b: vec!["ż".to_string()] });
+        assert_red_roundtrip(&vec![1.5, -2.0]);
+    }
+
+    #[test]
+    fn test_red_escapes() {
+        use crate::{from_bytes_with, to_bytes_with, DeserializerOptions, SerializerOptions, StringEncoding, Unencodable};
+        let options = SerializerOptions::new().string_encoding(StringEncoding::Ascii).unencodable(Unencodable::Escape);
+        let bytes = to_bytes_with(&"café", options).unwrap();
+        // checked only where Red is installed: Red keeps the escape as text
+        if let Some(saved) = run_red(&super::red(), &bytes) {
+            let saved = saved.unwrap();
+            let text: String = from_bytes_with(&saved, DeserializerOptions::new().unwrap_root(true)).unwrap();
+            assert_eq!("caf^(E9)", text);
+        }
+    }
+}
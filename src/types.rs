@@ -0,0 +1,145 @@
+//! Record type IDs and encodings shared by the serializer, the readers and
+//! the adapters.
+
+#[cfg(feature = "ser")]
+pub const NONE: i32 = 0x03_i32;
+#[cfg(feature = "ser")]
+pub const LOGIC: i32 = 0x04_i32;
+#[cfg(feature = "ser")]
+pub const BLOCK: i32 = 0x05_i32;
+#[cfg(feature = "ser")]
+pub const PAREN: i32 = 0x06_i32;
+#[cfg(feature = "ser")]
+pub const STRING: i32 = 0x07_i32;
+#[cfg(feature = "ser")]
+pub const FILE: i32 = 0x08_i32;
+#[cfg(feature = "ser")]
+pub const URL: i32 = 0x09_i32;
+#[cfg(feature = "ser")]
+pub const CHAR: i32 = 0x0A_i32;
+pub const INTEGER: i32 = 0x0B_i32;
+pub const FLOAT: i32 = 0x0C_i32;
+#[cfg(feature = "ser")]
+pub const WORD: i32 = 0x0F_i32;
+#[cfg(feature = "ser")]
+pub const SET_WORD: i32 = 0x10_i32;
+#[cfg(feature = "ser")]
+pub const ISSUE: i32 = 0x14_i32;
+pub const VECTOR: i32 = 0x23_i32;
+#[cfg(any(feature = "glam", feature = "nalgebra"))]
+pub const PAIR: i32 = 0x25_i32;
+#[cfg(feature = "ser")]
+pub const MAP: i32 = 0x28_i32;
+pub const TUPLE: i32 = 0x27_i32;
+pub const BYTES: i32 = 0x29_i32;
+pub const TIME: i32 = 0x2B_i32;
+#[cfg(feature = "ser")]
+pub const TAG: i32 = 0x2C_i32;
+#[cfg(feature = "ser")]
+pub const EMAIL: i32 = 0x2D_i32;
+pub const DATE: i32 = 0x2F_i32;
+#[cfg(any(feature = "glam", feature = "nalgebra"))]
+pub const POINT2D: i32 = 0x37_i32;
+#[cfg(any(feature = "glam", feature = "nalgebra"))]
+pub const POINT3D: i32 = 0x38_i32;
+
+/// Record type IDs as the first byte of a record, for matching on input.
+#[cfg(feature = "de")]
+pub(crate) mod byte {
+    pub const NONE: u8 = 0x03;
+    pub const LOGIC: u8 = 0x04;
+    pub const BLOCK: u8 = 0x05;
+    pub const PAREN: u8 = 0x06;
+    pub const STRING: u8 = 0x07;
+    pub const FILE: u8 = 0x08;
+    pub const URL: u8 = 0x09;
+    pub const CHAR: u8 = 0x0A;
+    pub const INTEGER: u8 = 0x0B;
+    pub const FLOAT: u8 = 0x0C;
+    pub const WORD: u8 = 0x0F;
+    pub const SET_WORD: u8 = 0x10;
+    pub const LIT_WORD: u8 = 0x11;
+    pub const GET_WORD: u8 = 0x12;
+    pub const REFINEMENT: u8 = 0x13;
+    pub const ISSUE: u8 = 0x14;
+    pub const PATH: u8 = 0x19;
+    pub const LIT_PATH: u8 = 0x1A;
+    pub const SET_PATH: u8 = 0x1B;
+    pub const GET_PATH: u8 = 0x1C;
+    pub const VECTOR: u8 = 0x23;
+    pub const PAIR: u8 = 0x25;
+    pub const TUPLE: u8 = 0x27;
+    pub const MAP: u8 = 0x28;
+    pub const BINARY: u8 = 0x29;
+    pub const TIME: u8 = 0x2B;
+    pub const TAG: u8 = 0x2C;
+    pub const EMAIL: u8 = 0x2D;
+    pub const DATE: u8 = 0x2F;
+    pub const POINT2D: u8 = 0x37;
+    pub const POINT3D: u8 = 0x38;
+}
+
+/// Size of the document header: magic, version, flags, record count and
+/// payload size.
+#[cfg(any(feature = "ser", feature = "de"))]
+pub(crate) const HEADER_LEN: usize = 16;
+
+/// `float!`-like record, e.g. `float!` or `time!`
+pub(crate) fn float_record(record_type: i32, v: f64) -> [u8; 12] {
+    let mut record = [0x00; 12];
+    let bytes = v.to_le_bytes();
+    record[..4].copy_from_slice(&record_type.to_le_bytes());
+
+    // swap words
+    // Optional padding at the beginning is not added.
+    // Red's "load/as [...] 'redbin" command accepts data without padding.
+    record[4..8].copy_from_slice(&bytes[4..8]);
+    record[8..].copy_from_slice(&bytes[..4]);
+    record
+}
+
+/// How enum variant names are written, see
+/// `SerializerOptions::variant_case`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum VariantCase {
+    /// As in Rust, e.g. `UnitStruct`.
+    #[default]
+    AsIs,
+    /// e.g. `unitstruct`
+    Lowercase,
+    /// Lowercase words joined by hyphens, as in Red, e.g. `unit-struct`.
+    Kebab,
+}
+
+impl VariantCase {
+    /// `name` written in this case. Kebab case splits words where an
+    /// uppercase letter follows a lowercase one or a digit, or starts a
+    /// word after an acronym, e.g. `HttpServer` and `HTTPServer` both
+    /// become `http-server`; underscores turn into hyphens.
+    pub fn apply(self, name: &str) -> std::borrow::Cow<'_, str> {
+        match self {
+            VariantCase::AsIs => name.into(),
+            VariantCase::Lowercase => name.to_lowercase().into(),
+            VariantCase::Kebab => {
+                let chars: Vec<char> = name.chars().collect();
+                let mut out = String::with_capacity(name.len() + 4);
+                for (i, &c) in chars.iter().enumerate() {
+                    if c == '_' {
+                        out.push('-');
+                        continue;
+                    }
+                    if c.is_uppercase() && i > 0 {
+                        let previous = chars[i - 1];
+                        let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+                        if previous.is_lowercase() || previous.is_ascii_digit()
+                            || (previous.is_uppercase() && next_lower) {
+                            out.push('-');
+                        }
+                    }
+                    out.extend(c.to_lowercase());
+                }
+                out.into()
+            }
+        }
+    }
+}
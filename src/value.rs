@@ -0,0 +1,1158 @@
+//! Dynamically typed Redbin values, for documents whose shape isn't known
+//! up front.
+
+use crate::adapters;
+use crate::parse::is_delimiter;
+use indexmap::IndexMap;
+use serde::de::{self, Deserialize, Deserializer, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Any Red value this crate can read and write.
+///
+/// Read from Redbin, words and series other than `string!` keep their
+/// record type, so a `Value` is written back the way it was read. Other
+/// formats see these as externally tagged enums, e.g. `{"set-word": "a"}`.
+///
+/// Values are totally ordered, so they can be map keys and blocks can be
+/// sorted: first by kind, in the order of the variants here, then by
+/// content. Floats compare by value, except that all NaNs are equal to each
+/// other and greater than any number; `0.0` and `-0.0` are equal. Words of
+/// any kind compare case-insensitively like in Red, keeping their spelling,
+/// so `Foo` and `foo` are equal but written back as they were read. Maps
+/// compare entry by entry, in order.
+#[derive(Clone, Debug)]
+pub enum Value {
+    None,
+    Logic(bool),
+    Integer(i32),
+    Float(f64),
+    Char(char),
+    String(String),
+    File(String),
+    Url(String),
+    Tag(String),
+    Email(String),
+    Binary(Vec<u8>),
+    Word(String),
+    SetWord(String),
+    LitWord(String),
+    GetWord(String),
+    Refinement(String),
+    Issue(String),
+    Block(Vec<Value>),
+    Paren(Vec<Value>),
+    Path(Vec<Value>),
+    SetPath(Vec<Value>),
+    GetPath(Vec<Value>),
+    LitPath(Vec<Value>),
+    /// a `map!`, keeping its keys in order
+    Map(IndexMap<Value, Value>),
+}
+
+impl Value {
+    /// Position of the kind of value in the order of kinds.
+    fn rank(&self) -> u8 {
+        match self {
+            Value::None => 0,
+            Value::Logic(_) => 1,
+            Value::Integer(_) => 2,
+            Value::Float(_) => 3,
+            Value::Char(_) => 4,
+            Value::String(_) => 5,
+            Value::File(_) => 6,
+            Value::Url(_) => 7,
+            Value::Tag(_) => 8,
+            Value::Email(_) => 9,
+            Value::Binary(_) => 10,
+            Value::Word(_) => 11,
+            Value::SetWord(_) => 12,
+            Value::LitWord(_) => 13,
+            Value::GetWord(_) => 14,
+            Value::Refinement(_) => 15,
+            Value::Issue(_) => 16,
+            Value::Block(_) => 17,
+            Value::Paren(_) => 18,
+            Value::Path(_) => 19,
+            Value::SetPath(_) => 20,
+            Value::GetPath(_) => 21,
+            Value::LitPath(_) => 22,
+            Value::Map(_) => 23,
+        }
+    }
+}
+
+/// Compares words case-insensitively, as Red does.
+pub(crate) fn cmp_words(a: &str, b: &str) -> Ordering {
+    fold_case(a).cmp(fold_case(b))
+}
+
+/// Hashes a word so that words equal by `cmp_words` hash the same.
+pub(crate) fn hash_word<H: Hasher>(word: &str, state: &mut H) {
+    for c in fold_case(word) {
+        c.hash(state);
+    }
+    0xFF_u8.hash(state);
+}
+
+fn fold_case(word: &str) -> impl Iterator<Item = char> + '_ {
+    word.chars().flat_map(char::to_lowercase)
+}
+
+fn cmp_floats(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => a.partial_cmp(&b).expect("neither is NaN"),
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        use Value::*;
+        match (self, other) {
+            (None, None) => Ordering::Equal,
+            (Logic(a), Logic(b)) => a.cmp(b),
+            (Integer(a), Integer(b)) => a.cmp(b),
+            (Float(a), Float(b)) => cmp_floats(*a, *b),
+            (Char(a), Char(b)) => a.cmp(b),
+            (String(a), String(b)) | (File(a), File(b)) | (Url(a), Url(b)) | (Tag(a), Tag(b))
+                | (Email(a), Email(b)) => a.cmp(b),
+            (Word(a), Word(b)) | (SetWord(a), SetWord(b)) | (LitWord(a), LitWord(b)) | (GetWord(a), GetWord(b))
+                | (Refinement(a), Refinement(b)) | (Issue(a), Issue(b)) => cmp_words(a, b),
+            (Binary(a), Binary(b)) => a.cmp(b),
+            (Block(a), Block(b)) | (Paren(a), Paren(b)) | (Path(a), Path(b)) | (SetPath(a), SetPath(b))
+                | (GetPath(a), GetPath(b)) | (LitPath(a), LitPath(b)) => a.cmp(b),
+            (Map(a), Map(b)) => a.iter().cmp(b.iter()),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value {}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Value::None => {}
+            Value::Logic(b) => b.hash(state),
+            Value::Integer(i) => i.hash(state),
+            // equal floats hash the same
+            Value::Float(x) if x.is_nan() => f64::NAN.to_bits().hash(state),
+            Value::Float(x) if *x == 0.0 => 0_u64.hash(state),
+            Value::Float(x) => x.to_bits().hash(state),
+            Value::Char(c) => c.hash(state),
+            Value::String(s) | Value::File(s) | Value::Url(s) | Value::Tag(s) | Value::Email(s) => s.hash(state),
+            Value::Word(s) | Value::SetWord(s) | Value::LitWord(s) | Value::GetWord(s)
+                | Value::Refinement(s) | Value::Issue(s) => hash_word(s, state),
+            Value::Binary(bytes) => bytes.hash(state),
+            Value::Block(values) | Value::Paren(values) | Value::Path(values) | Value::SetPath(values)
+                | Value::GetPath(values) | Value::LitPath(values) => values.hash(state),
+            Value::Map(map) => {
+                map.len().hash(state);
+                for entry in map {
+                    entry.hash(state);
+                }
+            }
+        }
+    }
+}
+
+/// Names of the tagged kinds of values, with their record types.
+pub(crate) const KINDS: &[(&str, u8)] = &[
+    ("file", 0x08),
+    ("url", 0x09),
+    ("tag", 0x2C),
+    ("email", 0x2D),
+    ("word", 0x0F),
+    ("set-word", 0x10),
+    ("lit-word", 0x11),
+    ("get-word", 0x12),
+    ("refinement", 0x13),
+    ("issue", 0x14),
+    ("paren", 0x06),
+    ("path", 0x19),
+    ("lit-path", 0x1A),
+    ("set-path", 0x1B),
+    ("get-path", 0x1C),
+    ("map", 0x28),
+];
+
+/// Kind name of a record type written as a tagged value.
+#[cfg(feature = "de")]
+pub(crate) fn kind_name(record_type: u8) -> Option<&'static str> {
+    KINDS.iter().find(|(_, t)| *t == record_type).map(|(name, _)| *name)
+}
+
+/// Record type of a tagged value's kind.
+pub(crate) fn kind_type(name: &str) -> Option<u8> {
+    KINDS.iter().find(|(n, _)| *n == name).map(|(_, t)| *t)
+}
+
+impl Value {
+    /// Red type name of the value, e.g. `"issue!"`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::None => "none!",
+            Value::Logic(_) => "logic!",
+            Value::Integer(_) => "integer!",
+            Value::Float(_) => "float!",
+            Value::Char(_) => "char!",
+            Value::String(_) => "string!",
+            Value::File(_) => "file!",
+            Value::Url(_) => "url!",
+            Value::Tag(_) => "tag!",
+            Value::Email(_) => "email!",
+            Value::Binary(_) => "binary!",
+            Value::Word(_) => "word!",
+            Value::SetWord(_) => "set-word!",
+            Value::LitWord(_) => "lit-word!",
+            Value::GetWord(_) => "get-word!",
+            Value::Refinement(_) => "refinement!",
+            Value::Issue(_) => "issue!",
+            Value::Block(_) => "block!",
+            Value::Paren(_) => "paren!",
+            Value::Path(_) => "path!",
+            Value::SetPath(_) => "set-path!",
+            Value::GetPath(_) => "get-path!",
+            Value::LitPath(_) => "lit-path!",
+            Value::Map(_) => "map!",
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        matches!(self, Value::None)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Logic(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            Value::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// The number of a `float!` or an `integer!`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Float(f) => Some(*f),
+            Value::Integer(i) => Some(f64::from(*i)),
+            _ => None,
+        }
+    }
+
+    pub fn as_char(&self) -> Option<char> {
+        match self {
+            Value::Char(c) => Some(*c),
+            _ => None,
+        }
+    }
+
+    /// Text of a string, or of a file, url, tag, email, word or issue.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => self.tagged_text().map(|(_, text)| text),
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Binary(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Values of a block, paren or path.
+    pub fn as_block(&self) -> Option<&[Value]> {
+        match self {
+            Value::Block(values) | Value::Paren(values) | Value::Path(values) | Value::SetPath(values)
+                | Value::GetPath(values) | Value::LitPath(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_block_mut(&mut self) -> Option<&mut Vec<Value>> {
+        match self {
+            Value::Block(values) | Value::Paren(values) | Value::Path(values) | Value::SetPath(values)
+                | Value::GetPath(values) | Value::LitPath(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// A block as a series at its head, for Red's series operations; see
+    /// `RedSeries`.
+    pub fn into_series(self) -> Option<adapters::RedSeries<Vec<Value>>> {
+        match self {
+            Value::Block(values) => Some(adapters::RedSeries::new(values)),
+            _ => None,
+        }
+    }
+
+    /// The value after `key` in a block, or of `key` in a map, like Red's
+    /// `select`. Words of any kind and strings match their text, e.g. `a`
+    /// in `[a: 1 "b" 2]`, words regardless of case.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        if let Value::Map(map) = self {
+            return map.iter().find(|(k, _)| k.is_key(key)).map(|(_, value)| value);
+        }
+        let values = self.as_block()?;
+        let at = values.iter().position(|value| value.is_key(key))?;
+        values.get(at + 1)
+    }
+
+    /// The value at zero-based `index` of a block, paren or path.
+    pub fn get_index(&self, index: usize) -> Option<&Value> {
+        self.as_block()?.get(index)
+    }
+
+    pub fn as_map(&self) -> Option<&IndexMap<Value, Value>> {
+        match self {
+            Value::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    pub fn as_map_mut(&mut self) -> Option<&mut IndexMap<Value, Value>> {
+        match self {
+            Value::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Number of values in a block, paren, path or map, counting the values
+    /// of nested ones too; 0 for other values.
+    pub fn deep_len(&self) -> usize {
+        if let Value::Map(map) = self {
+            return map.iter().map(|(key, value)| 2 + key.deep_len() + value.deep_len()).sum();
+        }
+        self.as_block().map_or(0, |values| values.iter().map(|value| 1 + value.deep_len()).sum())
+    }
+
+    /// Size in bytes of the document `to_bytes` writes for the value with
+    /// default options, header and symbol table included.
+    pub fn encoded_size(&self) -> usize {
+        let mut symbols = BTreeSet::new();
+        let records = self.records_size(&mut symbols);
+        let table = if symbols.is_empty() {
+            0
+        } else {
+            let names: usize = symbols.iter().map(|name| name.len() + 1).sum();
+            8 + 4 * symbols.len() + names.next_multiple_of(8)
+        };
+        16 + table + records
+    }
+
+    /// Size of the records of the value, collecting names of its words.
+    fn records_size<'a>(&'a self, symbols: &mut BTreeSet<&'a str>) -> usize {
+        let series = |unit: usize, length: usize| (12 + unit * length).next_multiple_of(4);
+        match self {
+            Value::None => 4,
+            Value::Logic(_) | Value::Integer(_) | Value::Char(_) => 8,
+            Value::Float(_) => 12,
+            // non-ASCII text is written with 4-byte characters
+            Value::String(s) | Value::File(s) | Value::Url(s) | Value::Tag(s) | Value::Email(s) =>
+                if s.is_ascii() {series(1, s.len())} else {series(4, s.chars().count())},
+            Value::Binary(bytes) => series(1, bytes.len()),
+            Value::Word(s) | Value::SetWord(s) | Value::LitWord(s) | Value::GetWord(s) | Value::Refinement(s)
+                | Value::Issue(s) => {
+                symbols.insert(s);
+                16
+            }
+            Value::Block(values) | Value::Paren(values) | Value::Path(values) | Value::SetPath(values)
+                | Value::GetPath(values) | Value::LitPath(values) =>
+                12 + values.iter().map(|value| value.records_size(symbols)).sum::<usize>(),
+            Value::Map(map) =>
+                8 + map.iter().map(|(key, value)| key.records_size(symbols) + value.records_size(symbols)).sum::<usize>(),
+        }
+    }
+
+    fn is_key(&self, key: &str) -> bool {
+        match self {
+            Value::String(s) => s == key,
+            Value::Word(s) | Value::SetWord(s) | Value::LitWord(s) | Value::GetWord(s)
+                | Value::Refinement(s) | Value::Issue(s) => cmp_words(s, key) == Ordering::Equal,
+            _ => false,
+        }
+    }
+
+    /// Kind name and text of values tagged with their kind.
+    fn tagged_text(&self) -> Option<(&'static str, &str)> {
+        let (kind, text) = match self {
+            Value::File(s) => ("file", s),
+            Value::Url(s) => ("url", s),
+            Value::Tag(s) => ("tag", s),
+            Value::Email(s) => ("email", s),
+            Value::Word(s) => ("word", s),
+            Value::SetWord(s) => ("set-word", s),
+            Value::LitWord(s) => ("lit-word", s),
+            Value::GetWord(s) => ("get-word", s),
+            Value::Refinement(s) => ("refinement", s),
+            Value::Issue(s) => ("issue", s),
+            _ => return None,
+        };
+        Some((kind, text))
+    }
+
+    /// The value of `kind` holding `value`, as tagged in other formats.
+    fn from_tagged(kind: &str, value: Value) -> Option<Value> {
+        match (kind, value) {
+            ("paren", Value::Block(values)) => Some(Value::Paren(values)),
+            ("path", Value::Block(values)) => Some(Value::Path(values)),
+            ("set-path", Value::Block(values)) => Some(Value::SetPath(values)),
+            ("get-path", Value::Block(values)) => Some(Value::GetPath(values)),
+            ("lit-path", Value::Block(values)) => Some(Value::LitPath(values)),
+            ("map", Value::Block(values)) if values.len() % 2 == 0 => {
+                let mut values = values.into_iter();
+                Some(Value::Map(std::iter::from_fn(|| Some((values.next()?, values.next()?))).collect()))
+            }
+            (kind, Value::String(text)) => Value::from_tagged_text(kind, text),
+            _ => None,
+        }
+    }
+
+    fn from_tagged_text(kind: &str, text: String) -> Option<Value> {
+        Some(match kind {
+            "file" => Value::File(text),
+            "url" => Value::Url(text),
+            "tag" => Value::Tag(text),
+            "email" => Value::Email(text),
+            "word" => Value::Word(text),
+            "set-word" => Value::SetWord(text),
+            "lit-word" => Value::LitWord(text),
+            "get-word" => Value::GetWord(text),
+            "refinement" => Value::Refinement(text),
+            "issue" => Value::Issue(text),
+            _ => return None,
+        })
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Logic(b)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(i: i32) -> Self {
+        Value::Integer(i)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(x: f64) -> Self {
+        Value::Float(x)
+    }
+}
+
+impl From<char> for Value {
+    fn from(c: char) -> Self {
+        Value::Char(c)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(values: Vec<Value>) -> Self {
+        Value::Block(values)
+    }
+}
+
+impl From<IndexMap<Value, Value>> for Value {
+    fn from(map: IndexMap<Value, Value>) -> Self {
+        Value::Map(map)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::None, Into::into)
+    }
+}
+
+macro_rules! from_integer {
+    ($($t:ty)*) => {$(
+        impl From<$t> for Value {
+            fn from(i: $t) -> Self {
+                Value::Integer(i32::from(i))
+            }
+        }
+    )*};
+}
+
+from_integer!(i8 u8 i16 u16);
+
+impl From<f32> for Value {
+    fn from(x: f32) -> Self {
+        Value::Float(f64::from(x))
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(bytes: Vec<u8>) -> Self {
+        Value::Binary(bytes)
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(bytes: &[u8]) -> Self {
+        Value::Binary(bytes.to_vec())
+    }
+}
+
+impl From<()> for Value {
+    fn from(_: ()) -> Self {
+        Value::None
+    }
+}
+
+// Comparisons with plain Rust values of the matching kind only: an
+// `integer!` isn't equal to an `f64`, nor a `word!` to a `&str`.
+macro_rules! eq_primitive {
+    ($($t:ty => $variant:ident($v:ident) $compare:expr;)*) => {$(
+        impl PartialEq<$t> for Value {
+            fn eq(&self, other: &$t) -> bool {
+                match self {
+                    Value::$variant($v) => $compare(other),
+                    _ => false,
+                }
+            }
+        }
+
+        impl PartialEq<Value> for $t {
+            fn eq(&self, other: &Value) -> bool {
+                other == self
+            }
+        }
+    )*};
+}
+
+eq_primitive! {
+    bool => Logic(b) |other: &bool| b == other;
+    i32 => Integer(i) |other: &i32| i == other;
+    f64 => Float(x) |other: &f64| x == other;
+    char => Char(c) |other: &char| c == other;
+    str => String(s) |other: &str| s == other;
+    &str => String(s) |other: &&str| s == other;
+    String => String(s) |other: &String| s == other;
+    [u8] => Binary(bytes) |other: &[u8]| bytes[..] == *other;
+}
+
+/// Molds the value as Red source text, which `parse_red` reads back; `none`
+/// and logic values use construction syntax, e.g. `#[none]`.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::None => f.write_str("#[none]"),
+            Value::Logic(b) => write!(f, "#[{}]", b),
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::Float(x) if x.is_nan() => f.write_str("1.#NaN"),
+            Value::Float(x) if x.is_infinite() => f.write_str(if *x > 0.0 {"1.#INF"} else {"-1.#INF"}),
+            Value::Float(x) => write!(f, "{:?}", x),
+            Value::Char(c) => {
+                f.write_str("#\"")?;
+                mold_char(f, *c, '"')?;
+                f.write_str("\"")
+            }
+            Value::String(s) => mold_quoted(f, s),
+            Value::File(s) if s.is_empty() || s.contains(|c: char| is_delimiter(c) || c == '%') => {
+                f.write_str("%")?;
+                mold_quoted(f, s)
+            }
+            Value::File(s) => write!(f, "%{}", s),
+            Value::Url(s) | Value::Email(s) | Value::Word(s) => f.write_str(s),
+            Value::Tag(s) => write!(f, "<{}>", s),
+            Value::Binary(bytes) => {
+                f.write_str("#{")?;
+                for byte in bytes {
+                    write!(f, "{:02X}", byte)?;
+                }
+                f.write_str("}")
+            }
+            Value::SetWord(s) => write!(f, "{}:", s),
+            Value::LitWord(s) => write!(f, "'{}", s),
+            Value::GetWord(s) => write!(f, ":{}", s),
+            Value::Refinement(s) => write!(f, "/{}", s),
+            Value::Issue(s) => write!(f, "#{}", s),
+            Value::Block(values) => mold_values(f, "[", values, " ", "]"),
+            Value::Paren(values) => mold_values(f, "(", values, " ", ")"),
+            Value::Path(values) => mold_values(f, "", values, "/", ""),
+            Value::SetPath(values) => mold_values(f, "", values, "/", ":"),
+            Value::GetPath(values) => mold_values(f, ":", values, "/", ""),
+            Value::LitPath(values) => mold_values(f, "'", values, "/", ""),
+            Value::Map(map) => {
+                f.write_str("#(")?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    write!(f, "{}{} {}", if i > 0 {" "} else {""}, key, value)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+/// Writes `c` as inside a string or char ending with `quote`.
+fn mold_char(f: &mut fmt::Formatter, c: char, quote: char) -> fmt::Result {
+    match c {
+        '\n' => f.write_str("^/"),
+        '\t' => f.write_str("^-"),
+        '^' => f.write_str("^^"),
+        c if c == quote => write!(f, "^{}", c),
+        c if c.is_control() => write!(f, "^({:02X})", c as u32),
+        c => write!(f, "{}", c),
+    }
+}
+
+fn mold_quoted(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        mold_char(f, c, '"')?;
+    }
+    f.write_str("\"")
+}
+
+fn mold_values(f: &mut fmt::Formatter, open: &str, values: &[Value], separator: &str, close: &str) -> fmt::Result {
+    f.write_str(open)?;
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            f.write_str(separator)?;
+        }
+        write!(f, "{}", value)?;
+    }
+    f.write_str(close)
+}
+
+fn variant_index(kind: &str) -> u32 {
+    KINDS.iter().position(|(name, _)| *name == kind).unwrap_or(0) as u32
+}
+
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if let Some((kind, text)) = self.tagged_text() {
+            return serializer.serialize_newtype_variant(adapters::VALUE, variant_index(kind), kind, text);
+        }
+        match self {
+            Value::None => serializer.serialize_unit(),
+            Value::Logic(v) => serializer.serialize_bool(*v),
+            Value::Integer(v) => serializer.serialize_i32(*v),
+            Value::Float(v) => serializer.serialize_f64(*v),
+            Value::Char(v) => serializer.serialize_char(*v),
+            Value::String(v) => serializer.serialize_str(v),
+            Value::Binary(v) => serializer.serialize_bytes(v),
+            Value::Block(v) => serializer.collect_seq(v),
+            Value::Paren(v) => serializer.serialize_newtype_variant(adapters::VALUE, variant_index("paren"), "paren", v),
+            Value::Path(v) => serializer.serialize_newtype_variant(adapters::VALUE, variant_index("path"), "path", v),
+            Value::SetPath(v) => serializer.serialize_newtype_variant(adapters::VALUE, variant_index("set-path"), "set-path", v),
+            Value::GetPath(v) => serializer.serialize_newtype_variant(adapters::VALUE, variant_index("get-path"), "get-path", v),
+            Value::LitPath(v) => serializer.serialize_newtype_variant(adapters::VALUE, variant_index("lit-path"), "lit-path", v),
+            Value::Map(v) => serializer.serialize_newtype_variant(adapters::VALUE, variant_index("map"), "map", v),
+            _ => unreachable!("tagged text values are handled above"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(adapters::VALUE, ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a Red value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Logic(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(i32::try_from(v).map_or(Value::Float(v as f64), Value::Integer))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(i32::try_from(v).map_or(Value::Float(v as f64), Value::Integer))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_char<E>(self, v: char) -> Result<Value, E> {
+        Ok(Value::Char(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::Binary(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::Binary(v))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::None)
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Value::Block(values))
+    }
+
+    // a map is a block of keys followed by their values, except for a single
+    // entry keyed by a kind name, which formats without enums use for tagged
+    // values, e.g. `{"set-word": "a"}` in JavaScript
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut values = Vec::with_capacity(map.size_hint().unwrap_or(0) * 2);
+        while let Some((key, value)) = map.next_entry()? {
+            values.push(key);
+            values.push(value);
+        }
+        if let [Value::String(kind), _] = values.as_slice() {
+            if kind_type(kind).is_some() {
+                let (value, kind) = (values.pop(), values.pop());
+                if let (Some(Value::String(kind)), Some(value)) = (kind, value) {
+                    return Value::from_tagged(&kind, value).ok_or_else(|| de::Error::custom(format!("invalid {} value", kind)));
+                }
+            }
+        }
+        Ok(Value::Block(values))
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let (kind, variant): (String, _) = data.variant()?;
+        match kind.as_str() {
+            "paren" => return Ok(Value::Paren(variant.newtype_variant()?)),
+            "path" => return Ok(Value::Path(variant.newtype_variant()?)),
+            "set-path" => return Ok(Value::SetPath(variant.newtype_variant()?)),
+            "get-path" => return Ok(Value::GetPath(variant.newtype_variant()?)),
+            "lit-path" => return Ok(Value::LitPath(variant.newtype_variant()?)),
+            "map" => return Ok(Value::Map(variant.newtype_variant()?)),
+            _ => {}
+        }
+        if kind_type(&kind).is_none() {
+            return Err(de::Error::unknown_variant(&kind, &KIND_NAMES));
+        }
+        let text = variant.newtype_variant()?;
+        Ok(Value::from_tagged_text(&kind, text).expect("every other kind is text"))
+    }
+}
+
+/// A `path!`, e.g. `obj/field/1`. Paths holding other kinds of values can be
+/// read as `Value::Path`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path(pub Vec<PathElement>);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathElement {
+    Word(String),
+    Integer(i32),
+    Paren(Vec<Value>),
+}
+
+impl Serialize for Path {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_variant(adapters::VALUE, variant_index("path"), "path", &self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Path {
+    fn deserialize<D>(deserializer: D) -> Result<Path, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Value::deserialize(deserializer)? {
+            Value::Path(elements) => elements.into_iter().map(PathElement::from_value).collect::<Option<_>>()
+                .map(Path)
+                .ok_or_else(|| de::Error::custom("path elements must be words, integers or parens")),
+            _ => Err(de::Error::custom("expected a path")),
+        }
+    }
+}
+
+impl PathElement {
+    fn from_value(value: Value) -> Option<PathElement> {
+        match value {
+            Value::Word(name) => Some(PathElement::Word(name)),
+            Value::Integer(i) => Some(PathElement::Integer(i)),
+            Value::Paren(values) => Some(PathElement::Paren(values)),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for PathElement {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            PathElement::Word(name) => serializer.serialize_newtype_variant(adapters::VALUE, variant_index("word"), "word", name),
+            PathElement::Integer(i) => serializer.serialize_i32(*i),
+            PathElement::Paren(values) => serializer.serialize_newtype_variant(adapters::VALUE, variant_index("paren"), "paren", values),
+        }
+    }
+}
+
+const KIND_NAMES: [&str; 16] = ["file", "url", "tag", "email", "word", "set-word", "lit-word", "get-word",
+    "refinement", "issue", "paren", "path", "lit-path", "set-path", "get-path", "map"];
+
+/// Generates values nested up to 4 levels deep. Words and the like get short
+/// names made of letters and `-?!`, as loaded Red code would have.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Value {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        arbitrary_value(u, 4)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+fn arbitrary_value(u: &mut arbitrary::Unstructured<'_>, depth: usize) -> arbitrary::Result<Value> {
+    let kinds = if depth == 0 {17} else {24};
+    Ok(match u.int_in_range(0..=kinds - 1)? {
+        0 => Value::None,
+        1 => Value::Logic(u.arbitrary()?),
+        2 => Value::Integer(u.arbitrary()?),
+        3 => Value::Float(u.arbitrary()?),
+        4 => Value::Char(u.arbitrary()?),
+        5 => Value::String(u.arbitrary()?),
+        6 => Value::File(u.arbitrary()?),
+        7 => Value::Url(u.arbitrary()?),
+        8 => Value::Tag(u.arbitrary()?),
+        9 => Value::Email(u.arbitrary()?),
+        10 => Value::Binary(u.arbitrary()?),
+        11 => Value::Word(arbitrary_name(u)?),
+        12 => Value::SetWord(arbitrary_name(u)?),
+        13 => Value::LitWord(arbitrary_name(u)?),
+        14 => Value::GetWord(arbitrary_name(u)?),
+        15 => Value::Refinement(arbitrary_name(u)?),
+        16 => Value::Issue(arbitrary_name(u)?),
+        17 => Value::Block(arbitrary_values(u, depth - 1)?),
+        18 => Value::Paren(arbitrary_values(u, depth - 1)?),
+        19 => Value::Path(arbitrary_path(u)?),
+        20 => Value::SetPath(arbitrary_path(u)?),
+        21 => Value::GetPath(arbitrary_path(u)?),
+        22 => Value::LitPath(arbitrary_path(u)?),
+        _ => {
+            let len = u.int_in_range(0..=4)?;
+            Value::Map((0..len).map(|_| Ok((Value::SetWord(arbitrary_name(u)?), arbitrary_value(u, depth - 1)?)))
+                .collect::<arbitrary::Result<_>>()?)
+        }
+    })
+}
+
+/// A word followed by words and integers, e.g. `a/b/1`.
+#[cfg(feature = "arbitrary")]
+fn arbitrary_path(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Vec<Value>> {
+    let mut path = vec![Value::Word(arbitrary_name(u)?)];
+    for _ in 0..u.int_in_range(1..=3)? {
+        path.push(if u.arbitrary()? {Value::Word(arbitrary_name(u)?)} else {Value::Integer(u.arbitrary()?)});
+    }
+    Ok(path)
+}
+
+#[cfg(feature = "arbitrary")]
+fn arbitrary_values(u: &mut arbitrary::Unstructured<'_>, depth: usize) -> arbitrary::Result<Vec<Value>> {
+    let len = u.int_in_range(0..=8)?;
+    (0..len).map(|_| arbitrary_value(u, depth)).collect()
+}
+
+#[cfg(feature = "arbitrary")]
+fn arbitrary_name(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<String> {
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz-?!";
+    let len = u.int_in_range(1..=8)?;
+    (0..len).map(|_| Ok(char::from(*u.choose(CHARS)?))).collect()
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use super::{Path, PathElement, Value};
+    use crate::{from_bytes, parse_red, to_bytes};
+    use serde::de::value::{Error, MapDeserializer};
+    use serde::Deserialize;
+
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_value() {
+        // [a: 1 "x" :b #c 'd /e %f.txt (g 2.5) none true #"z" #{CAFE} [ż]]
+        let value = Value::Block(vec![
+            Value::SetWord("a".to_string()),
+            Value::Integer(1),
+            Value::String("x".to_string()),
+            Value::GetWord("b".to_string()),
+            Value::Issue("c".to_string()),
+            Value::LitWord("d".to_string()),
+            Value::Refinement("e".to_string()),
+            Value::File("f.txt".to_string()),
+            Value::Paren(vec![Value::Word("g".to_string()), Value::Float(2.5)]),
+            Value::None,
+            Value::Logic(true),
+            Value::Char('z'),
+            Value::Binary(vec![0xCA, 0xFE]),
+            Value::Block(vec![Value::String("ż".to_string())]),
+            Value::Path(vec![Value::Word("obj".to_string()), Value::String("field".to_string())]),
+            // obj/a: :obj/b 'obj/1
+            Value::SetPath(vec![Value::Word("obj".to_string()), Value::Word("a".to_string())]),
+            Value::GetPath(vec![Value::Word("obj".to_string()), Value::Word("b".to_string())]),
+            Value::LitPath(vec![Value::Word("obj".to_string()), Value::Integer(1)]),
+        ]);
+        let bytes = to_bytes(&value).unwrap();
+        assert_eq!(value, from_bytes::<Value>(&bytes).unwrap());
+
+        // records written by typed fields read as values
+        let bytes = to_bytes(&("a", "b")).unwrap();
+        assert_eq!(Value::Block(vec![Value::String("a".to_string()), Value::String("b".to_string())]),
+            from_bytes::<Value>(&bytes).unwrap());
+
+        // formats without enums tag values with single entry maps
+        let tagged = MapDeserializer::<_, Error>::new(vec![("set-word", "a")].into_iter());
+        assert_eq!(Value::SetWord("a".to_string()), Value::deserialize(tagged).unwrap());
+        let map = MapDeserializer::<_, Error>::new(vec![("a", "b")].into_iter());
+        assert_eq!(Value::Block(vec![Value::String("a".to_string()), Value::String("b".to_string())]),
+            Value::deserialize(map).unwrap());
+    }
+
+    #[test]
+    fn test_accessors() {
+        let value = parse_red(r#"[a: 1 b: [2.5 "x" #{CAFE}] "c" #[true] d: #[none] #"z"]"#).unwrap();
+        assert_eq!(Some(1), value.get("a").and_then(Value::as_i32));
+        assert_eq!(Some(2.5), value.get("b").and_then(|b| b.get_index(0)).and_then(Value::as_f64));
+        assert_eq!(Some("x"), value.get("b").and_then(|b| b.get_index(1)).and_then(Value::as_str));
+        assert_eq!(Some(&[0xCA, 0xFE][..]), value.get("b").and_then(|b| b.get_index(2)).and_then(Value::as_bytes));
+        assert_eq!(Some(true), value.get("c").and_then(Value::as_bool));
+        assert!(value.get("d").is_some_and(Value::is_none));
+        assert_eq!(Some('z'), value.get_index(8).and_then(Value::as_char));
+        assert_eq!(Some(1.0), value.get_index(1).and_then(Value::as_f64));
+        assert_eq!(Some("a"), value.get_index(0).and_then(Value::as_str));
+        assert_eq!(None, value.get("e"));
+        assert_eq!(None, value.get_index(9));
+        assert_eq!(None, value.get_index(1).and_then(Value::as_block));
+
+        let mut value = value;
+        value.as_block_mut().unwrap().truncate(2);
+        assert_eq!(2, value.as_block().unwrap().len());
+    }
+
+    #[test]
+    fn test_primitives() {
+        let value = Value::from(vec![Value::from(1_u8), Value::from("x"), Value::from(2.5_f32),
+            Value::from(Some(true)), Value::from(None::<i32>), Value::from(vec![0xCA_u8])]);
+        assert_eq!(parse_red(r#"[1 "x" 2.5 #[true] #[none] #{CA}]"#).unwrap(), value);
+        let values = value.as_block().unwrap();
+        assert_eq!(values[0], 1);
+        assert_eq!(1, values[0]);
+        assert_eq!(values[1], "x");
+        assert_eq!("x", values[1]);
+        assert_eq!(values[1], *"x");
+        assert_eq!(values[1], "x".to_string());
+        assert_eq!(values[2], 2.5);
+        assert_eq!(values[3], true);
+        assert_eq!(values[5], [0xCA][..]);
+        assert_ne!(values[0], 1.0);
+        assert_ne!(Value::Word("x".to_string()), "x");
+        assert_eq!(Value::None, Value::from(()));
+    }
+
+    #[test]
+    fn test_sizes() {
+        let values = [
+            parse_red(r#"[a: 1 b: [2.5 "żółw" #{0102} %file 'a a/b/c #"x" #[true]] (c) #[none]]"#).unwrap(),
+            parse_red("[]").unwrap(),
+            Value::String("abcde".to_string()),
+            Value::Word("x".to_string()),
+        ];
+        for value in &values {
+            assert_eq!(to_bytes(value).unwrap().len(), value.encoded_size());
+        }
+        assert_eq!(18, values[0].deep_len());
+        assert_eq!((0, 0), (values[1].deep_len(), values[2].deep_len()));
+    }
+
+    #[test]
+    fn test_map() {
+        use std::collections::BTreeMap;
+
+        let value = parse_red(r#"#(z: 1 "b" [2] a: #(d: #[none]))"#).unwrap();
+        let keys: Vec<String> = value.as_map().unwrap().keys().map(|key| key.to_string()).collect();
+        assert_eq!(vec!["z:", "\"b\"", "a:"], keys);
+        assert_eq!(r#"#(z: 1 "b" [2] a: #(d: #[none]))"#, value.to_string());
+
+        let bytes = to_bytes(&value).unwrap();
+        assert_eq!(0x28, bytes[16 + 8 + 4 * 3 + 8]);
+        assert_eq!(value, from_bytes::<Value>(&bytes).unwrap());
+        assert_eq!((bytes.len(), 9), (value.encoded_size(), value.deep_len()));
+        assert_eq!(Some(&Value::Integer(1)), value.get("z"));
+
+        let numbers = to_bytes(&parse_red("#(b: 1 a: 2)").unwrap()).unwrap();
+        let map: BTreeMap<String, i32> = from_bytes(&numbers).unwrap();
+        assert_eq!(Some(&2), map.get("a"));
+        assert!(parse_red("#(a: 1 b:)").is_err());
+    }
+
+    #[test]
+    fn test_ordering() {
+        use std::collections::{BTreeMap, HashSet};
+
+        let mut values = parse_red(r#"[b "b" 2.5 1 "a" #[none] a [1 2] [1] -0.0 0.0]"#).unwrap();
+        values.as_block_mut().unwrap().push(Value::Float(f64::NAN));
+        values.as_block_mut().unwrap().sort();
+        let mut sorted = parse_red(r#"[#[none] 1 -0.0 0.0 2.5 "a" "b" a b [1] [1 2]]"#).unwrap();
+        sorted.as_block_mut().unwrap().insert(5, Value::Float(f64::NAN));
+        assert_eq!(sorted, values);
+        assert_eq!(Value::Float(f64::NAN), Value::Float(-f64::NAN));
+        assert_eq!(Value::Float(0.0), Value::Float(-0.0));
+
+        let set: HashSet<Value> = values.as_block().unwrap().iter().cloned().collect();
+        assert_eq!(11, set.len());
+        let mut counts = BTreeMap::new();
+        for key in ["x", "y", "x"] {
+            *counts.entry(Value::Word(key.to_string())).or_insert(0) += 1;
+        }
+        assert_eq!(Some(&2), counts.get(&Value::Word("x".to_string())));
+    }
+
+    #[test]
+    fn test_word_case() {
+        use std::collections::HashSet;
+
+        let value = parse_red("[Foo: 1 fOO \"FOO\" #ABC]").unwrap();
+        let values = value.as_block().unwrap();
+        assert_eq!(Value::SetWord("foo".to_string()), values[0]);
+        assert_eq!(Value::Word("FOO".to_string()), values[2]);
+        assert_ne!(Value::String("foo".to_string()), values[3]);
+        assert_eq!(Value::Issue("abc".to_string()), values[4]);
+        assert_eq!(Some(&Value::Integer(1)), value.get("FOO"));
+        let set: HashSet<Value> = ["Foo", "foo", "fOO"].iter().map(|w| Value::Word(w.to_string())).collect();
+        assert_eq!(1, set.len());
+
+        // spelling kept through the symbol table
+        let bytes = to_bytes(&value).unwrap();
+        assert_eq!(value.to_string(), from_bytes::<Value>(&bytes).unwrap().to_string());
+        assert_eq!("[Foo: 1 fOO \"FOO\" #ABC]", value.to_string());
+    }
+
+    #[test]
+    fn test_path() {
+        use PathElement::*;
+
+        // obj/field/1/(2 + 1)
+        let path = Path(vec![Word("obj".to_string()), Word("field".to_string()), Integer(1),
+            Paren(vec![Value::Integer(2), Value::Word("+".to_string()), Value::Integer(1)])]);
+        let bytes = to_bytes(&path).unwrap();
+        let payload = &bytes[bytes.len() - bytes[12] as usize..];
+        assert_eq!(&payload[..12], &[0x19, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00]);
+        assert_eq!(path, from_bytes::<Path>(&bytes).unwrap());
+
+        let bytes = to_bytes(&Value::Block(vec![])).unwrap();
+        assert!(from_bytes::<Path>(&bytes).is_err());
+        let bytes = to_bytes(&Value::Path(vec![Value::String("x".to_string())])).unwrap();
+        assert!(from_bytes::<Path>(&bytes).is_err());
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    #[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+    fn test_arbitrary() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let mut seed = 1_u32;
+        for _ in 0..200 {
+            let data: Vec<u8> = (0..512).map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (seed >> 16) as u8
+            }).collect();
+            let value = Value::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            // compared encoded, as NaN floats don't equal themselves
+            let bytes = to_bytes(&value).unwrap();
+            let decoded: Value = from_bytes(&bytes).unwrap();
+            assert_eq!(bytes, to_bytes(&decoded).unwrap(), "{:?}", value);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Documents tagged with the version of the application that saved them,
+//! with migrations bringing older ones up to date.
+//!
+//! A versioned document is a block of the version and the value, e.g.
+//! `[3 [name: "x"]]`, as Red's `save/as %file reduce [3 data] 'redbin` writes.
+//!
+//! ```
+//! # use redbin::versioned::{to_bytes_versioned, Migrator};
+//! # use redbin::Value;
+//! # use serde_derive::{Deserialize, Serialize};
+//! # #[derive(Deserialize, PartialEq, Debug)]
+//! # struct Config { name: String, tags: Vec<String> }
+//! # #[derive(Serialize)]
+//! # struct OldConfig { nick: String }
+//! # fn rename_key(mut value: Value, from: &str, to: &str) -> Value {
+//! #     for key in value.as_block_mut().unwrap().iter_mut().step_by(2) {
+//! #         if key.as_str() == Some(from) {
+//! #             *key = Value::SetWord(to.to_string());
+//! #         }
+//! #     }
+//! #     value
+//! # }
+//! # fn add_key(mut value: Value, key: &str, default: Value) -> Value {
+//! #     value.as_block_mut().unwrap().extend(vec![Value::SetWord(key.to_string()), default]);
+//! #     value
+//! # }
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let saved = to_bytes_versioned(0, &OldConfig { nick: "x".to_string() })?;
+//! let migrator = Migrator::new(2)
+//!     .step(0, |v| Ok(rename_key(v, "nick", "name")))
+//!     .step(1, |v| Ok(add_key(v, "tags", Value::Block(vec![]))));
+//! let config: Config = migrator.from_bytes(&saved)?;
+//! # assert_eq!(Config { name: "x".to_string(), tags: vec![] }, config);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{text_of, DeError, SerError};
+use crate::value::Value;
+use crate::{from_bytes, to_bytes};
+use serde::de::{Deserialize, DeserializeOwned, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A value with the version of its layout, written as `[version value]`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Versioned<T> {
+    pub version: u32,
+    pub value: T,
+}
+
+impl<T> Serialize for Versioned<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (self.version, &self.value).serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Versioned<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (version, value) = Deserialize::deserialize(deserializer)?;
+        Ok(Versioned { version, value })
+    }
+}
+
+/// Writes `value` as a document of the given layout `version`.
+pub fn to_bytes_versioned<T>(version: u32, value: &T) -> Result<Vec<u8>, SerError>
+where
+    T: Serialize,
+{
+    to_bytes(&Versioned { version, value })
+}
+
+type Step = Box<dyn Fn(Value) -> Result<Value, DeError> + Send + Sync>;
+
+/// Migrations of versioned documents, each from one version to the next.
+pub struct Migrator {
+    current: u32,
+    steps: BTreeMap<u32, Step>,
+}
+
+impl fmt::Debug for Migrator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Migrator")
+            .field("current", &self.current)
+            .field("steps", &self.steps.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Migrator {
+    /// Migrator to the `current` version, written by this application.
+    pub fn new(current: u32) -> Self {
+        Migrator { current, steps: BTreeMap::new() }
+    }
+
+    /// Adds the migration of values from version `from` to `from + 1`.
+    pub fn step<F>(mut self, from: u32, migrate: F) -> Self
+    where
+        F: Fn(Value) -> Result<Value, DeError> + Send + Sync + 'static,
+    {
+        self.steps.insert(from, Box::new(migrate));
+        self
+    }
+
+    /// Brings a value of `version` up to the current version.
+    pub fn migrate(&self, version: u32, mut value: Value) -> Result<Value, DeError> {
+        if version > self.current {
+            return Err(DeError::Message(message!("document version {} is newer than {}", version, self.current)));
+        }
+        for from in version..self.current {
+            let step = self.steps.get(&from)
+                .ok_or_else(|| DeError::Message(message!("no migration from version {}", from)))?;
+            value = step(value)?;
+        }
+        Ok(value)
+    }
+
+    /// Reads a versioned document, migrating it to the current version first
+    /// if it's older.
+    pub fn from_bytes<T>(&self, bytes: &[u8]) -> Result<T, DeError>
+    where
+        T: DeserializeOwned,
+    {
+        let document: Versioned<Value> = from_bytes(bytes)?;
+        if document.version == self.current {
+            let document: Versioned<T> = from_bytes(bytes)?;
+            return Ok(document.value);
+        }
+        let value = self.migrate(document.version, document.value)?;
+        from_bytes(&to_bytes(&value).map_err(|e| DeError::Message(text_of(e, "value can't be written")))?)
+    }
+
+    /// Writes `value` as a document of the current version.
+    pub fn to_bytes<T>(&self, value: &T) -> Result<Vec<u8>, SerError>
+    where
+        T: Serialize,
+    {
+        to_bytes_versioned(self.current, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_bytes_versioned, Migrator, Versioned};
+    use crate::error::DeError;
+    use crate::value::Value;
+    use crate::{from_bytes, parse_red};
+    use serde_derive::Deserialize;
+
+    #[test]
+    fn test_versioned() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Config {
+            name: String,
+            retries: i32,
+        }
+
+        // version 0 had `nick`, version 1 renamed it to `name`, version 2
+        // added `retries`
+        let migrator = Migrator::new(2)
+            .step(0, |mut value| {
+                if let Some(Value::SetWord(key)) = value.as_block_mut().and_then(|v| v.first_mut()) {
+                    *key = "name".to_string();
+                }
+                Ok(value)
+            })
+            .step(1, |mut value| {
+                if let Some(values) = value.as_block_mut() {
+                    values.extend(vec![Value::SetWord("retries".to_string()), Value::Integer(3)]);
+                }
+                Ok(value)
+            });
+        let expected = Config { name: "x".to_string(), retries: 3 };
+
+        let v0 = to_bytes_versioned(0, &parse_red("[nick: \"x\"]").unwrap()).unwrap();
+        assert_eq!(expected, migrator.from_bytes(&v0).unwrap());
+        let v1 = to_bytes_versioned(1, &parse_red("[name: \"x\"]").unwrap()).unwrap();
+        assert_eq!(expected, migrator.from_bytes(&v1).unwrap());
+        let v2 = migrator.to_bytes(&parse_red("[name: \"x\" retries: 3]").unwrap()).unwrap();
+        assert_eq!(expected, migrator.from_bytes(&v2).unwrap());
+        assert_eq!(2, from_bytes::<Versioned<Value>>(&v2).unwrap().version);
+
+        let v3 = to_bytes_versioned(3, &Value::None).unwrap();
+        assert_eq!(DeError::Message(message!("document version {} is newer than {}", 3, 2)),
+            migrator.from_bytes::<Config>(&v3).unwrap_err());
+        assert!(Migrator::new(1).from_bytes::<Config>(&v0).is_err());
+    }
+}
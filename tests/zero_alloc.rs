@@ -0,0 +1,183 @@
+#![cfg(all(feature = "ser", feature = "de"))]
+
+//! Checks that borrowed types deserialize, and arena and fixed-buffer
+//! serialization run, without touching the global heap. Lives in its own test binary because it
+//! swaps the global allocator.
+
+use redbin::{from_bytes, to_bytes};
+use serde_derive::{Deserialize, Serialize};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+fn allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let value = f();
+    (value, ALLOCATIONS.with(Cell::get) - before)
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Record<'a> {
+    name: &'a str,
+    #[serde(with = "serde_bytes")]
+    payload: &'a [u8],
+    id: i32,
+    ratio: f64,
+    flags: [bool; 3],
+    span: (u16, i8),
+    #[serde(borrow)]
+    tags: (&'a str, &'a str),
+}
+
+#[test]
+fn test_borrowed_without_allocations() {
+    let record = Record {
+        name: "sensor",
+        payload: &[0xCA, 0xFE],
+        id: -7,
+        ratio: 0.25,
+        flags: [true, false, true],
+        span: (300, -1),
+        tags: ("a", "b"),
+    };
+    let bytes = to_bytes(&record).unwrap();
+
+    let (decoded, n) = allocations(|| from_bytes::<Record>(&bytes));
+    assert_eq!(0, n);
+    assert_eq!(record, decoded.unwrap());
+}
+
+#[test]
+#[cfg_attr(feature = "ascii-only", ignore = "reads wide strings")]
+fn test_wide_strings_reuse_scratch() {
+    use redbin::Deserializer;
+    use serde::de::{Deserialize, Visitor};
+    use std::fmt;
+
+    // number of characters of a string, looked at without keeping it
+    #[derive(PartialEq, Debug)]
+    struct Chars(usize);
+
+    impl<'de> Deserialize<'de> for Chars {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct CharsVisitor;
+            impl<'de> Visitor<'de> for CharsVisitor {
+                type Value = Chars;
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a string")
+                }
+                fn visit_str<E>(self, text: &str) -> Result<Chars, E> {
+                    Ok(Chars(text.chars().count()))
+                }
+            }
+            deserializer.deserialize_str(CharsVisitor)
+        }
+    }
+
+    let bytes = to_bytes(&("zażółć gęślą jaźń", "💖 a 💖", "ascii")).unwrap();
+    let mut de = Deserializer::from_bytes(&bytes);
+    de.read_header().unwrap();
+    let first = <(Chars, Chars, Chars)>::deserialize(&mut de).unwrap();
+    assert_eq!((Chars(17), Chars(5), Chars(5)), first);
+
+    de.reset(&bytes);
+    de.read_header().unwrap();
+    let (again, n) = allocations(|| <(Chars, Chars, Chars)>::deserialize(&mut de));
+    assert_eq!(0, n);
+    assert_eq!(first, again.unwrap());
+}
+
+
+#[cfg(feature = "bumpalo")]
+#[test]
+fn test_arena_serialization() {
+    use redbin::{adapters::SetWordKeys, to_bytes_in, SerializerOptions};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Document<'a> {
+        #[serde(borrow)]
+        record: Record<'a>,
+        title: &'a str,
+        note: Option<&'a str>,
+        #[serde(with = "SetWordKeys")]
+        meta: &'a BTreeMap<&'a str, i32>,
+    }
+
+    let meta = BTreeMap::from([("width", 640), ("height", 480)]);
+    let document = Document {
+        record: Record {
+            name: "sensor",
+            payload: &[0xCA, 0xFE],
+            id: -7,
+            ratio: 0.25,
+            flags: [true, false, true],
+            span: (300, -1),
+            tags: ("a", "b"),
+        },
+        title: "zażółć 💖",
+        note: None,
+        meta: &meta,
+    };
+    let options = SerializerOptions::new().skip_none_fields(true);
+    let expected = redbin::to_bytes_with(&document, options.clone()).unwrap();
+
+    let bump = bumpalo::Bump::with_capacity(64 * 1024);
+    let (bytes, n) = allocations(|| to_bytes_in(&document, &bump, options).unwrap());
+    assert_eq!(0, n);
+    assert_eq!(&expected[..], &bytes[..]);
+}
+
+#[test]
+fn test_fixed_serialization() {
+    use redbin::{adapters::Word, to_slice_fixed, SerError, SerializerOptions};
+
+    let record = Record {
+        name: "sensor",
+        payload: &[0xCA, 0xFE],
+        id: -7,
+        ratio: 0.25,
+        flags: [true, false, true],
+        span: (300, -1),
+        tags: ("a", "b"),
+    };
+    let value = (Word("telemetry".to_string()), record);
+    let expected = to_bytes(&value).unwrap();
+
+    let mut region = [0x00; 512];
+    let mut symbols = [0x00; 64];
+    let (size, n) = allocations(|| to_slice_fixed(&value, &mut region, &mut symbols, SerializerOptions::new()));
+    assert_eq!(0, n);
+    assert_eq!(&expected[..], &region[..size.unwrap()]);
+
+    let (result, n) = allocations(|| to_slice_fixed(&value, &mut region[..100], &mut symbols, SerializerOptions::new()));
+    assert_eq!(0, n);
+    assert_eq!(Err(SerError::OutputTooSmall { needed: expected.len() }), result);
+    assert!(to_slice_fixed(&value, &mut region, &mut symbols[..8], SerializerOptions::new()).is_err());
+
+    #[cfg(feature = "heapless")]
+    {
+        let (document, n) = allocations(|| redbin::to_heapless::<_, 512>(&value, &mut symbols, SerializerOptions::new()));
+        assert_eq!(0, n);
+        assert_eq!(&expected[..], &document.unwrap()[..]);
+    }
+}
@@ -0,0 +1,24 @@
+//! JavaScript functions, for web front-ends reading data saved by Red.
+//!
+//! Values are seen as `Value`s: blocks are arrays, binaries are
+//! `Uint8Array`s, `none` is `null`, and words, paths and other tagged kinds
+//! are single entry objects, e.g. `{"set-word": "a"}`. Other objects are
+//! encoded as blocks of keys and values.
+
+use redbin::{from_bytes, to_bytes, Value};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// Decodes a Redbin document into a JavaScript value.
+#[wasm_bindgen(js_name = decode)]
+pub fn decode(bytes: &[u8]) -> Result<JsValue, JsError> {
+    let value: Value = from_bytes(bytes)?;
+    Ok(value.serialize(&serde_wasm_bindgen::Serializer::json_compatible())?)
+}
+
+/// Encodes a JavaScript value into a Redbin document.
+#[wasm_bindgen(js_name = encode)]
+pub fn encode(value: JsValue) -> Result<Vec<u8>, JsError> {
+    let value: Value = serde_wasm_bindgen::from_value(value)?;
+    Ok(to_bytes(&value)?)
+}